@@ -7,3 +7,15 @@ fn main() {
     let n = <BigEndian as ByteOrder>::read_u32(buf);
     assert_eq!(n, 0x01020304);
 }
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ByteOrder};
+
+    #[test]
+    fn little_endian() {
+        let buf = &[1,2,3,4];
+        let n = <LittleEndian as ByteOrder>::read_u32(buf);
+        assert_eq!(n, 0x04030201);
+    }
+}
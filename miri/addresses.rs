@@ -0,0 +1,71 @@
+//! Integer addresses for allocations. Pointers are an allocation and an offset into it, and
+//! usually that is all miri needs. Comparing pointers into different allocations needs an order
+//! of the allocations, though, and code that treats pointers as plain numbers (hashing them,
+//! tagging their low bits) needs an actual address, with `-Zmiri-virtual-addresses`. Every
+//! allocation is given a fixed address the first time one is needed.
+
+use std::collections::HashMap;
+
+use super::*;
+
+/// The address of the first allocation, so that small integers are never addresses
+const FIRST_ADDRESS: u64 = 0x1_0000;
+
+pub struct Addresses {
+    /// The address and size of every allocation that was given one. Freed allocations keep
+    /// theirs, so dangling pointers keep their address and their order.
+    bases: HashMap<u64, (u64, u64)>,
+    /// The lowest address the next allocation may get, addresses are never reused
+    next: u64,
+}
+
+impl Default for Addresses {
+    fn default() -> Self {
+        Addresses {
+            bases: HashMap::new(),
+            next: FIRST_ADDRESS,
+        }
+    }
+}
+
+/// The size and alignment of the allocation `alloc_id`. Functions get an address too.
+fn size_and_align<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, alloc_id: AllocId) -> (u64, u64) {
+    match ecx.memory.get(alloc_id) {
+        Ok(alloc) => (alloc.bytes.len() as u64, alloc.align),
+        Err(_) => (1, 1),
+    }
+}
+
+/// The address of the first byte of the allocation `alloc_id` and its size
+fn base_and_size<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, alloc_id: AllocId) -> (u64, u64) {
+    if let Some(&base_and_size) = ecx.memory.data.addresses.borrow().bases.get(&alloc_id.0) {
+        return base_and_size;
+    }
+    let (size, align) = size_and_align(ecx, alloc_id);
+    let mut addresses = ecx.memory.data.addresses.borrow_mut();
+    let base = (addresses.next + align - 1) / align * align;
+    // Leave a gap, so that a pointer one past the end of an allocation does not have the same
+    // address as the start of the next one
+    addresses.next = base + size + 1;
+    addresses.bases.insert(alloc_id.0, (base, size));
+    (base, size)
+}
+
+/// The address `ptr` points to
+pub(crate) fn address<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, ptr: MemoryPointer) -> u64 {
+    base_and_size(ecx, ptr.alloc_id).0 + ptr.offset
+}
+
+/// A pointer to `address` in the allocation `ptr` points to, if the address is in its bounds
+pub(crate) fn pointer_to<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    ptr: MemoryPointer,
+    address: u64,
+) -> Option<MemoryPointer> {
+    let (base, size) = base_and_size(ecx, ptr.alloc_id);
+    if base <= address && address <= base + size {
+        Some(MemoryPointer::new(ptr.alloc_id, address - base))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,107 @@
+//! Statistics about the allocations of the program (`-Zmiri-alloc-stats`), printed when it ends:
+//! the peak of the memory in use, how many allocations of which size were made, and the
+//! statements that allocated the most. They explain why a program hits the memory limit. The
+//! counts and the peaks are also what `-Zmiri-summary` prints, they are only counted here.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rustc::ty::TyCtxt;
+use syntax::codemap::Span;
+
+use super::*;
+use rustc_compat;
+
+/// Number of statements with the most allocations to report
+const TOP_SPANS: usize = 10;
+
+#[derive(Default)]
+pub struct AllocStats {
+    /// Bytes of all allocations not freed yet, stack and heap
+    live_bytes: u64,
+    /// The maximum of `live_bytes`
+    peak_bytes: u64,
+    /// Number of allocations ever made
+    pub(crate) allocations: u64,
+    /// Number of allocations freed
+    pub(crate) deallocations: u64,
+    /// Bytes allocated on the heap (by the program's allocator or `malloc`) and not freed yet
+    heap_bytes: u64,
+    /// The maximum of `heap_bytes`
+    pub(crate) peak_heap_bytes: u64,
+    /// Bytes ever allocated
+    total_bytes: u64,
+    /// Number of allocations by size, bucket `i` holds the sizes from `2^(i-1)` to `2^i - 1`
+    /// (bucket 0 the empty allocations)
+    histogram: BTreeMap<usize, u64>,
+    /// Number of allocations and their bytes by the statement that made them
+    per_span: HashMap<Span, (u64, u64)>,
+}
+
+impl AllocStats {
+    pub fn report<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>) {
+        eprintln!(
+            "allocation statistics: {} allocations of {} bytes in total, peak memory usage {} bytes",
+            self.allocations,
+            self.total_bytes,
+            self.peak_bytes,
+        );
+        eprintln!("allocations by size:");
+        for (&bucket, &count) in &self.histogram {
+            let sizes = match bucket {
+                0 => "0".to_owned(),
+                1 => "1".to_owned(),
+                _ => format!("{}..{}", 1u128 << (bucket - 1), (1u128 << bucket) - 1),
+            };
+            eprintln!("{:>24} {:>12}", sizes, count);
+        }
+        let mut spans: Vec<_> = self.per_span.iter().collect();
+        // Most allocations first
+        spans.sort_by_key(|&(_, &(count, bytes))| ::std::cmp::Reverse((count, bytes)));
+        eprintln!("statements with the most allocations (allocations, bytes):");
+        for (span, &(count, bytes)) in spans.into_iter().take(TOP_SPANS) {
+            eprintln!("{:>12} {:>12}  {}", count, bytes, rustc_compat::span_to_string(tcx, *span));
+        }
+    }
+}
+
+/// Called when an allocation of `size` bytes is made
+pub(crate) fn allocated(data: &MemoryData, size: u64) {
+    if let Some(ref stats) = data.alloc_stats {
+        let mut stats = stats.borrow_mut();
+        stats.allocations += 1;
+        stats.total_bytes += size;
+        stats.live_bytes += size;
+        stats.peak_bytes = stats.peak_bytes.max(stats.live_bytes);
+        let bucket = 64 - size.leading_zeros() as usize;
+        *stats.histogram.entry(bucket).or_insert(0) += 1;
+        if let Some(span) = data.current_span.get() {
+            let entry = stats.per_span.entry(span).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += size;
+        }
+    }
+}
+
+/// Called when an allocation of `size` bytes is freed
+pub(crate) fn freed(data: &MemoryData, size: u64) {
+    if let Some(ref stats) = data.alloc_stats {
+        let mut stats = stats.borrow_mut();
+        stats.deallocations += 1;
+        stats.live_bytes = stats.live_bytes.saturating_sub(size);
+    }
+}
+
+/// Called when the heap grew (or shrank, for negative `delta`) by `delta` bytes
+pub(crate) fn heap_changed(data: &MemoryData, delta: i64) {
+    if let Some(ref stats) = data.alloc_stats {
+        let mut stats = stats.borrow_mut();
+        stats.heap_bytes = if delta >= 0 {
+            stats.heap_bytes.saturating_add(delta as u64)
+        } else {
+            let freed = delta.wrapping_neg() as u64;
+            debug_assert!(freed <= stats.heap_bytes, "freed more heap memory than was allocated");
+            stats.heap_bytes.saturating_sub(freed)
+        };
+        stats.peak_heap_bytes = stats.peak_heap_bytes.max(stats.heap_bytes);
+    }
+}
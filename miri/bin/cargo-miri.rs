@@ -1,14 +1,21 @@
 extern crate cargo_metadata;
+extern crate miri;
 
 use std::path::{PathBuf, Path};
 use std::io::Write;
 use std::process::Command;
 
 
-const CARGO_MIRI_HELP: &str = r#"Interprets bin crates
+const CARGO_MIRI_HELP: &str = r#"Interprets bin crates and test suites
 
 Usage:
     cargo miri [options] [--] [<opts>...]
+    cargo miri test [options] [--] [<opts>...]
+    cargo miri setup
+
+`cargo miri` runs the bin targets of the package, `cargo miri test` its unit tests (of the lib
+and bin targets) and its integration tests. Dependencies are built normally, with full MIR.
+Arguments after `--` are passed to miri, e.g. `-Zmiri-seed=42`.
 
 Common options:
     -h, --help               Print this message
@@ -17,6 +24,11 @@ Common options:
 
 Other options are the same as `cargo rustc`.
 
+`cargo miri setup` builds a libstd with full MIR (with xargo) into the cache directory
+(`$MIRI_CACHE_DIR`, by default `~/.cache/miri`), which is used from then on. Run it again after
+updating the toolchain. `MIRI_SYSROOT` overrides the sysroot, the dependencies are built against
+the same sysroot.
+
 The feature `cargo-miri` is automatically defined for convenience. You can use
 it to configure the resource limits
 
@@ -44,7 +56,11 @@ fn main() {
         return;
     }
 
-    if let Some("miri") = std::env::args().nth(1).as_ref().map(AsRef::as_ref) {
+    if std::env::args().nth(1).map_or(false, |arg| arg == "miri") &&
+        std::env::args().nth(2).map_or(false, |arg| arg == "setup")
+    {
+        setup();
+    } else if let Some("miri") = std::env::args().nth(1).as_ref().map(AsRef::as_ref) {
         // this arm is when `cargo miri` is called
 
         let test = std::env::args().nth(2).map_or(false, |text| text == "test");
@@ -96,24 +112,25 @@ fn main() {
             let kind = target.kind.get(0).expect(
                 "badly formatted cargo metadata: target::kind is an empty array",
             );
-            if test && kind == "test" {
-                if let Err(code) = process(
-                    vec!["--test".to_string(), target.name].into_iter().chain(
-                        args,
-                    ),
-                )
-                {
-                    std::process::exit(code);
-                }
-            } else if !test && kind == "bin" {
-                if let Err(code) = process(
-                    vec!["--bin".to_string(), target.name].into_iter().chain(
-                        args,
-                    ),
-                )
-                {
-                    std::process::exit(code);
-                }
+            let result = match (test, kind.as_str()) {
+                (true, "test") => process(
+                    vec!["--test".to_string(), target.name].into_iter().chain(args),
+                    &[],
+                ),
+                // Unit tests are the lib or bin target built as a test harness
+                (true, "lib") => process(vec!["--lib".to_string()].into_iter().chain(args), &["--test"]),
+                (true, "bin") => process(
+                    vec!["--bin".to_string(), target.name].into_iter().chain(args),
+                    &["--test"],
+                ),
+                (false, "bin") => process(
+                    vec!["--bin".to_string(), target.name].into_iter().chain(args),
+                    &[],
+                ),
+                _ => Ok(()),
+            };
+            if let Err(code) = result {
+                std::process::exit(code);
             }
         }
     } else {
@@ -121,7 +138,10 @@ fn main() {
 
         let home = option_env!("RUSTUP_HOME").or(option_env!("MULTIRUST_HOME"));
         let toolchain = option_env!("RUSTUP_TOOLCHAIN").or(option_env!("MULTIRUST_TOOLCHAIN"));
-        let sys_root = if let (Some(home), Some(toolchain)) = (home, toolchain) {
+        let sys_root = if let Some(sysroot) = miri::fullmir_sysroot() {
+            // A libstd with full MIR, the dependencies have to be built against it as well
+            sysroot.to_str().expect("the miri sysroot is not valid unicode").to_owned()
+        } else if let (Some(home), Some(toolchain)) = (home, toolchain) {
             format!("{}/toolchains/{}", home, toolchain)
         } else {
             option_env!("RUST_SYSROOT")
@@ -177,7 +197,61 @@ fn main() {
     }
 }
 
-fn process<I>(old_args: I) -> Result<(), i32>
+const SETUP_CARGO_TOML: &str = r#"[package]
+name = "miri-xargo"
+version = "0.0.0"
+
+[lib]
+path = "lib.rs"
+"#;
+
+const SETUP_XARGO_TOML: &str = r#"[dependencies]
+std = {features = ["panic_unwind", "jemalloc", "backtrace"]}
+"#;
+
+/// Builds (or rebuilds, if the toolchain changed) a libstd with full MIR in the cache directory
+fn setup() {
+    let dir = miri::cache_dir().unwrap_or_else(|| {
+        eprintln!("error: could not find a cache directory, set MIRI_CACHE_DIR");
+        std::process::exit(1);
+    });
+    let write = |name: &str, contents: &str| {
+        std::fs::create_dir_all(&dir)
+            .and_then(|()| std::fs::File::create(dir.join(name)))
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .unwrap_or_else(|e| {
+                eprintln!("error: could not write {}: {}", dir.join(name).display(), e);
+                std::process::exit(1);
+            });
+    };
+    write("Cargo.toml", SETUP_CARGO_TOML);
+    write("Xargo.toml", SETUP_XARGO_TOML);
+    write("lib.rs", "");
+
+    let status = Command::new("xargo")
+        .arg("build")
+        .current_dir(&dir)
+        .env("RUSTFLAGS", "-Zalways-encode-mir -Zmir-emit-validate=1")
+        .env("XARGO_HOME", &dir)
+        .status();
+    match status {
+        Ok(status) if status.success() => {
+            eprintln!("A libstd with full MIR is now in {}", dir.join("HOST").display());
+        }
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!(
+                "error: could not run xargo ({}), install it with `cargo install xargo` and \
+                 `rustup component add rust-src`",
+                e,
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs `cargo rustc` with `old_args`, `rustc_args` are passed to the compilation of the final crate
+fn process<I>(old_args: I, rustc_args: &[&str]) -> Result<(), i32>
 where
     I: Iterator<Item = String>,
 {
@@ -192,6 +266,7 @@ where
         args.push("--".to_owned());
     }
     args.push("-Zno-trans".to_owned());
+    args.extend(rustc_args.iter().map(|arg| arg.to_string()));
     args.push("--cfg".to_owned());
     args.push(r#"feature="cargo-miri""#.to_owned());
 
@@ -16,12 +16,35 @@ use rustc_driver::{Compilation, CompilerCalls, RustcDefaultCalls};
 use rustc_driver::driver::{CompileState, CompileController};
 use rustc::session::config::{self, Input, ErrorOutputType};
 use rustc::hir::{self, itemlikevisit};
+use rustc::hir::def_id::DefId;
 use rustc::ty::TyCtxt;
+use miri::ResourceLimitsExt;
 use syntax::ast::{self, MetaItemKind, NestedMetaItemKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::io::Write;
 
 struct MiriCompilerCalls {
     default: RustcDefaultCalls,
+    /// Settings for the interpreted program, taken from the `-Zmiri-*` flags and everything after `--`
+    config: miri::MiriConfig,
+    /// Only evaluate the crate's constants and statics, instead of running `main` (`--check-consts`)
+    check_consts: bool,
+    /// Limits given on the command line
+    limit_flags: LimitFlags,
+    /// Exit with the status of the category of the error the program ran into
+    /// (`-Zmiri-category-exit-status`)
+    category_exit_status: bool,
+}
+
+/// Limits given on the command line, they take precedence over the `#![miri(...)]` attributes
+#[derive(Copy, Clone, Default)]
+struct LimitFlags {
+    /// `-Zmiri-step-limit`
+    step_limit: Option<u64>,
+    /// `-Zmiri-memory-limit`
+    memory_size: Option<u64>,
+    /// `-Zmiri-stack-limit`
+    stack_limit: Option<usize>,
 }
 
 impl<'a> CompilerCalls<'a> for MiriCompilerCalls {
@@ -77,7 +100,13 @@ impl<'a> CompilerCalls<'a> for MiriCompilerCalls {
     ) -> CompileController<'a> {
         let mut control = self.default.build_controller(sess, matches);
         control.after_hir_lowering.callback = Box::new(after_hir_lowering);
-        control.after_analysis.callback = Box::new(after_analysis);
+        let config = self.config.clone();
+        let check_consts = self.check_consts;
+        let limit_flags = self.limit_flags;
+        let category_exit_status = self.category_exit_status;
+        control.after_analysis.callback = Box::new(move |state| {
+            after_analysis(state, &config, check_consts, limit_flags, category_exit_status)
+        });
         if sess.target.target != sess.host {
             // only fully compile targets on the host. linking will fail for cross-compilation.
             control.after_analysis.stop = Compilation::Stop;
@@ -94,41 +123,40 @@ fn after_hir_lowering(state: &mut CompileState) {
     state.session.plugin_attributes.borrow_mut().push(attr);
 }
 
-fn after_analysis<'a, 'tcx>(state: &mut CompileState<'a, 'tcx>) {
+fn after_analysis<'a, 'tcx>(
+    state: &mut CompileState<'a, 'tcx>,
+    config: &miri::MiriConfig,
+    check_consts: bool,
+    limit_flags: LimitFlags,
+    category_exit_status: bool,
+) {
     state.session.abort_if_errors();
 
     let tcx = state.tcx.unwrap();
-    let limits = resource_limits_from_attributes(state);
-
-    if std::env::args().any(|arg| arg == "--test") {
-        struct Visitor<'a, 'tcx: 'a>(
-            miri::ResourceLimits,
-            TyCtxt<'a, 'tcx, 'tcx>,
-            &'a CompileState<'a, 'tcx>
-        );
-        impl<'a, 'tcx: 'a, 'hir> itemlikevisit::ItemLikeVisitor<'hir> for Visitor<'a, 'tcx> {
-            fn visit_item(&mut self, i: &'hir hir::Item) {
-                if let hir::Item_::ItemFn(_, _, _, _, _, body_id) = i.node {
-                    if i.attrs.iter().any(|attr| {
-                        attr.name().map_or(false, |n| n == "test")
-                    })
-                    {
-                        let did = self.1.hir.body_owner_def_id(body_id);
-                        println!(
-                            "running test: {}",
-                            self.1.def_path_debug_str(did),
-                        );
-                        miri::eval_main(self.1, did, None, self.0);
-                        self.2.session.abort_if_errors();
-                    }
-                }
-            }
-            fn visit_trait_item(&mut self, _trait_item: &'hir hir::TraitItem) {}
-            fn visit_impl_item(&mut self, _impl_item: &'hir hir::ImplItem) {}
-        }
-        state.hir_crate.unwrap().visit_all_item_likes(
-            &mut Visitor(limits, tcx, state),
-        );
+    let mut limits = limits_from_attributes(state);
+    if let Some(step_limit) = limit_flags.step_limit {
+        limits = limits.steps(step_limit);
+    }
+    if let Some(memory_size) = limit_flags.memory_size {
+        limits = limits.memory(memory_size);
+    }
+    if let Some(stack_limit) = limit_flags.stack_limit {
+        limits = limits.stack(stack_limit);
+    }
+    let limits = match limits.build() {
+        Ok(limits) => limits,
+        Err(e) => state.session.fatal(&e.to_string()),
+    };
+
+    if check_consts {
+        miri::eval_consts(tcx, limits, config.clone());
+        state.session.abort_if_errors();
+    } else if config.constant_time.is_some() {
+        miri::check_constant_time(tcx, limits, config.clone());
+        state.session.abort_if_errors();
+    } else if state.session.opts.test {
+        // rustc's own `--test`, a `--test` after `--` is an argument of the program
+        run_tests(state, tcx, limits, config);
     } else if let Some((entry_node_id, _)) = *state.session.entry_fn.borrow() {
         let entry_def_id = tcx.hir.local_def_id(entry_node_id);
         let start_wrapper = tcx.lang_items().start_fn().and_then(|start_fn| {
@@ -138,16 +166,252 @@ fn after_analysis<'a, 'tcx>(state: &mut CompileState<'a, 'tcx>) {
                 None
             }
         });
-        miri::eval_main(tcx, entry_def_id, start_wrapper, limits);
+        let outcome = miri::eval_main(tcx, entry_def_id, start_wrapper, limits, config.clone());
 
+        if category_exit_status {
+            if let Some(category) = outcome.error_category() {
+                std::process::exit(category.exit_status());
+            }
+        }
         state.session.abort_if_errors();
+        if let miri::EvalOutcome::Completed { exit_code } = outcome {
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
     } else {
         println!("no main function found, assuming auxiliary build");
     }
 }
 
-fn resource_limits_from_attributes(state: &CompileState) -> miri::ResourceLimits {
-    let mut limits = miri::ResourceLimits::default();
+/// A `#[test]` function
+struct Test {
+    def_id: DefId,
+    /// The path of the function, which the filters match
+    name: String,
+    /// Whether the test is `#[should_panic]`, with the expected message
+    should_panic: Option<Option<String>>,
+    /// Whether the test is `#[ignore]`
+    ignore: bool,
+}
+
+/// Which tests to run, from the arguments after `--`: like libtest, the tests whose name contains
+/// one of the filters (all without filters) and none of the `--skip` filters that are not
+/// `#[ignore]` (`--ignored` runs only those, `--include-ignored` runs both). libtest's options
+/// for running and printing the tests (`--test-threads`, `--nocapture`, `--quiet`, `--color`)
+/// are accepted and have no effect.
+#[derive(Default)]
+struct TestFilter {
+    filters: Vec<String>,
+    skip: Vec<String>,
+    /// Match the name exactly instead of a part of it
+    exact: bool,
+    ignored: bool,
+    include_ignored: bool,
+}
+
+impl TestFilter {
+    fn from_args(args: &[String]) -> Self {
+        let mut filter = TestFilter::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match &arg[..] {
+                "--ignored" => filter.ignored = true,
+                "--include-ignored" => filter.include_ignored = true,
+                "--exact" => filter.exact = true,
+                "--skip" => match args.next() {
+                    Some(skip) => filter.skip.push(skip.to_owned()),
+                    None => {
+                        eprintln!("--skip needs a filter");
+                        std::process::exit(1);
+                    }
+                },
+                // FIXME: run the tests on a thread pool of this size. The compiler's type context
+                // cannot be shared between threads, so for now the tests always run one after
+                // the other.
+                "--test-threads" | "--color" => {
+                    if args.next().is_none() {
+                        eprintln!("{} needs a value", arg);
+                        std::process::exit(1);
+                    }
+                }
+                arg if arg.starts_with("--test-threads=") || arg.starts_with("--color=") => {}
+                // The output of the tests is never captured, and the results are always printed
+                // one per line
+                "--nocapture" | "-q" | "--quiet" => {}
+                arg if arg.starts_with('-') => {
+                    eprintln!("unknown test option `{}`", arg);
+                    std::process::exit(1);
+                }
+                arg => filter.filters.push(arg.to_owned()),
+            }
+        }
+        filter
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        let matches = |filter: &String| if self.exact {
+            name == filter
+        } else {
+            name.contains(&filter[..])
+        };
+        (self.filters.is_empty() || self.filters.iter().any(&matches)) && !self.skip.iter().any(&matches)
+    }
+
+    /// Whether an ignored test runs anyway
+    fn runs_ignored(&self) -> bool {
+        self.ignored || self.include_ignored
+    }
+
+    /// Whether a test that is not ignored runs
+    fn runs_not_ignored(&self) -> bool {
+        !self.ignored || self.include_ignored
+    }
+}
+
+/// The `#[test]` functions of the crate, in the order they are defined
+fn collect_tests<'a, 'tcx: 'a>(state: &CompileState<'a, 'tcx>, tcx: TyCtxt<'a, 'tcx, 'tcx>) -> Vec<Test> {
+    struct Visitor<'a, 'tcx: 'a> {
+        tcx: TyCtxt<'a, 'tcx, 'tcx>,
+        tests: Vec<Test>,
+    }
+    impl<'a, 'tcx: 'a, 'hir> itemlikevisit::ItemLikeVisitor<'hir> for Visitor<'a, 'tcx> {
+        fn visit_item(&mut self, i: &'hir hir::Item) {
+            if let hir::Item_::ItemFn(_, _, _, _, _, body_id) = i.node {
+                if i.attrs.iter().any(|attr| attr.name().map_or(false, |n| n == "test")) {
+                    let def_id = self.tcx.hir.body_owner_def_id(body_id);
+                    self.tests.push(Test {
+                        def_id,
+                        name: self.tcx.item_path_str(def_id),
+                        should_panic: should_panic(&i.attrs),
+                        ignore: i.attrs.iter().any(|attr| attr.name().map_or(false, |n| n == "ignore")),
+                    });
+                }
+            }
+        }
+        fn visit_trait_item(&mut self, _trait_item: &'hir hir::TraitItem) {}
+        fn visit_impl_item(&mut self, _impl_item: &'hir hir::ImplItem) {}
+    }
+    let mut visitor = Visitor {
+        tcx,
+        tests: Vec::new(),
+    };
+    state.hir_crate.unwrap().visit_all_item_likes(&mut visitor);
+    visitor.tests
+}
+
+/// Runs the `#[test]` functions selected by the arguments and reports the results like libtest,
+/// so that tools parsing its output work with miri
+fn run_tests<'a, 'tcx: 'a>(
+    state: &CompileState<'a, 'tcx>,
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    limits: miri::ResourceLimits,
+    config: &miri::MiriConfig,
+) {
+    let filter = TestFilter::from_args(&config.args);
+    let tests = collect_tests(state, tcx);
+    let total = tests.len();
+    let tests: Vec<Test> = tests
+        .into_iter()
+        .filter(|test| filter.matches_name(&test.name) && (test.ignore || filter.runs_not_ignored()))
+        .collect();
+    println!();
+    println!("running {} test{}", tests.len(), if tests.len() == 1 { "" } else { "s" });
+    let mut passed = 0;
+    let mut ignored = 0;
+    let mut failed = Vec::new();
+    // The profile, trace and coverage cover all tests together
+    let mut reports = miri::Reports::new(tcx, config);
+    for test in &tests {
+        if test.ignore && !filter.runs_ignored() {
+            println!("test {} ... ignored", test.name);
+            ignored += 1;
+            continue;
+        }
+        print!("test {} ... ", test.name);
+        std::io::stdout().flush().ok();
+        let errors = state.session.err_count();
+        let mut config = config.clone();
+        config.expect_panic = test.should_panic.is_some();
+        // Every test leaves a heap of its own behind
+        if let Some(file) = config.heap_graph.take() {
+            config.heap_graph = Some(format!("{}.{}", file, test.name.replace("::", "-")));
+        }
+        let outcome = miri::eval_main_with_reports(tcx, test.def_id, None, limits, config, &mut reports);
+        if let Some(ref expected) = test.should_panic {
+            check_panic(state.session, &outcome, expected.as_ref().map(|s| &s[..]));
+        }
+        if state.session.err_count() == errors {
+            println!("ok");
+            passed += 1;
+        } else {
+            println!("FAILED");
+            failed.push(&test.name);
+        }
+    }
+    reports.write(tcx);
+    if !failed.is_empty() {
+        println!();
+        println!("failures:");
+        for name in &failed {
+            println!("    {}", name);
+        }
+    }
+    println!();
+    println!(
+        "test result: {}. {} passed; {} failed; {} ignored; 0 measured; {} filtered out",
+        if failed.is_empty() { "ok" } else { "FAILED" },
+        passed,
+        failed.len(),
+        ignored,
+        total - tests.len(),
+    );
+    println!();
+    state.session.abort_if_errors();
+}
+
+/// Whether a test is `#[should_panic]`, and the `expected` part of its panic message if it has
+/// one (`#[should_panic(expected = "...")]` or `#[should_panic = "..."]`)
+fn should_panic(attrs: &[ast::Attribute]) -> Option<Option<String>> {
+    let attr = attrs.iter().find(|attr| attr.name().map_or(false, |n| n == "should_panic"))?;
+    if let Some(expected) = attr.value_str() {
+        return Some(Some(expected.to_string()));
+    }
+    let expected = attr.meta_item_list().and_then(|items| {
+        items.iter()
+            .find(|item| item.name().map_or(false, |n| n == "expected"))
+            .and_then(|item| item.value_str())
+            .map(|expected| expected.to_string())
+    });
+    Some(expected)
+}
+
+/// Checks that a `#[should_panic]` test panicked like libtest expects it to: with a message
+/// containing `expected`, if given
+fn check_panic(session: &Session, outcome: &miri::EvalOutcome, expected: Option<&str>) {
+    match *outcome {
+        miri::EvalOutcome::Panicked { ref message, .. } => match (expected, message.as_ref()) {
+            (Some(expected), Some(message)) if !message.contains(expected) => session.err(&format!(
+                "the test panicked with `{}`, which does not contain the expected `{}`",
+                message,
+                expected,
+            )),
+            (Some(expected), None) => session.warn(&format!(
+                "the message of the panic is not known, it was not checked to contain `{}`",
+                expected,
+            )),
+            _ => {}
+        },
+        miri::EvalOutcome::Completed { .. } => {
+            session.err("the test did not panic, but it is marked `#[should_panic]`");
+        }
+        // Other errors were reported by the evaluation
+        _ => {}
+    }
+}
+
+fn limits_from_attributes(state: &CompileState) -> miri::ResourceLimitsBuilder {
+    let mut limits = miri::ResourceLimits::builder();
     let krate = state.hir_crate.as_ref().unwrap();
     let err_msg = "miri attributes need to be in the form `miri(key = value)`";
     let extract_int = |lit: &syntax::ast::Lit| -> u128 {
@@ -171,9 +435,9 @@ fn resource_limits_from_attributes(state: &CompileState) -> miri::ResourceLimits
                 if let NestedMetaItemKind::MetaItem(ref inner) = item.node {
                     if let MetaItemKind::NameValue(ref value) = inner.node {
                         match &inner.name().as_str()[..] {
-                            "memory_size" => limits.memory_size = extract_int(value) as u64,
-                            "step_limit" => limits.step_limit = extract_int(value) as u64,
-                            "stack_limit" => limits.stack_limit = extract_int(value) as usize,
+                            "memory_size" => limits = limits.memory(extract_int(value) as u64),
+                            "step_limit" => limits = limits.steps(extract_int(value) as u64),
+                            "stack_limit" => limits = limits.stack(extract_int(value) as usize),
                             _ => state.session.span_err(item.span, "unknown miri attribute"),
                         }
                     } else {
@@ -226,8 +490,8 @@ fn init_logger() {
 }
 
 fn find_sysroot() -> String {
-    if let Ok(sysroot) = std::env::var("MIRI_SYSROOT") {
-        return sysroot;
+    if let Some(sysroot) = miri::fullmir_sysroot() {
+        return sysroot.to_str().expect("the miri sysroot is not valid unicode").to_owned();
     }
 
     // Taken from https://github.com/Manishearth/rust-clippy/pull/911.
@@ -245,10 +509,293 @@ fn find_sysroot() -> String {
     }
 }
 
+/// Parses the `COLUMNSxROWS` argument of `-Zmiri-tty`
+fn parse_tty_size(size: &str) -> (u16, u16) {
+    let mut parts = size.splitn(2, 'x').map(str::parse::<u16>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(cols)), Some(Ok(rows))) => (cols, rows),
+        _ => {
+            eprintln!("-Zmiri-tty expects a terminal size like `80x24`, got `{}`", size);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The crate name of the library at `path`, e.g. `foo_model` for `target/debug/libfoo_model.rlib`
+fn model_crate_name(path: &str) -> String {
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path);
+    // Cargo adds a hash to the names of dependencies
+    let stem = stem.splitn(2, '-').next().unwrap();
+    if stem.starts_with("lib") {
+        stem["lib".len()..].to_owned()
+    } else {
+        stem.to_owned()
+    }
+}
+
+/// Parses the argument of `-Zmiri-memory-limit`, a number of bytes with an optional `K`, `M` or `G`
+/// suffix, e.g. `512M`
+fn parse_memory_size(size: &str) -> u64 {
+    let (number, unit) = match size.chars().last() {
+        Some('K') | Some('k') => (&size[..size.len() - 1], 1 << 10),
+        Some('M') | Some('m') => (&size[..size.len() - 1], 1 << 20),
+        Some('G') | Some('g') => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
+    match number.parse::<u64>().ok().and_then(|number| number.checked_mul(unit)) {
+        Some(bytes) => bytes,
+        None => {
+            eprintln!("-Zmiri-memory-limit expects a size like `4096`, `512M` or `4G`, got `{}`", size);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses the `ALLOC:OFFSET=VALUE` argument of `-Zmiri-break-on-byte`
+fn parse_byte_breakpoint(breakpoint: &str) -> (u64, u64, u8) {
+    let parsed = breakpoint.find(':').and_then(|colon| {
+        let (alloc, rest) = (&breakpoint[..colon], &breakpoint[colon + 1..]);
+        let eq = rest.find('=')?;
+        let (offset, value) = (&rest[..eq], &rest[eq + 1..]);
+        Some((alloc.parse().ok()?, offset.parse().ok()?, value.parse().ok()?))
+    });
+    match parsed {
+        Some(breakpoint) => breakpoint,
+        None => {
+            eprintln!("-Zmiri-break-on-byte expects a breakpoint like `12:4=0`, got `{}`", breakpoint);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
     init_logger();
     let mut args: Vec<String> = std::env::args().collect();
 
+    let mut config = miri::MiriConfig::default();
+
+    // Everything after `--` is meant for the interpreted program, not for rustc
+    if let Some(pos) = args.iter().position(|arg| arg == "--") {
+        config.args = args.split_off(pos + 1);
+        args.pop();
+    }
+
+    let mut check_consts = false;
+    let mut limit_flags = LimitFlags::default();
+    let mut category_exit_status = false;
+    let mut model_crate = None;
+    let mut profile_file = None;
+    let mut profile_interval = 10_000;
+
+    // Take out the flags meant for miri, rustc does not know them
+    args.retain(|arg| match arg.as_str() {
+        "--check-consts" => {
+            check_consts = true;
+            false
+        }
+        "--debug" => {
+            config.debug = true;
+            false
+        }
+        "-Zmiri-short-writes" => {
+            config.short_writes = true;
+            false
+        }
+        arg if arg.starts_with("-Zmiri-tty=") => {
+            config.tty_size = Some(parse_tty_size(&arg["-Zmiri-tty=".len()..]));
+            false
+        }
+        arg if arg.starts_with("-Zmiri-break-on-error=") => {
+            config.break_on_error = Some(arg["-Zmiri-break-on-error=".len()..].to_owned());
+            false
+        }
+        arg if arg.starts_with("-Zmiri-break-on-byte=") => {
+            config.break_on_byte = Some(parse_byte_breakpoint(&arg["-Zmiri-break-on-byte=".len()..]));
+            false
+        }
+        arg if arg.starts_with("-Zmiri-track-alloc-id=") => {
+            for id in arg["-Zmiri-track-alloc-id=".len()..].split(',') {
+                match id.parse() {
+                    Ok(id) => config.track_alloc_ids.push(id),
+                    Err(_) => {
+                        eprintln!("-Zmiri-track-alloc-id expects allocation ids like `12,15`, got `{}`", id);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            false
+        }
+        arg if arg.starts_with("-Zmiri-error-format=") => {
+            config.json_errors = match &arg["-Zmiri-error-format=".len()..] {
+                "human" => false,
+                "json" => true,
+                format => {
+                    eprintln!("-Zmiri-error-format expects `human` or `json`, got `{}`", format);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        arg if arg.starts_with("-Zmiri-step-limit=") => {
+            let limit = &arg["-Zmiri-step-limit=".len()..];
+            limit_flags.step_limit = match limit.parse() {
+                Ok(limit) => Some(limit),
+                Err(_) => {
+                    eprintln!("-Zmiri-step-limit expects a number of steps, got `{}`", limit);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        arg if arg.starts_with("-Zmiri-memory-limit=") => {
+            limit_flags.memory_size = Some(parse_memory_size(&arg["-Zmiri-memory-limit=".len()..]));
+            false
+        }
+        arg if arg.starts_with("-Zmiri-stack-limit=") => {
+            let limit = &arg["-Zmiri-stack-limit=".len()..];
+            limit_flags.stack_limit = match limit.parse() {
+                Ok(limit) => Some(limit),
+                Err(_) => {
+                    eprintln!("-Zmiri-stack-limit expects a number of stack frames, got `{}`", limit);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        arg if arg.starts_with("-Zmiri-max-calls-per-fn=") => {
+            let max = &arg["-Zmiri-max-calls-per-fn=".len()..];
+            config.max_calls_per_fn = match max.parse() {
+                Ok(max) => Some(max),
+                Err(_) => {
+                    eprintln!("-Zmiri-max-calls-per-fn expects a number of calls, got `{}`", max);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        arg if arg.starts_with("-Zmiri-constant-time=") => {
+            let spec = &arg["-Zmiri-constant-time=".len()..];
+            let parts: Vec<&str> = spec.splitn(3, ',').collect();
+            if parts.len() != 3 {
+                eprintln!("-Zmiri-constant-time expects `FUNCTION,INPUT1,INPUT2`, got `{}`", spec);
+                std::process::exit(1);
+            }
+            config.constant_time = Some((parts[0].to_owned(), parts[1].to_owned(), parts[2].to_owned()));
+            false
+        }
+        arg if arg.starts_with("-Zmiri-seed=") => {
+            let seed = &arg["-Zmiri-seed=".len()..];
+            config.seed = match seed.parse() {
+                Ok(seed) => seed,
+                Err(_) => {
+                    eprintln!("-Zmiri-seed expects a number, got `{}`", seed);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        arg if arg.starts_with("-Zmiri-model-crate=") => {
+            model_crate = Some(arg["-Zmiri-model-crate=".len()..].to_owned());
+            false
+        }
+        "-Zmiri-category-exit-status" => {
+            category_exit_status = true;
+            false
+        }
+        "-Zmiri-allow-fs" => {
+            config.allow_fs = true;
+            false
+        }
+        "-Zmiri-virtual-addresses" => {
+            config.virtual_addresses = true;
+            false
+        }
+        "-Zmiri-disable-value-checks" => {
+            config.disable_value_checks = true;
+            false
+        }
+        "-Zmiri-host-time" => {
+            config.host_time = true;
+            false
+        }
+        "-Zmiri-summary" => {
+            config.summary = true;
+            false
+        }
+        "-Zmiri-warn-overflow-profile" => {
+            config.warn_overflow_profile = true;
+            false
+        }
+        "-Zmiri-memory-hotspots" => {
+            config.memory_hotspots = Some(10);
+            false
+        }
+        arg if arg.starts_with("-Zmiri-memory-hotspots=") => {
+            let top = &arg["-Zmiri-memory-hotspots=".len()..];
+            config.memory_hotspots = match top.parse() {
+                Ok(top) => Some(top),
+                Err(_) => {
+                    eprintln!("-Zmiri-memory-hotspots expects a number of statements, got `{}`", top);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        arg if arg.starts_with("-Zmiri-trace=") => {
+            config.trace = Some(arg["-Zmiri-trace=".len()..].to_owned());
+            false
+        }
+        arg if arg.starts_with("-Zmiri-coverage=") => {
+            config.coverage = Some(arg["-Zmiri-coverage=".len()..].to_owned());
+            false
+        }
+        "-Zmiri-self-profile" => {
+            config.self_profile = true;
+            false
+        }
+        arg if arg.starts_with("-Zmiri-heap-graph=") => {
+            config.heap_graph = Some(arg["-Zmiri-heap-graph=".len()..].to_owned());
+            false
+        }
+        "-Zmiri-alloc-stats" => {
+            config.alloc_stats = true;
+            false
+        }
+        "-Zmiri-detect-loops" => {
+            config.detect_loops = true;
+            false
+        }
+        arg if arg.starts_with("-Zmiri-profile=") => {
+            profile_file = Some(arg["-Zmiri-profile=".len()..].to_owned());
+            false
+        }
+        arg if arg.starts_with("-Zmiri-profile-interval=") => {
+            let interval = &arg["-Zmiri-profile-interval=".len()..];
+            profile_interval = match interval.parse() {
+                Ok(interval) if interval > 0 => interval,
+                _ => {
+                    eprintln!("-Zmiri-profile-interval expects a positive number of steps, got `{}`", interval);
+                    std::process::exit(1);
+                }
+            };
+            false
+        }
+        _ => true,
+    });
+    config.profile = profile_file.map(|file| (file, profile_interval));
+    if let Some(path) = model_crate {
+        let name = model_crate_name(&path);
+        // A bare crate name is found in the library search path, like any `extern crate`
+        if name != path {
+            args.push("--extern".to_owned());
+            args.push(format!("{}={}", name, path));
+        }
+        config.model_crate = Some(name);
+    }
+
     let sysroot_flag = String::from("--sysroot");
     if !args.contains(&sysroot_flag) {
         args.push(sysroot_flag);
@@ -260,5 +807,9 @@ fn main() {
 
     rustc_driver::run_compiler(&args, &mut MiriCompilerCalls {
         default: RustcDefaultCalls,
+        config,
+        check_consts,
+        limit_flags,
+        category_exit_status,
     }, None, None);
 }
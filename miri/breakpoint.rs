@@ -0,0 +1,130 @@
+//! Conditional breakpoints: instead of attaching a debugger, users can ask miri to dump the
+//! interpreter state when a specific error is raised or when a byte in memory takes a given value.
+//! Watchpoints report everything that happens to an allocation.
+
+use super::*;
+use inspect::EvalContextExt as InspectEvalContextExt;
+use rustc_compat;
+use json;
+
+/// Something that happened to a tracked allocation
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum AllocEvent {
+    Created,
+    Read { offset: u64, size: u64 },
+    Written { offset: u64, size: u64 },
+    Freed,
+}
+
+/// Called by the memory for everything that happens to an allocation, remembers the event if
+/// the allocation is tracked. The events are reported after the step that caused them.
+pub(crate) fn record_alloc_event(data: &MemoryData, alloc_id: u64, event: AllocEvent) {
+    if !data.tracked_allocs.is_empty() && data.tracked_allocs.contains(&alloc_id) {
+        data.alloc_events.borrow_mut().push((alloc_id, event));
+    }
+}
+
+/// Called before every step of the program, the call stack the events of the step happen in
+pub(crate) fn backtrace<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) -> Option<Vec<(String, Span)>> {
+    if ecx.memory().data.tracked_allocs.is_empty() {
+        return None;
+    }
+    Some(ecx.stack()
+        .iter()
+        .rev()
+        .map(|frame| (rustc_compat::frame_fn_path(ecx.tcx, frame), rustc_compat::frame_span(frame)))
+        .collect())
+}
+
+/// Called after every step of the program, reports what happened to the tracked allocations
+pub(crate) fn report_alloc_events<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    backtrace: Option<Vec<(String, Span)>>,
+) {
+    let events = ::std::mem::replace(&mut *ecx.memory().data.alloc_events.borrow_mut(), Vec::new());
+    for (alloc_id, event) in events {
+        let what = match event {
+            AllocEvent::Created => "was created".to_owned(),
+            AllocEvent::Read { offset, size } => format!("was read ({} bytes at offset {})", size, offset),
+            AllocEvent::Written { offset, size } => format!("was written ({} bytes at offset {})", size, offset),
+            AllocEvent::Freed => "was freed".to_owned(),
+        };
+        eprintln!("miri: allocation {} {}", alloc_id, what);
+        for &(ref path, span) in backtrace.iter().flat_map(|backtrace| backtrace) {
+            eprintln!("    in {} at {}", path, rustc_compat::span_to_string(ecx.tcx, span));
+        }
+    }
+}
+
+/// Called after every step of the program, checks the memory breakpoint
+pub(crate) fn step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    let (alloc_id, offset, value) = match ecx.machine.config.break_on_byte {
+        Some(breakpoint) if !ecx.machine.byte_breakpoint_hit => breakpoint,
+        _ => return,
+    };
+    let hit = match ecx.memory().get(AllocId(alloc_id)) {
+        Ok(alloc) => {
+            offset < alloc.bytes.len() as u64 &&
+                alloc.undef_mask.is_range_defined(offset, offset + 1) &&
+                alloc.bytes[offset as usize] == value
+        }
+        // The allocation does not exist (yet or anymore)
+        Err(_) => false,
+    };
+    if hit {
+        // Only report the first time, the byte might keep its value for a long time
+        ecx.machine.byte_breakpoint_hit = true;
+        let ptr = MemoryPointer::new(AllocId(alloc_id), offset);
+        dump_state(ecx, &format!("byte {} is {}", ecx.describe_ptr(ptr), value));
+        if let Ok(alloc) = ecx.memory().get(AllocId(alloc_id)) {
+            eprintln!("allocation {}: {}", alloc_id, format_bytes(alloc));
+        }
+    }
+}
+
+/// Called when the evaluation ends with an error, before it is reported
+pub(crate) fn on_error<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, e: &EvalError<'tcx>) {
+    let kind = match ecx.machine.config.break_on_error {
+        Some(ref kind) => kind,
+        None => return,
+    };
+    // The same names as the `kind` of `-Zmiri-error-format=json`, including the `MiriError` ones
+    if json::error_kind(e) == *kind {
+        dump_state(ecx, &format!("{} was raised: {}", kind, e));
+    }
+}
+
+/// Prints every frame of the call stack, the statement it is at and its live locals
+fn dump_state<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, reason: &str) {
+    eprintln!("miri: breakpoint hit: {}", reason);
+    for (i, frame) in ecx.stack().iter().enumerate().rev() {
+        eprintln!(
+            "frame {}: {} at {}",
+            i,
+            rustc_compat::frame_fn_path(ecx.tcx, frame),
+            rustc_compat::span_to_string(ecx.tcx, rustc_compat::frame_span(frame)),
+        );
+        for local in ecx.frame_locals(i) {
+            if let Some(value) = local.value {
+                match local.name {
+                    Some(name) => eprintln!("    {:?} ({}: {}) = {:?}", local.local, name, local.ty, value),
+                    None => eprintln!("    {:?}: {} = {:?}", local.local, local.ty, value),
+                }
+            }
+        }
+    }
+}
+
+/// Formats the bytes of an allocation in hex, undefined bytes are shown as `__`
+pub(crate) fn format_bytes(alloc: &Allocation) -> String {
+    alloc.bytes
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| if alloc.undef_mask.is_range_defined(i as u64, i as u64 + 1) {
+            format!("{:02x}", byte)
+        } else {
+            "__".to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
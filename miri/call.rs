@@ -0,0 +1,79 @@
+//! Calling a single function of the crate with arguments given by the embedder, instead of running
+//! the whole program from `main`. The evaluation context outlives the call, so the result (and
+//! everything it points to) can be inspected in its memory afterwards.
+
+use rustc::ty::layout::LayoutOf;
+
+use super::*;
+
+/// An evaluation context for `eval_fn`. Arguments that need memory (e.g. references) are
+/// allocated in it before the call.
+pub fn new_eval_context<'a, 'tcx: 'a>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    limits: ResourceLimits,
+    config: MiriConfig,
+) -> EvalContext<'a, 'tcx, Evaluator<'tcx>> {
+    let machine = Evaluator {
+        config,
+        ..Default::default()
+    };
+    EvalContext::new(tcx, ty::ParamEnv::empty(traits::Reveal::All), limits, machine, Default::default())
+}
+
+/// Calls the function `def_id` with `args` and runs it to completion. The function must not be
+/// generic, `args` are written to its arguments with the types of the arguments.
+///
+/// Returns the returned value. Aggregates are returned by reference, into an allocation that
+/// stays alive in `ecx`.
+pub fn eval_fn<'a, 'tcx: 'a>(
+    ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    def_id: DefId,
+    args: &[Value],
+) -> EvalResult<'tcx, Value> {
+    let generics = ecx.tcx.generics_of(def_id);
+    if generics.parent_types != 0 || !generics.types.is_empty() {
+        return err!(Unimplemented(format!(
+            "`{}` is generic, only non-generic functions can be called directly",
+            ecx.tcx.item_path_str(def_id),
+        )));
+    }
+    let instance = ty::Instance::mono(ecx.tcx, def_id);
+    let mir = ecx.load_mir(instance.def)?;
+    if mir.arg_count != args.len() {
+        return err!(Unimplemented(format!(
+            "`{}` takes {} arguments, but {} were given",
+            ecx.tcx.item_path_str(def_id),
+            mir.arg_count,
+            args.len(),
+        )));
+    }
+
+    let return_ty = mir.return_ty();
+    let layout = ecx.layout_of(return_ty)?;
+    let return_ptr = ecx.memory_mut().allocate(
+        layout.size.bytes(),
+        layout.align.abi(),
+        Some(MemoryKind::Stack),
+    )?;
+    ecx.push_stack_frame(
+        instance,
+        mir.span,
+        mir,
+        Place::from_ptr(return_ptr),
+        StackPopCleanup::None,
+    )?;
+    let arg_locals: Vec<mir::Local> = mir.args_iter().collect();
+    for (&local, &value) in arg_locals.iter().zip(args) {
+        let dest = ecx.eval_place(&mir::Place::Local(local))?;
+        let ty = mir.local_decls[local].ty;
+        ecx.write_value(ValTy { value, ty }, dest)?;
+    }
+
+    let depth = ecx.stack().len();
+    while ecx.stack().len() >= depth {
+        if !super::step(ecx)? {
+            break;
+        }
+    }
+    ecx.read_value(return_ptr.into(), return_ty)
+}
@@ -0,0 +1,108 @@
+//! What kind of problem an error is. The messages of errors change with their wording, tools that
+//! need to tell undefined behavior from a missing feature of miri should use the category and its
+//! code instead.
+
+use std::fmt;
+
+use super::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The program has undefined behavior
+    UndefinedBehavior,
+    /// The program does something miri cannot evaluate (yet), e.g. calling an unknown foreign
+    /// function or inspecting the address of a pointer
+    Unsupported,
+    /// The program exceeded a limit of the evaluation, or would never have terminated
+    ResourceExhaustion,
+    /// The program panicked, which is not an error of the program's semantics
+    Panic,
+    /// miri itself failed, please report this
+    InterpreterBug,
+    /// The user stopped the evaluation in the debugger before the program ended
+    Stopped,
+}
+
+impl ErrorCategory {
+    /// The stable name of the category, e.g. in JSON errors
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorCategory::UndefinedBehavior => "undefined-behavior",
+            ErrorCategory::Unsupported => "unsupported",
+            ErrorCategory::ResourceExhaustion => "resource-exhaustion",
+            ErrorCategory::Panic => "panic",
+            ErrorCategory::InterpreterBug => "interpreter-bug",
+            ErrorCategory::Stopped => "stopped",
+        }
+    }
+
+    /// The exit status of the `miri` binary with `-Zmiri-category-exit-status`. A panic exits
+    /// with 101, like the program would have natively.
+    pub fn exit_status(self) -> i32 {
+        match self {
+            ErrorCategory::UndefinedBehavior => 2,
+            ErrorCategory::Unsupported => 3,
+            ErrorCategory::ResourceExhaustion => 4,
+            ErrorCategory::Panic => 101,
+            ErrorCategory::InterpreterBug => 5,
+            ErrorCategory::Stopped => 6,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+pub trait EvalErrorExt {
+    fn category(&self) -> ErrorCategory;
+}
+
+impl<'tcx> EvalErrorExt for EvalError<'tcx> {
+    fn category(&self) -> ErrorCategory {
+        match self.kind {
+            EvalErrorKind::Panic => ErrorCategory::Panic,
+            EvalErrorKind::OutOfMemory { .. } |
+            EvalErrorKind::ExecutionTimeLimitReached |
+            EvalErrorKind::StackFrameLimitReached |
+            EvalErrorKind::OutOfTls => ErrorCategory::ResourceExhaustion,
+            // The limits of miri itself, and the program giving up after running out of memory
+            EvalErrorKind::MachineError(_) => match MiriError::of(self) {
+                Some(&MiriError::LimitReached(..)) |
+                Some(&MiriError::OutOfMemoryAbort) => ErrorCategory::ResourceExhaustion,
+                Some(&MiriError::StoppedByDebugger) => ErrorCategory::Stopped,
+                None => ErrorCategory::InterpreterBug,
+            },
+            EvalErrorKind::Unimplemented(_) |
+            EvalErrorKind::NeedsRfc(_) |
+            EvalErrorKind::NoMirFor(_) |
+            EvalErrorKind::InlineAsm |
+            EvalErrorKind::PathNotFound(_) => ErrorCategory::Unsupported,
+            // Pointers do not have addresses (without `-Zmiri-virtual-addresses`), this is not
+            // undefined behavior in itself
+            EvalErrorKind::ReadPointerAsBytes |
+            EvalErrorKind::ReadBytesAsPointer |
+            EvalErrorKind::InvalidPointerMath => ErrorCategory::Unsupported,
+            // Intrinsics called with arguments they are not defined for, e.g. `unchecked_div` by
+            // zero or `transmute` between types of different sizes
+            EvalErrorKind::Intrinsic(_) => ErrorCategory::UndefinedBehavior,
+            EvalErrorKind::TypeNotPrimitive(_) | EvalErrorKind::Layout(_) => ErrorCategory::InterpreterBug,
+            _ => ErrorCategory::UndefinedBehavior,
+        }
+    }
+}
+
+impl<'tcx> EvalOutcome<'tcx> {
+    /// The category of the error the evaluation ended with, if any
+    pub fn error_category(&self) -> Option<ErrorCategory> {
+        match *self {
+            EvalOutcome::Completed { .. } => None,
+            EvalOutcome::Panicked { .. } => Some(ErrorCategory::Panic),
+            EvalOutcome::Ub(ref e) => Some(e.category()),
+            EvalOutcome::LimitReached(_) => Some(ErrorCategory::ResourceExhaustion),
+            EvalOutcome::StoppedByDebugger => Some(ErrorCategory::Stopped),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! An experimental check for constant-time code. A function taking a byte slice is evaluated
+//! once for each of two inputs (e.g. two different secret keys). If the statements executed or
+//! the memory locations accessed differ between the runs, the function's timing may leak
+//! information about its input.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, Read};
+
+use super::*;
+use rustc_compat;
+
+/// Something the program did that may influence its timing
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Event {
+    /// A statement or terminator was evaluated
+    Statement {
+        def_id: DefId,
+        block: mir::BasicBlock,
+        stmt: usize,
+        span: Span,
+    },
+    /// Memory was read or written
+    Access {
+        alloc_id: u64,
+        offset: u64,
+        size: u64,
+        write: bool,
+    },
+}
+
+/// Called on every memory access, records it if a trace is being collected
+pub(crate) fn record_access(data: &MemoryData, ptr: MemoryPointer, size: u64, access: AccessKind) {
+    if let Some(ref trace) = data.constant_time_trace {
+        trace.borrow_mut().push(Event::Access {
+            alloc_id: ptr.alloc_id.0,
+            offset: ptr.offset,
+            size,
+            write: match access {
+                AccessKind::Read => false,
+                AccessKind::Write => true,
+            },
+        });
+    }
+}
+
+/// Evaluates the function named in `config.constant_time` with both of its inputs and reports
+/// the first difference between the two runs as an error
+pub fn check_constant_time<'a, 'tcx: 'a>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    limits: ResourceLimits,
+    config: MiriConfig,
+) {
+    let (path, first_file, second_file) = config.constant_time.clone().expect("no function to check");
+    let def_id = match find_fn(tcx, &path) {
+        Some(def_id) => def_id,
+        None => {
+            tcx.sess.err(&format!("cannot find function `{}` to check for constant time", path));
+            return;
+        }
+    };
+    let mut traces = Vec::new();
+    for file in &[first_file, second_file] {
+        let input = match read_file(file) {
+            Ok(input) => input,
+            Err(e) => {
+                tcx.sess.err(&format!("could not read the input `{}`: {}", file, e));
+                return;
+            }
+        };
+        let machine = Evaluator {
+            config: config.clone(),
+            ..Default::default()
+        };
+        let mut ecx = EvalContext::new(tcx, ty::ParamEnv::empty(traits::Reveal::All), limits, machine, Default::default());
+        match trace_fn(&mut ecx, def_id, &input) {
+            Ok(trace) => traces.push(trace),
+            Err(mut e) => {
+                report_error(&ecx, &mut e);
+                return;
+            }
+        }
+    }
+    let (first, second) = (&traces[0], &traces[1]);
+    let diverged = match first.iter().zip(second).position(|(a, b)| a != b) {
+        Some(index) => index,
+        None if first.len() == second.len() => {
+            eprintln!(
+                "miri: `{}` executed the same {} statements and memory accesses for both inputs",
+                path,
+                first.iter().filter(|event| match **event {
+                    Event::Statement { .. } => true,
+                    Event::Access { .. } => false,
+                }).count(),
+            );
+            return;
+        }
+        None => first.len().min(second.len()),
+    };
+    // Point at the statement during which the runs diverged
+    let span = first[..diverged]
+        .iter()
+        .rev()
+        .filter_map(|event| match *event {
+            Event::Statement { span, .. } => Some(span),
+            Event::Access { .. } => None,
+        })
+        .next()
+        .unwrap_or_else(|| tcx.def_span(def_id));
+    tcx.sess.span_err(span, &format!(
+        "the execution of `{}` depends on its input: with the first input it proceeds with {}, \
+         with the second input with {}",
+        path,
+        describe_event(tcx, first.get(diverged)),
+        describe_event(tcx, second.get(diverged)),
+    ));
+}
+
+fn read_file(file: &str) -> io::Result<Vec<u8>> {
+    let mut input = Vec::new();
+    File::open(file)?.read_to_end(&mut input)?;
+    Ok(input)
+}
+
+/// Finds the local function with the given path, e.g. `compare` or `crypto::compare`
+fn find_fn<'a, 'tcx: 'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, path: &str) -> Option<DefId> {
+    tcx.hir.krate().bodies.keys()
+        .map(|&body_id| tcx.hir.body_owner(body_id))
+        .filter(|&node_id| match tcx.hir.body_owner_kind(node_id) {
+            hir::BodyOwnerKind::Fn => true,
+            hir::BodyOwnerKind::Const | hir::BodyOwnerKind::Static(_) => false,
+        })
+        .map(|node_id| tcx.hir.local_def_id(node_id))
+        .find(|&def_id| tcx.item_path_str(def_id) == path)
+}
+
+/// Evaluates `fn(&[u8])` with `input`, collecting everything it does
+fn trace_fn<'a, 'tcx: 'a>(
+    ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    def_id: DefId,
+    input: &[u8],
+) -> EvalResult<'tcx, Vec<Event>> {
+    let instance = ty::Instance::mono(ecx.tcx, def_id);
+    let mir = ecx.load_mir(instance.def)?;
+    let arg_ty = mir.args_iter().next().map(|arg| mir.local_decls[arg].ty);
+    let arg_ty = match arg_ty {
+        Some(ty) if mir.arg_count == 1 && is_byte_slice(ecx.tcx, ty) && mir.return_ty().is_nil() => ty,
+        _ => return err!(Unimplemented(
+            "the constant time check needs a function with the signature `fn(&[u8])`".to_owned(),
+        )),
+    };
+
+    let ptr = ecx.memory.allocate(input.len() as u64, 1, None)?;
+    ecx.memory.write_bytes(ptr.into(), input)?;
+    ecx.memory.mark_static_initalized(ptr.alloc_id, Mutability::Immutable)?;
+
+    ecx.push_stack_frame(
+        instance,
+        mir.span,
+        mir,
+        Place::undef(),
+        StackPopCleanup::None,
+    )?;
+    let arg = ecx.frame().mir.args_iter().next().unwrap();
+    let dest = ecx.eval_place(&mir::Place::Local(arg))?;
+    ecx.write_value(
+        ValTy {
+            value: Value::ByValPair(PrimVal::Ptr(ptr), PrimVal::Bytes(input.len() as u128)),
+            ty: arg_ty,
+        },
+        dest,
+    )?;
+
+    // Only trace the function itself, not setting up its argument
+    ecx.memory.data.constant_time_trace = Some(RefCell::new(Vec::new()));
+    loop {
+        if let Some(frame) = ecx.stack().last() {
+            let event = Event::Statement {
+                def_id: frame.instance.def_id(),
+                block: frame.block,
+                stmt: frame.stmt,
+                span: rustc_compat::frame_span(frame),
+            };
+            ecx.memory.data.constant_time_trace.as_ref().unwrap().borrow_mut().push(event);
+        }
+        if !super::step(ecx)? {
+            break;
+        }
+    }
+    Ok(ecx.memory.data.constant_time_trace.take().unwrap().into_inner())
+}
+
+fn is_byte_slice<'a, 'tcx: 'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.sty {
+        ty::TyRef(_, ref pointee) => match pointee.ty.sty {
+            ty::TySlice(elem) => elem == tcx.types.u8,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn describe_event<'a, 'tcx: 'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, event: Option<&Event>) -> String {
+    match event {
+        Some(&Event::Statement { span, .. }) => {
+            format!("the statement at {}", rustc_compat::span_to_string(tcx, span))
+        }
+        Some(&Event::Access { alloc_id, offset, size, write }) => format!(
+            "a {}-byte {} at offset {} of allocation {}",
+            size,
+            if write { "write" } else { "read" },
+            offset,
+            alloc_id,
+        ),
+        None => "returning".to_owned(),
+    }
+}
@@ -0,0 +1,163 @@
+//! Statement coverage of the functions of the local crate. Every executed statement and
+//! terminator is recorded, at the end the report lists the share of statements and basic blocks
+//! executed in every function, followed by the MIR of each function with the statements that
+//! never ran marked with `-`:
+//!
+//! ```text
+//! total: 41/47 statements (87.2%), 9/11 blocks
+//! 100.0%  20/20 statements  4/4 blocks  main
+//!  76.9%  20/26 statements  5/7 blocks  parse
+//!
+//! parse
+//! + bb0[0]     src/lib.rs:3  StorageLive(_2)
+//! ...
+//! - bb4[0]     src/lib.rs:9  _0 = const None
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use rustc::hir;
+use rustc::mir;
+use rustc::ty;
+
+use super::*;
+use rustc_compat;
+
+#[derive(Default)]
+pub struct Coverage {
+    /// The statements and terminators executed in every function, terminators have the index
+    /// one past the last statement of their block
+    executed: HashMap<DefId, HashSet<(mir::BasicBlock, usize)>>,
+}
+
+/// Called before every step, records the statement or terminator about to be executed
+pub(crate) fn before_step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    let (def_id, block, stmt) = match ecx.stack().last() {
+        Some(frame) => match frame.instance.def {
+            // Shims have MIR of their own, which is not in the crate
+            ty::InstanceDef::Item(def_id) if def_id.is_local() => (def_id, frame.block, frame.stmt),
+            _ => return,
+        },
+        None => return,
+    };
+    if let Some(ref mut coverage) = ecx.machine.coverage {
+        coverage.executed.entry(def_id).or_insert_with(HashSet::new).insert((block, stmt));
+    }
+}
+
+/// Statements and basic blocks executed in one function, out of how many it has
+struct FnCoverage {
+    path: String,
+    def_id: DefId,
+    stmts: usize,
+    stmts_executed: usize,
+    blocks: usize,
+    blocks_executed: usize,
+}
+
+impl FnCoverage {
+    fn percentage(&self) -> f64 {
+        percentage(self.stmts_executed, self.stmts)
+    }
+}
+
+fn percentage(executed: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        executed as f64 * 100.0 / total as f64
+    }
+}
+
+impl Coverage {
+    /// Writes the report for all functions of the local crate to `file`, including the ones that
+    /// were never called
+    pub fn write<'a, 'tcx: 'a>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>, file: &str) -> io::Result<()> {
+        let no_statements = HashSet::new();
+        let mut fns: Vec<FnCoverage> = tcx.hir.krate().bodies.keys()
+            .map(|&body_id| tcx.hir.body_owner(body_id))
+            .filter(|&node_id| match tcx.hir.body_owner_kind(node_id) {
+                hir::BodyOwnerKind::Fn => true,
+                hir::BodyOwnerKind::Const | hir::BodyOwnerKind::Static(_) => false,
+            })
+            .map(|node_id| {
+                let def_id = tcx.hir.local_def_id(node_id);
+                let mir = tcx.optimized_mir(def_id);
+                let executed = self.executed.get(&def_id).unwrap_or(&no_statements);
+                let blocks = mir.basic_blocks();
+                FnCoverage {
+                    path: tcx.item_path_str(def_id),
+                    def_id,
+                    stmts: blocks.iter().map(|data| data.statements.len() + 1).sum(),
+                    stmts_executed: executed.len(),
+                    blocks: blocks.len(),
+                    blocks_executed: blocks.indices().filter(|&bb| executed.contains(&(bb, 0))).count(),
+                }
+            })
+            .collect();
+        fns.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut out = BufWriter::new(File::create(file)?);
+        let stmts: usize = fns.iter().map(|f| f.stmts).sum();
+        let stmts_executed: usize = fns.iter().map(|f| f.stmts_executed).sum();
+        writeln!(
+            out,
+            "total: {}/{} statements ({:.1}%), {}/{} blocks",
+            stmts_executed,
+            stmts,
+            percentage(stmts_executed, stmts),
+            fns.iter().map(|f| f.blocks_executed).sum::<usize>(),
+            fns.iter().map(|f| f.blocks).sum::<usize>(),
+        )?;
+        for f in &fns {
+            writeln!(
+                out,
+                "{:5.1}%  {}/{} statements  {}/{} blocks  {}",
+                f.percentage(),
+                f.stmts_executed,
+                f.stmts,
+                f.blocks_executed,
+                f.blocks,
+                f.path,
+            )?;
+        }
+        for f in &fns {
+            writeln!(out, "\n{}", f.path)?;
+            let executed = self.executed.get(&f.def_id).unwrap_or(&no_statements);
+            let mir = tcx.optimized_mir(f.def_id);
+            for (bb, data) in mir.basic_blocks().iter_enumerated() {
+                for (i, stmt) in data.statements.iter().enumerate() {
+                    let location = format!("{:?}[{}]", bb, i);
+                    write_line(&mut out, tcx, executed.contains(&(bb, i)), &location, stmt.source_info.span, stmt)?;
+                }
+                let terminator = data.terminator();
+                let location = format!("{:?}[term]", bb);
+                let ran = executed.contains(&(bb, data.statements.len()));
+                write_line(&mut out, tcx, ran, &location, terminator.source_info.span, &terminator.kind)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+fn write_line<'a, 'tcx: 'a, W: Write, T: ::std::fmt::Debug>(
+    out: &mut W,
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    executed: bool,
+    location: &str,
+    span: Span,
+    what: &T,
+) -> io::Result<()> {
+    let (file, line, ..) = rustc_compat::span_lines(tcx, span);
+    writeln!(
+        out,
+        "{} {:10} {}:{}  {:?}",
+        if executed { '+' } else { '-' },
+        location,
+        file,
+        line,
+        what,
+    )
+}
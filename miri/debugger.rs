@@ -0,0 +1,275 @@
+//! An interactive debugger (`--debug`): the evaluation stops before the first statement and
+//! whenever the user asks it to, and reads commands from stdin. The debugger talks on stderr, so
+//! the program's own output stays separate.
+
+use std::io::{self, BufRead, Write};
+
+use rustc::mir;
+
+use super::*;
+use host::EvalContextExt as HostEvalContextExt;
+use inspect::{EvalContextExt as InspectEvalContextExt, LocalInfo};
+use rustc_compat;
+
+const HELP: &str = "\
+commands:
+    step, s              run until the next statement, entering calls
+    next, n              run until the next statement of this function, stepping over calls
+    continue, c          run until the next breakpoint or the end of the program
+    break, b FUNCTION    stop when FUNCTION (e.g. `mycrate::module::func`) is called
+    break, b FILE:LINE   stop when a statement on LINE of FILE (e.g. `src/lib.rs:42`) is reached
+    breakpoints          list the breakpoints
+    delete, d N          delete breakpoint N
+    backtrace, bt        print the call stack
+    print, p LOCAL       print a local of the current function, by name or as `_N`
+    locals               print the arguments and variables of the current function
+    memory, m ALLOC      print the bytes of an allocation
+    graph FILE           write the allocations and pointers as a Graphviz graph to FILE
+    quit, q              stop the program
+    help, h              print this message";
+
+/// When to stop next
+#[derive(Copy, Clone, Debug)]
+enum Mode {
+    /// Before the next step
+    Step,
+    /// Before the next step with at most this many frames on the stack
+    Next(usize),
+    /// At the next breakpoint
+    Continue,
+}
+
+#[derive(Clone, Debug)]
+enum Breakpoint {
+    /// The path of a function, stops at its first statement
+    Function(String),
+    /// A file and line, stops at the first statement on that line
+    Line(String, usize),
+}
+
+pub struct Debugger {
+    mode: Mode,
+    breakpoints: Vec<Breakpoint>,
+    /// The file and line of the previous step, a line breakpoint only stops when entering the line
+    previous_line: Option<(String, usize)>,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        // Stop before the first statement, to let the user set things up
+        Debugger {
+            mode: Mode::Step,
+            breakpoints: Vec::new(),
+            previous_line: None,
+        }
+    }
+}
+
+/// Called before every step, stops if the user asked for it and runs their commands
+pub(crate) fn before_step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) -> EvalResult<'tcx> {
+    let mut debugger = match ecx.machine.debugger.take() {
+        Some(debugger) => debugger,
+        None => return Ok(()),
+    };
+    let depth = ecx.stack().len();
+    let breakpoint = hit_breakpoint(ecx, &mut debugger);
+    if let Some(i) = breakpoint {
+        eprintln!("breakpoint {} hit", i);
+    }
+    let stop = match debugger.mode {
+        _ if breakpoint.is_some() => true,
+        Mode::Step => true,
+        Mode::Next(max_depth) => depth <= max_depth,
+        Mode::Continue => false,
+    };
+    let result = if stop && depth > 0 {
+        print_location(ecx);
+        prompt(ecx, &mut debugger)
+    } else {
+        Ok(())
+    };
+    ecx.machine.debugger = Some(debugger);
+    result
+}
+
+/// The index of the breakpoint the next step is at, if any
+fn hit_breakpoint<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, debugger: &mut Debugger) -> Option<usize> {
+    if debugger.breakpoints.is_empty() {
+        return None;
+    }
+    let frame = ecx.stack().last()?;
+    let line = {
+        let (file, line, ..) = rustc_compat::span_lines(ecx.tcx, rustc_compat::frame_span(frame));
+        (file, line)
+    };
+    let entered_line = debugger.previous_line.as_ref() != Some(&line);
+    let entered_fn = frame.block == mir::START_BLOCK && frame.stmt == 0;
+    let hit = debugger.breakpoints.iter().position(|breakpoint| match *breakpoint {
+        Breakpoint::Function(ref path) => {
+            entered_fn && paths_match(&rustc_compat::frame_fn_path(ecx.tcx, frame), path)
+        }
+        Breakpoint::Line(ref file, number) => entered_line && number == line.1 && paths_match(&line.0, file),
+    });
+    debugger.previous_line = Some(line);
+    hit
+}
+
+/// Whether one of the paths is a suffix of the other one, e.g. `mycrate::module::func` and
+/// `module::func` or `/home/me/mycrate/src/lib.rs` and `src/lib.rs`
+fn paths_match(a: &str, b: &str) -> bool {
+    let suffix_of = |long: &str, short: &str| {
+        long.ends_with(short) && match long[..long.len() - short.len()].chars().last() {
+            None | Some(':') | Some('/') | Some('\\') => true,
+            Some(_) => false,
+        }
+    };
+    suffix_of(a, b) || suffix_of(b, a)
+}
+
+fn parse_breakpoint(arg: &str) -> Breakpoint {
+    // A `:` that is not part of a `::` separates the line
+    if let Some(colon) = arg.rfind(':') {
+        if !arg[..colon].ends_with(':') {
+            if let Ok(line) = arg[colon + 1..].parse() {
+                return Breakpoint::Line(arg[..colon].to_owned(), line);
+            }
+        }
+    }
+    Breakpoint::Function(arg.to_owned())
+}
+
+/// Reads and runs commands until one of them resumes the evaluation
+fn prompt<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>, debugger: &mut Debugger) -> EvalResult<'tcx> {
+    let stdin = io::stdin();
+    loop {
+        eprint!("(miri) ");
+        io::stderr().flush().ok();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            // No more commands, let the program run to the end
+            debugger.mode = Mode::Continue;
+            return Ok(());
+        }
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        let arg = words.next();
+        match command {
+            "step" | "s" => {
+                debugger.mode = Mode::Step;
+                return Ok(());
+            }
+            "next" | "n" => {
+                debugger.mode = Mode::Next(ecx.stack().len());
+                return Ok(());
+            }
+            "continue" | "c" => {
+                debugger.mode = Mode::Continue;
+                return Ok(());
+            }
+            "backtrace" | "bt" => print_backtrace(ecx),
+            "print" | "p" => match arg {
+                Some(name) => print_local(ecx, name),
+                None => eprintln!("print needs the name of a local"),
+            },
+            "locals" => print_locals(ecx),
+            "memory" | "m" => match arg.and_then(|id| id.parse().ok()) {
+                Some(alloc_id) => print_allocation(ecx, alloc_id),
+                None => eprintln!("memory needs the id of an allocation"),
+            },
+            "graph" => match arg {
+                Some(file) => match heap_graph::write_heap_graph(ecx, file) {
+                    Ok(()) => eprintln!("heap graph written to {}", file),
+                    Err(e) => eprintln!("could not write {}: {}", file, e),
+                },
+                None => eprintln!("graph needs the file to write to"),
+            },
+            "break" | "b" => match arg {
+                Some(arg) => {
+                    debugger.breakpoints.push(parse_breakpoint(arg));
+                    eprintln!("breakpoint {} set", debugger.breakpoints.len() - 1);
+                }
+                None => eprintln!("break needs a function path or FILE:LINE"),
+            },
+            "breakpoints" => {
+                for (i, breakpoint) in debugger.breakpoints.iter().enumerate() {
+                    match *breakpoint {
+                        Breakpoint::Function(ref path) => eprintln!("breakpoint {}: {}", i, path),
+                        Breakpoint::Line(ref file, line) => eprintln!("breakpoint {}: {}:{}", i, file, line),
+                    }
+                }
+            }
+            "delete" | "d" => match arg.and_then(|i| i.parse().ok()) {
+                Some(i) if i < debugger.breakpoints.len() => {
+                    debugger.breakpoints.remove(i);
+                }
+                _ => eprintln!("delete needs the number of a breakpoint"),
+            },
+            "quit" | "q" => {
+                return Err(MiriError::StoppedByDebugger.into());
+            }
+            "help" | "h" => eprintln!("{}", HELP),
+            _ => eprintln!("unknown command `{}`, try `help`", command),
+        }
+    }
+}
+
+fn print_location<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    let frame = ecx.frame();
+    eprintln!(
+        "stopped in {} at {}",
+        rustc_compat::frame_fn_path(ecx.tcx, frame),
+        rustc_compat::span_to_string(ecx.tcx, rustc_compat::frame_span(frame)),
+    );
+}
+
+fn print_backtrace<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    for (i, frame) in ecx.stack().iter().enumerate().rev() {
+        eprintln!(
+            "frame {}: {} at {}",
+            i,
+            rustc_compat::frame_fn_path(ecx.tcx, frame),
+            rustc_compat::span_to_string(ecx.tcx, rustc_compat::frame_span(frame)),
+        );
+    }
+}
+
+fn print_local<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, name: &str) {
+    let frame = ecx.stack().len() - 1;
+    match ecx.find_local(frame, name) {
+        Some(local) => print_local_info(ecx, local),
+        None => eprintln!("there is no local `{}` in this function", name),
+    }
+}
+
+/// Prints the arguments and variables of the current function, temporaries have no name
+fn print_locals<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    let frame = ecx.stack().len() - 1;
+    for local in ecx.frame_locals(frame) {
+        if local.name.is_some() {
+            print_local_info(ecx, local);
+        }
+    }
+}
+
+fn print_local_info<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, local: LocalInfo<'tcx>) {
+    let name = local.display_name();
+    let value = match local.value {
+        Some(value) => value,
+        None => {
+            eprintln!("{}: {} is dead", name, local.ty);
+            return;
+        }
+    };
+    match ecx.value_to_host(value, local.ty) {
+        Ok(host) => eprintln!("{}: {} = {:?}", name, local.ty, host),
+        // Fall back to the raw value, e.g. for trait objects
+        Err(_) => eprintln!("{}: {} = {:?}", name, local.ty, value),
+    }
+}
+
+fn print_allocation<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, alloc_id: u64) {
+    dump::dump_alloc(ecx, &mut io::stderr(), AllocId(alloc_id), None).ok();
+}
@@ -0,0 +1,115 @@
+//! Turns pointers into something users recognize: the local variable, field or element they point
+//! to, instead of an allocation id.
+
+use rustc::ty;
+use rustc::ty::layout::{LayoutOf, TyLayout};
+
+use super::*;
+use rustc_compat;
+
+pub trait EvalContextExt<'tcx> {
+    /// Describes what `ptr` points to, e.g. "local `v`.len of `foo` (frame #2)"
+    fn describe_ptr(&self, ptr: MemoryPointer) -> String;
+
+    /// Describes the path from a value of the given layout to the field or element at `offset`,
+    /// e.g. `.buf[3]`
+    fn describe_offset(&self, layout: TyLayout<'tcx>, offset: u64) -> EvalResult<'tcx, String>;
+}
+
+impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'tcx>> {
+    fn describe_ptr(&self, ptr: MemoryPointer) -> String {
+        // Innermost frames first, locals of outer frames are unlikely to be what we look for
+        for (frame_idx, frame) in self.stack().iter().enumerate().rev() {
+            for local in frame.mir.local_decls.indices() {
+                let local_ptr = match frame.get_local(local) {
+                    Ok(Value::ByRef(PtrAndAlign { ptr, .. })) => ptr,
+                    _ => continue,
+                };
+                match local_ptr.into_inner_primval() {
+                    PrimVal::Ptr(local_ptr) if local_ptr.alloc_id == ptr.alloc_id => {}
+                    _ => continue,
+                }
+                let name = match rustc_compat::local_name(frame.mir, local) {
+                    Some(name) => format!("local `{}`", name),
+                    None => format!("temporary `{:?}`", local),
+                };
+                let ty = self.monomorphize(frame.mir.local_decls[local].ty, frame.instance.substs);
+                let path = self.layout_of(ty)
+                    .and_then(|layout| self.describe_offset(layout, ptr.offset))
+                    .unwrap_or_default();
+                return format!(
+                    "{}{} of `{}` (frame #{})",
+                    name,
+                    path,
+                    rustc_compat::frame_fn_path(self.tcx, frame),
+                    frame_idx,
+                );
+            }
+        }
+        if let Ok(instance) = self.memory.get_fn(ptr) {
+            return format!("function `{}`", instance);
+        }
+        format!("offset {} of allocation {}", ptr.offset, ptr.alloc_id.0)
+    }
+
+    fn describe_offset(&self, mut layout: TyLayout<'tcx>, mut offset: u64) -> EvalResult<'tcx, String> {
+        let mut path = String::new();
+        loop {
+            match layout.ty.sty {
+                ty::TyArray(elem_ty, _) | ty::TySlice(elem_ty) => {
+                    let elem_layout = self.layout_of(elem_ty)?;
+                    let elem_size = elem_layout.size.bytes();
+                    if elem_size == 0 {
+                        break;
+                    }
+                    let idx = offset / elem_size;
+                    path.push_str(&format!("[{}]", idx));
+                    offset -= idx * elem_size;
+                    layout = elem_layout;
+                }
+                ty::TyAdt(adt_def, _) if adt_def.is_struct() => {
+                    match find_field(self, layout, offset)? {
+                        Some((idx, field_offset, field_layout)) => {
+                            let name = adt_def.struct_variant().fields[idx].name;
+                            path.push_str(&format!(".{}", name));
+                            offset -= field_offset;
+                            layout = field_layout;
+                        }
+                        None => break,
+                    }
+                }
+                ty::TyTuple(..) | ty::TyClosure(..) => {
+                    match find_field(self, layout, offset)? {
+                        Some((idx, field_offset, field_layout)) => {
+                            path.push_str(&format!(".{}", idx));
+                            offset -= field_offset;
+                            layout = field_layout;
+                        }
+                        None => break,
+                    }
+                }
+                _ => break,
+            }
+        }
+        if offset != 0 {
+            path.push_str(&format!(" (+{} bytes)", offset));
+        }
+        Ok(path)
+    }
+}
+
+/// Finds the field of `layout` containing the byte at `offset`
+fn find_field<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, super::Evaluator<'tcx>>,
+    layout: TyLayout<'tcx>,
+    offset: u64,
+) -> EvalResult<'tcx, Option<(usize, u64, TyLayout<'tcx>)>> {
+    for idx in 0..layout.fields.count() {
+        let field_offset = layout.fields.offset(idx).bytes();
+        let field_layout = layout.field(ecx, idx)?;
+        if field_offset <= offset && offset < field_offset + field_layout.size.bytes() {
+            return Ok(Some((idx, field_offset, field_layout)));
+        }
+    }
+    Ok(None)
+}
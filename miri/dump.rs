@@ -0,0 +1,143 @@
+//! Dumps of allocations for embedders and the debugger. Unlike the compiler's `Memory::dump`,
+//! which prints to stdout, the dump is written to any `io::Write`, and with the type of the
+//! allocation's contents every field is shown on a line of its own:
+//!
+//! ```text
+//! alloc 12 (16 bytes, aligned to 8)
+//!     0..8    .0        &u8    [alloc 7: 00 00 00 00 00 00 00 00]
+//!     8..9    .1        bool   01
+//!     9..16   padding          __ __ __ __ __ __ __
+//! ```
+
+use std::io::{self, Write};
+
+use rustc::ty::{self, Ty};
+use rustc::ty::layout::{LayoutOf, TyLayout};
+
+use super::*;
+
+/// Arrays longer than this are shown as their first elements and one line for the rest
+const MAX_ELEMENTS: usize = 16;
+
+/// Writes the allocation `alloc_id` to `out`. With `ty`, the type stored at the start of the
+/// allocation, the bytes are split at the boundaries of its fields.
+pub fn dump_alloc<'a, 'tcx, W: Write>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    out: &mut W,
+    alloc_id: AllocId,
+    ty: Option<Ty<'tcx>>,
+) -> io::Result<()> {
+    let alloc = match ecx.memory.get(alloc_id) {
+        Ok(alloc) => alloc,
+        Err(e) => return writeln!(out, "alloc {}: {}", alloc_id.0, e),
+    };
+    let size = alloc.bytes.len() as u64;
+    let pointer_size = ecx.memory.pointer_size();
+    writeln!(out, "alloc {} ({} bytes, aligned to {})", alloc_id.0, size, alloc.align)?;
+    let layout = match ty.map(|ty| ecx.layout_of(ty)) {
+        Some(Ok(layout)) => layout,
+        Some(Err(e)) => {
+            writeln!(out, "    cannot lay out the type: {}", e)?;
+            return writeln!(out, "    {}", format_range(alloc, 0, size, pointer_size));
+        }
+        None => return writeln!(out, "    {}", format_range(alloc, 0, size, pointer_size)),
+    };
+    let mut fields = Vec::new();
+    collect_fields(ecx, layout, 0, String::new(), &mut fields);
+    let mut end = 0;
+    for (start, field_size, path, ty) in fields {
+        if start > end {
+            write_line(out, alloc, pointer_size, end, start, "padding", "")?;
+        }
+        let field_end = (start + field_size).min(size);
+        write_line(out, alloc, pointer_size, start, field_end, &path, &ty.to_string())?;
+        end = field_end;
+    }
+    if end < size {
+        write_line(out, alloc, pointer_size, end, size, "padding", "")?;
+    }
+    Ok(())
+}
+
+/// Collects the fields of `layout` that are not split further, as (offset, size, path, type)
+fn collect_fields<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    layout: TyLayout<'tcx>,
+    offset: u64,
+    path: String,
+    fields: &mut Vec<(u64, u64, String, Ty<'tcx>)>,
+) {
+    // Enums would need their discriminant to pick the variant, pointers and boxes are scalars
+    let leaf = match layout.ty.sty {
+        ty::TyAdt(adt, _) => adt.is_enum() || adt.is_box(),
+        _ => layout.fields.count() == 0,
+    };
+    if leaf || layout.fields.count() == 0 {
+        let path = if path.is_empty() { ".".to_owned() } else { path };
+        fields.push((offset, layout.size.bytes(), path, layout.ty));
+        return;
+    }
+    let count = layout.fields.count();
+    let shown = match layout.ty.sty {
+        ty::TyArray(..) => count.min(MAX_ELEMENTS),
+        _ => count,
+    };
+    for i in 0..shown {
+        let field = match layout.field(ecx, i) {
+            Ok(field) => field,
+            Err(_) => continue,
+        };
+        let name = match layout.ty.sty {
+            ty::TyAdt(adt, _) => format!(".{}", adt.variants[0].fields[i].name),
+            ty::TyArray(..) => format!("[{}]", i),
+            _ => format!(".{}", i),
+        };
+        collect_fields(ecx, field, offset + layout.fields.offset(i).bytes(), format!("{}{}", path, name), fields);
+    }
+    if shown < count {
+        // The remaining elements, in one piece
+        let start = layout.fields.offset(shown).bytes();
+        fields.push((offset + start, layout.size.bytes() - start, format!("{}[{}..]", path, shown), layout.ty));
+    }
+    fields.sort_by_key(|&(offset, ..)| offset);
+}
+
+fn write_line<W: Write>(
+    out: &mut W,
+    alloc: &Allocation,
+    pointer_size: u64,
+    start: u64,
+    end: u64,
+    path: &str,
+    ty: &str,
+) -> io::Result<()> {
+    let range = format!("{}..{}", start, end);
+    writeln!(out, "    {:<8}{:<10}{:<7}{}", range, path, ty, format_range(alloc, start, end, pointer_size))
+}
+
+/// Formats the bytes `start..end` of an allocation in hex, undefined bytes are shown as `__`.
+/// The bytes of a pointer (the offset it points to) are prefixed with the allocation it points
+/// into, e.g. `[alloc 7: 08 00 00 00 00 00 00 00]`.
+fn format_range(alloc: &Allocation, start: u64, end: u64, pointer_size: u64) -> String {
+    let mut parts = Vec::new();
+    let mut offset = start;
+    while offset < end {
+        if let Some(target) = alloc.relocations.get(&offset) {
+            let ptr_end = (offset + pointer_size).min(end);
+            let bytes = (offset..ptr_end)
+                .map(|i| format!("{:02x}", alloc.bytes[i as usize]))
+                .collect::<Vec<_>>()
+                .join(" ");
+            parts.push(format!("[alloc {}: {}]", target.0, bytes));
+            offset = ptr_end;
+        } else {
+            if alloc.undef_mask.is_range_defined(offset, offset + 1) {
+                parts.push(format!("{:02x}", alloc.bytes[offset as usize]));
+            } else {
+                parts.push("__".to_owned());
+            }
+            offset += 1;
+        }
+    }
+    parts.join(" ")
+}
@@ -0,0 +1,51 @@
+//! Errors of miri itself, which have no variant in rustc's `EvalErrorKind`. They are raised as
+//! `EvalErrorKind::MachineError`, and `MiriError::of` tells them apart again, so nothing needs to
+//! recognize them by their message.
+
+use std::error::Error;
+use std::fmt;
+
+use super::*;
+
+#[derive(Clone, Debug)]
+pub enum MiriError {
+    /// The program exceeded a limit checked by miri, the message names what was exceeded
+    LimitReached(Limit, String),
+    /// The program's global allocator could not allocate and called `__rust_oom`, which aborts
+    OutOfMemoryAbort,
+    /// The user stopped the evaluation with `quit` in the debugger, this is not an error of the
+    /// program
+    StoppedByDebugger,
+}
+
+impl MiriError {
+    /// The miri error `e` is, if it is one
+    pub fn of<'a>(e: &'a EvalError) -> Option<&'a MiriError> {
+        match e.kind {
+            EvalErrorKind::MachineError(ref inner) => inner.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl<'tcx> From<MiriError> for EvalError<'tcx> {
+    fn from(e: MiriError) -> Self {
+        EvalErrorKind::MachineError(Box::new(e)).into()
+    }
+}
+
+impl Error for MiriError {
+    fn description(&self) -> &str {
+        match *self {
+            MiriError::LimitReached(_, ref msg) => msg,
+            MiriError::OutOfMemoryAbort => "the evaluated program aborted: out of memory",
+            MiriError::StoppedByDebugger => "the evaluation was stopped by the debugger",
+        }
+    }
+}
+
+impl fmt::Display for MiriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
@@ -7,15 +7,20 @@ use syntax::abi::Abi;
 use syntax::codemap::Span;
 
 use std::mem;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use rustc::traits;
 
 use super::*;
 
 use tls::MemoryExt;
+use fs::{self, EvalContextExt as FsEvalContextExt};
 
 use super::memory::MemoryKind;
 
+/// How far the virtual clock advances with every step of the program
+const NANOS_PER_STEP: u64 = 1000;
+
 pub trait EvalContextExt<'tcx> {
     fn call_c_abi(
         &mut self,
@@ -28,6 +33,23 @@ pub trait EvalContextExt<'tcx> {
 
     fn resolve_path(&self, path: &[&str]) -> EvalResult<'tcx, ty::Instance<'tcx>>;
 
+    fn resolve_model_fn(&self, path: &str) -> EvalResult<'tcx, Option<ty::Instance<'tcx>>>;
+
+    fn eval_path_scalar(&mut self, path: &[&str], ty: Ty<'tcx>) -> EvalResult<'tcx, Option<u128>>;
+
+    /// Records a change of the heap size in the summary
+    fn track_heap(&mut self, delta: i64);
+
+    /// The current time of the given clock as (seconds, nanoseconds). Unless host time is
+    /// requested, this is a virtual clock advancing with every step of the program.
+    fn current_time(&mut self, realtime: bool) -> (u64, u32);
+
+    /// The next output of the pseudo-random generator seeded with the configured seed
+    fn next_random(&mut self) -> u64;
+
+    /// Fills `len` bytes at `ptr` with pseudo-random bytes
+    fn write_random_bytes(&mut self, ptr: Pointer, len: u64) -> EvalResult<'tcx>;
+
     fn call_missing_fn(
         &mut self,
         instance: ty::Instance<'tcx>,
@@ -60,21 +82,60 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
     ) -> EvalResult<'tcx, bool> {
         trace!("eval_fn_call: {:#?}, {:#?}", instance, destination);
 
-        let mir = match self.load_mir(instance.def) {
-            Ok(mir) => mir,
+        // Programs may provide their own (weak) `miri_under_interpreter` for native builds.
+        // Under miri we always answer the call ourselves.
+        if let ty::InstanceDef::Item(def_id) = instance.def {
+            // The name is cheap to compare, the attributes are only looked at for a match
+            if self.tcx.item_name(def_id) == "miri_under_interpreter" &&
+                attr::contains_name(&self.tcx.get_attrs(def_id), "no_mangle")
+            {
+                if let Some((dest, block)) = destination {
+                    self.call_c_abi(def_id, args, dest, sig.output(), block)?;
+                    return Ok(true);
+                }
+            }
+        }
+
+        let start = timings::start(&self.memory.data);
+        let loaded = self.load_mir(instance.def);
+        timings::stop(&self.memory.data, timings::Category::MirLoading, start);
+        let (instance, mir) = match loaded {
+            Ok(mir) => (instance, mir),
             Err(EvalError { kind: EvalErrorKind::NoMirFor(path), .. }) => {
-                self.call_missing_fn(
-                    instance,
-                    destination,
-                    args,
-                    sig,
-                    path,
-                )?;
-                return Ok(true);
+                // A model of the function takes precedence over our own emulation
+                if let Some(model) = self.resolve_model_fn(&path)? {
+                    let mir = self.load_mir(model.def)?;
+                    (model, mir)
+                } else {
+                    let start = timings::start(&self.memory.data);
+                    let result = self.call_missing_fn(
+                        instance,
+                        destination,
+                        args,
+                        sig,
+                        path,
+                    );
+                    timings::stop(&self.memory.data, timings::Category::ForeignFns, start);
+                    result?;
+                    return Ok(true);
+                }
             }
             Err(other) => return Err(other),
         };
 
+        if let Some(max_calls) = self.machine.config.max_calls_per_fn {
+            let def_id = instance.def_id();
+            let calls = self.machine.call_counts.entry(def_id).or_insert(0);
+            *calls += 1;
+            if *calls > max_calls {
+                return Err(MiriError::LimitReached(Limit::CallsPerFn, format!(
+                    "reached the configured maximum number of calls ({}) of `{}`",
+                    max_calls,
+                    self.tcx.item_path_str(def_id),
+                )).into());
+            }
+        }
+
         let (return_place, return_to_block) = match destination {
             Some((place, block)) => (place, StackPopCleanup::Goto(block)),
             None => (Place::undef(), StackPopCleanup::None),
@@ -104,6 +165,19 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
             Some(name) => name.as_str(),
             None => self.tcx.item_name(def_id),
         };
+        if let Some(ref mut summary) = self.machine.summary {
+            summary.foreign_fn(&link_name);
+        }
+
+        if let Some(shim) = self.machine.config.shims.get(&link_name) {
+            self.machine.loop_detector.foreign_shim_called = true;
+            if let Some(value) = shim(self, args)? {
+                self.write_primval(dest, value, dest_ty)?;
+            }
+            self.dump_local(dest);
+            self.goto_block(dest_block);
+            return Ok(());
+        }
 
         match &link_name[..] {
             "malloc" => {
@@ -112,19 +186,92 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     self.write_null(dest, dest_ty)?;
                 } else {
                     let align = self.memory.pointer_size();
-                    let ptr = self.memory.allocate(size, align, Some(MemoryKind::C.into()))?;
-                    self.write_primval(dest, PrimVal::Ptr(ptr), dest_ty)?;
+                    let ptr = guest_allocation(self.memory.allocate(size, align, Some(MemoryKind::C.into())))?;
+                    if let PrimVal::Ptr(ptr) = ptr {
+                        undef::record_allocation(self, ptr, size);
+                        self.track_heap(size as i64);
+                    }
+                    self.write_primval(dest, ptr, dest_ty)?;
+                }
+            }
+
+            "calloc" => {
+                let count = self.value_to_primval(args[0])?.to_u64()?;
+                let elem_size = self.value_to_primval(args[1])?.to_u64()?;
+                match count.checked_mul(elem_size) {
+                    Some(0) => self.write_null(dest, dest_ty)?,
+                    Some(size) => {
+                        let align = self.memory.pointer_size();
+                        let ptr = guest_allocation(self.memory.allocate(size, align, Some(MemoryKind::C.into())))?;
+                        if let PrimVal::Ptr(ptr) = ptr {
+                            self.memory.write_repeat(ptr.into(), 0, size)?;
+                            self.track_heap(size as i64);
+                        }
+                        self.write_primval(dest, ptr, dest_ty)?;
+                    }
+                    // Nothing could be that large
+                    None => self.write_null(dest, dest_ty)?,
+                }
+            }
+
+            "realloc" => {
+                let ptr = self.into_ptr(args[0].value)?;
+                let new_size = self.value_to_primval(args[1])?.to_u64()?;
+                let align = self.memory.pointer_size();
+                if ptr.is_null()? {
+                    // Like `malloc`
+                    if new_size == 0 {
+                        self.write_null(dest, dest_ty)?;
+                    } else {
+                        let new_ptr = guest_allocation(self.memory.allocate(new_size, align, Some(MemoryKind::C.into())))?;
+                        if let PrimVal::Ptr(new_ptr) = new_ptr {
+                            undef::record_allocation(self, new_ptr, new_size);
+                            self.track_heap(new_size as i64);
+                        }
+                        self.write_primval(dest, new_ptr, dest_ty)?;
+                    }
+                } else {
+                    let ptr = ptr.to_ptr()?;
+                    let old_size = self.memory.get(ptr.alloc_id)?.bytes.len() as u64;
+                    if new_size == 0 {
+                        // Like `free`
+                        self.memory.deallocate(ptr, None, MemoryKind::C.into())?;
+                        self.track_heap(-(old_size as i64));
+                        self.write_null(dest, dest_ty)?;
+                    } else {
+                        // On failure, the old allocation stays as it is
+                        let new_ptr = guest_allocation(self.memory.reallocate(
+                            ptr,
+                            old_size,
+                            align,
+                            new_size,
+                            align,
+                            MemoryKind::C.into(),
+                        ))?;
+                        if let PrimVal::Ptr(new_ptr) = new_ptr {
+                            if new_size > old_size {
+                                let grown = new_ptr.offset(old_size, &self)?;
+                                undef::record_allocation(self, grown, new_size - old_size);
+                            }
+                            self.track_heap(new_size as i64 - old_size as i64);
+                        }
+                        self.write_primval(dest, new_ptr, dest_ty)?;
+                    }
                 }
             }
 
             "free" => {
                 let ptr = self.into_ptr(args[0].value)?;
                 if !ptr.is_null()? {
+                    let ptr = ptr.to_ptr()?;
+                    let size = self.memory.get(ptr.alloc_id).map(|alloc| alloc.bytes.len() as u64);
                     self.memory.deallocate(
-                        ptr.to_ptr()?,
+                        ptr,
                         None,
                         MemoryKind::C.into(),
                     )?;
+                    // `deallocate` succeeded, so the allocation existed
+                    self.track_heap(-(size.unwrap() as i64));
                 }
             }
 
@@ -136,9 +283,10 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 // is called if a `HashMap` is created the regular way.
                 match self.value_to_primval(args[0])?.to_u64()? {
                     318 | 511 => {
-                        return err!(Unimplemented(
-                            "miri does not support random number generators".to_owned(),
-                        ))
+                        let ptr = self.into_ptr(args[1].value)?;
+                        let len = self.value_to_primval(args[2])?.to_u64()?;
+                        self.write_random_bytes(ptr, len)?;
+                        self.write_primval(dest, PrimVal::Bytes(len as u128), dest_ty)?;
                     }
                     id => {
                         return err!(Unimplemented(
@@ -148,6 +296,39 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 }
             }
 
+            "SecRandomCopyBytes" => {
+                let len = self.value_to_primval(args[1])?.to_u64()?;
+                let ptr = self.into_ptr(args[2].value)?;
+                self.write_random_bytes(ptr, len)?;
+                self.write_null(dest, dest_ty)?;
+            }
+
+            "getentropy" => {
+                let ptr = self.into_ptr(args[0].value)?;
+                let len = self.value_to_primval(args[1])?.to_u64()?;
+                self.write_random_bytes(ptr, len)?;
+                self.write_null(dest, dest_ty)?;
+            }
+
+            "arc4random_buf" => {
+                let ptr = self.into_ptr(args[0].value)?;
+                let len = self.value_to_primval(args[1])?.to_u64()?;
+                self.write_random_bytes(ptr, len)?;
+            }
+
+            "arc4random" => {
+                let random = self.next_random() as u32;
+                self.write_primval(dest, PrimVal::Bytes(random as u128), dest_ty)?;
+            }
+
+            // `RtlGenRandom`
+            "SystemFunction036" => {
+                let ptr = self.into_ptr(args[0].value)?;
+                let len = self.value_to_primval(args[1])?.to_u64()?;
+                self.write_random_bytes(ptr, len)?;
+                self.write_primval(dest, PrimVal::Bytes(1), dest_ty)?;
+            }
+
             "dlsym" => {
                 let _handle = self.into_ptr(args[0].value)?;
                 let symbol = self.into_ptr(args[1].value)?.to_ptr()?;
@@ -199,10 +380,25 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 return Ok(());
             }
 
+            "miri_under_interpreter" => {
+                self.write_primval(dest, PrimVal::from_bool(true), dest_ty)?;
+            }
+
             "__rust_start_panic" => {
                 return err!(Panic);
             }
 
+            "exit" | "_exit" => {
+                let code = self.value_to_primval(args[0])?.to_i128()? as i32;
+                trace!("Called exit({})", code);
+                self.machine.exit_code = Some(code);
+                // Unwind the entire stack, no more code of the program gets to run
+                while !self.stack().is_empty() {
+                    self.pop_stack_frame()?;
+                }
+                return Ok(());
+            }
+
             "memcmp" => {
                 let left = self.into_ptr(args[0].value)?;
                 let right = self.into_ptr(args[1].value)?;
@@ -333,29 +529,321 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 trace!("Called write({:?}, {:?}, {:?})", fd, buf, n);
                 let result = if fd == 1 || fd == 2 {
                     // stdout/stderr
-                    use std::io::{self, Write};
-
+                    let short_writes = self.machine.config.short_writes;
                     let buf_cont = self.memory.read_bytes(buf, n)?;
-                    let res = if fd == 1 {
-                        io::stdout().write(buf_cont)
-                    } else {
-                        io::stderr().write(buf_cont)
-                    };
-                    match res {
-                        Ok(n) => n as isize,
-                        Err(_) => -1,
-                    }
+                    write_to_host(fd, buf_cont, short_writes)
+                } else if let Some(written) = self.write_file(fd as i32, buf, n)? {
+                    written as isize
                 } else {
                     warn!("Ignored output to FD {}", fd);
                     n as isize // pretend it all went well
                 }; // now result is the value we return back to the program
                 self.write_primval(
                     dest,
-                    PrimVal::Bytes(result as u128),
+                    PrimVal::from_i128(result as i128),
                     dest_ty,
                 )?;
             }
 
+            "open" | "open64" => {
+                let path = self.into_ptr(args[0].value)?;
+                let fd = self.open(path, args[1])?;
+                self.write_primval(dest, PrimVal::from_i128(fd as i128), dest_ty)?;
+            }
+
+            "read" => {
+                let fd = self.value_to_primval(args[0])?.to_i128()?;
+                let buf = self.into_ptr(args[1].value)?;
+                let count = self.value_to_primval(args[2])?.to_u64()?;
+                let result = self.read(fd as i32, buf, count)?;
+                self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+            }
+
+            "close" => {
+                let fd = self.value_to_primval(args[0])?.to_i128()?;
+                let result = self.close(fd as i32)?;
+                self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+            }
+
+            "stat" | "stat64" => {
+                let path = self.into_ptr(args[0].value)?;
+                let buf = self.into_ptr(args[1].value)?;
+                let result = self.stat(Ok(path), buf, &link_name)?;
+                self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+            }
+
+            "fstat" | "fstat64" => {
+                let fd = self.value_to_primval(args[0])?.to_i128()?;
+                let buf = self.into_ptr(args[1].value)?;
+                let result = self.stat(Err(fd as i32), buf, &link_name[1..])?;
+                self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+            }
+
+            "unlink" => {
+                let path = self.into_ptr(args[0].value)?;
+                let result = self.unlink(path)?;
+                self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+            }
+
+            "__errno_location" | "__error" => {
+                let errno = self.errno_ptr()?;
+                self.write_primval(dest, PrimVal::Ptr(errno), dest_ty)?;
+            }
+
+            "writev" => {
+                let fd = self.value_to_primval(args[0])?.to_u64()?;
+                let iov = self.into_ptr(args[1].value)?.to_ptr()?;
+                let iovcnt = self.value_to_primval(args[2])?.to_i128()?;
+                trace!("Called writev({:?}, {:?}, {:?})", fd, iov, iovcnt);
+                if iovcnt < 0 {
+                    return err!(AbiViolation(format!("writev called with iovcnt {}", iovcnt)));
+                }
+                let ptr_size = self.memory.pointer_size();
+                let short_writes = self.machine.config.short_writes;
+                let mut result = 0isize;
+                for i in 0..iovcnt as u64 {
+                    // struct iovec { void *iov_base; size_t iov_len; }
+                    let base_ptr = iov.offset(2 * i * ptr_size, &self)?;
+                    let len_ptr = base_ptr.offset(ptr_size, &self)?;
+                    let base = self.memory.read_ptr_sized_unsigned(base_ptr)?;
+                    let len = self.memory.read_ptr_sized_unsigned(len_ptr)?.to_u64()?;
+                    let written = if fd == 1 || fd == 2 {
+                        let buf_cont = self.memory.read_bytes(Pointer::from(base), len)?;
+                        write_to_host(fd, buf_cont, short_writes)
+                    } else {
+                        warn!("Ignored output to FD {}", fd);
+                        len as isize // pretend it all went well
+                    };
+                    if written < 0 {
+                        // Only report the error if nothing was written so far
+                        if result == 0 {
+                            result = -1;
+                        }
+                        break;
+                    }
+                    result += written;
+                    if written as u64 != len {
+                        // A partial write ends the entire `writev`
+                        break;
+                    }
+                }
+                self.write_primval(
+                    dest,
+                    PrimVal::from_i128(result as i128),
+                    dest_ty,
+                )?;
+            }
+
+            "clock_gettime" => {
+                let realtime = self.eval_path_scalar(&["libc", "CLOCK_REALTIME"], args[0].ty)?;
+                let clock = self.value_to_primval(args[0])?.to_bytes()?;
+                let (secs, nanos) = self.current_time(Some(clock) == realtime);
+                // struct timespec { tv_sec: time_t, tv_nsec: c_long }, both pointer sized
+                let timespec = self.into_ptr(args[1].value)?.to_ptr()?;
+                let ptr_size = self.memory.pointer_size();
+                self.memory.write_primval(timespec, PrimVal::Bytes(secs as u128), ptr_size, true)?;
+                let tv_nsec = timespec.offset(ptr_size, &self)?;
+                self.memory.write_primval(tv_nsec, PrimVal::Bytes(nanos as u128), ptr_size, true)?;
+                self.write_null(dest, dest_ty)?;
+            }
+
+            "gettimeofday" => {
+                let (secs, nanos) = self.current_time(true);
+                // struct timeval { tv_sec: time_t, tv_usec: suseconds_t }, both pointer sized
+                let timeval = self.into_ptr(args[0].value)?.to_ptr()?;
+                let ptr_size = self.memory.pointer_size();
+                self.memory.write_primval(timeval, PrimVal::Bytes(secs as u128), ptr_size, true)?;
+                let tv_usec = timeval.offset(ptr_size, &self)?;
+                self.memory.write_primval(tv_usec, PrimVal::Bytes((nanos / 1000) as u128), ptr_size, true)?;
+                self.write_null(dest, dest_ty)?;
+            }
+
+            "mach_absolute_time" => {
+                let (secs, nanos) = self.current_time(false);
+                let ticks = secs as u128 * 1_000_000_000 + nanos as u128;
+                self.write_primval(dest, PrimVal::Bytes(ticks), dest_ty)?;
+            }
+
+            "mach_timebase_info" => {
+                // struct mach_timebase_info { numer: u32, denom: u32 }, ticks are nanoseconds
+                let info = self.into_ptr(args[0].value)?.to_ptr()?;
+                self.memory.write_primval(info, PrimVal::Bytes(1), 4, false)?;
+                let denom = info.offset(4, &self)?;
+                self.memory.write_primval(denom, PrimVal::Bytes(1), 4, false)?;
+                self.write_null(dest, dest_ty)?;
+            }
+
+            // Terminal detection. Unless `-Zmiri-tty` is given, the standard streams are no terminal.
+            "isatty" => {
+                let fd = self.value_to_primval(args[0])?.to_i128()?;
+                let is_tty = self.machine.config.tty_size.is_some() && fd >= 0 && fd <= 2;
+                if !is_tty {
+                    self.fail_with_errno(fs::ENOTTY)?;
+                }
+                self.write_primval(dest, PrimVal::Bytes(is_tty as u128), dest_ty)?;
+            }
+
+            "ioctl" => {
+                let fd = self.value_to_primval(args[0])?.to_i128()?;
+                let request = self.value_to_primval(args[1])?.to_bytes()?;
+                let tiocgwinsz = self.eval_path_scalar(&["libc", "TIOCGWINSZ"], args[1].ty)?;
+                let tty_size = self.machine.config.tty_size;
+                match tty_size {
+                    Some((cols, rows)) if fd >= 0 && fd <= 2 && Some(request) == tiocgwinsz => {
+                        // struct winsize { ws_row, ws_col, ws_xpixel, ws_ypixel: c_ushort }
+                        let winsize = self.into_ptr(args[2].value)?.to_ptr()?;
+                        for (i, &val) in [rows, cols, 0, 0].iter().enumerate() {
+                            let field = winsize.offset(2 * i as u64, &self)?;
+                            self.memory.write_primval(field, PrimVal::Bytes(val as u128), 2, false)?;
+                        }
+                        self.write_null(dest, dest_ty)?;
+                    }
+                    _ => {
+                        let result = self.fail_with_errno(fs::ENOTTY)?;
+                        self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+                    }
+                }
+            }
+
+            "fcntl" => {
+                let fd = self.value_to_primval(args[0])?.to_i128()?;
+                let cmd = self.value_to_primval(args[1])?.to_bytes()?;
+                let f_getfd = self.eval_path_scalar(&["libc", "F_GETFD"], args[1].ty)?;
+                // The standard streams have no flags set
+                if fd >= 0 && fd <= 2 {
+                    self.write_null(dest, dest_ty)?;
+                } else if self.machine.file_handler.is_open(fd as i32) {
+                    // Files are opened with `O_CLOEXEC` by libstd, which checks that it was set
+                    if Some(cmd) == f_getfd {
+                        let cloexec = self.eval_path_scalar(&["libc", "FD_CLOEXEC"], dest_ty)?;
+                        self.write_primval(dest, PrimVal::Bytes(cloexec.unwrap_or(1)), dest_ty)?;
+                    } else {
+                        self.write_null(dest, dest_ty)?;
+                    }
+                } else {
+                    let result = self.fail_with_errno(fs::EBADF)?;
+                    self.write_primval(dest, PrimVal::from_i128(result as i128), dest_ty)?;
+                }
+            }
+
+            // libm functions that libstd's float methods call directly
+            "cbrtf" | "tanf" | "asinf" | "acosf" | "atanf" | "sinhf" | "coshf" | "tanhf" |
+            "expm1f" | "log1pf" => {
+                let f = f32::from_bits(self.value_to_primval(args[0])?.to_bytes()? as u32);
+                let f = match &link_name[..] {
+                    "cbrtf" => f.cbrt(),
+                    "tanf" => f.tan(),
+                    "asinf" => f.asin(),
+                    "acosf" => f.acos(),
+                    "atanf" => f.atan(),
+                    "sinhf" => f.sinh(),
+                    "coshf" => f.cosh(),
+                    "tanhf" => f.tanh(),
+                    "expm1f" => f.exp_m1(),
+                    "log1pf" => f.ln_1p(),
+                    _ => bug!(),
+                };
+                self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_ty)?;
+            }
+            "cbrt" | "tan" | "asin" | "acos" | "atan" | "sinh" | "cosh" | "tanh" |
+            "expm1" | "log1p" => {
+                let f = f64::from_bits(self.value_to_primval(args[0])?.to_bytes()? as u64);
+                let f = match &link_name[..] {
+                    "cbrt" => f.cbrt(),
+                    "tan" => f.tan(),
+                    "asin" => f.asin(),
+                    "acos" => f.acos(),
+                    "atan" => f.atan(),
+                    "sinh" => f.sinh(),
+                    "cosh" => f.cosh(),
+                    "tanh" => f.tanh(),
+                    "expm1" => f.exp_m1(),
+                    "log1p" => f.ln_1p(),
+                    _ => bug!(),
+                };
+                self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_ty)?;
+            }
+            "fmaxf" | "fminf" | "atan2f" | "hypotf" | "fdimf" => {
+                let a = f32::from_bits(self.value_to_primval(args[0])?.to_bytes()? as u32);
+                let b = f32::from_bits(self.value_to_primval(args[1])?.to_bytes()? as u32);
+                let f = match &link_name[..] {
+                    "fmaxf" => a.max(b),
+                    "fminf" => a.min(b),
+                    "atan2f" => a.atan2(b),
+                    "hypotf" => a.hypot(b),
+                    "fdimf" => if a.is_nan() || b.is_nan() {
+                        ::std::f32::NAN
+                    } else if a > b {
+                        a - b
+                    } else {
+                        0.0
+                    },
+                    _ => bug!(),
+                };
+                self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_ty)?;
+            }
+            "fmax" | "fmin" | "atan2" | "hypot" | "fdim" => {
+                let a = f64::from_bits(self.value_to_primval(args[0])?.to_bytes()? as u64);
+                let b = f64::from_bits(self.value_to_primval(args[1])?.to_bytes()? as u64);
+                let f = match &link_name[..] {
+                    "fmax" => a.max(b),
+                    "fmin" => a.min(b),
+                    "atan2" => a.atan2(b),
+                    "hypot" => a.hypot(b),
+                    "fdim" => if a.is_nan() || b.is_nan() {
+                        ::std::f64::NAN
+                    } else if a > b {
+                        a - b
+                    } else {
+                        0.0
+                    },
+                    _ => bug!(),
+                };
+                self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_ty)?;
+            }
+
+            // compiler-builtins functions for 128 bit arithmetic that targets lack instructions for
+            "__udivti3" | "__umodti3" => {
+                let a = self.value_to_primval(args[0])?.to_u128()?;
+                let b = self.value_to_primval(args[1])?.to_u128()?;
+                if b == 0 {
+                    return err!(Intrinsic(format!("division by zero in `{}`", link_name)));
+                }
+                let result = if &link_name[..] == "__udivti3" { a / b } else { a % b };
+                self.write_primval(dest, PrimVal::Bytes(result), dest_ty)?;
+            }
+
+            "__divti3" | "__modti3" => {
+                let a = self.value_to_primval(args[0])?.to_i128()?;
+                let b = self.value_to_primval(args[1])?.to_i128()?;
+                if b == 0 {
+                    return err!(Intrinsic(format!("division by zero in `{}`", link_name)));
+                }
+                // `i128::MIN / -1` overflows, like the builtins we wrap around
+                let result = if &link_name[..] == "__divti3" {
+                    a.wrapping_div(b)
+                } else {
+                    a.wrapping_rem(b)
+                };
+                self.write_primval(dest, PrimVal::from_i128(result), dest_ty)?;
+            }
+
+            "__multi3" => {
+                let a = self.value_to_primval(args[0])?.to_u128()?;
+                let b = self.value_to_primval(args[1])?.to_u128()?;
+                self.write_primval(dest, PrimVal::Bytes(a.wrapping_mul(b)), dest_ty)?;
+            }
+
+            "__muloti4" => {
+                let a = self.value_to_primval(args[0])?.to_i128()?;
+                let b = self.value_to_primval(args[1])?.to_i128()?;
+                let (result, overflowed) = a.overflowing_mul(b);
+                // The third argument is an `int *` receiving whether the multiplication overflowed
+                let overflow = self.into_ptr(args[2].value)?.to_ptr()?;
+                self.memory.write_primval(overflow, PrimVal::Bytes(overflowed as u128), 4, true)?;
+                self.write_primval(dest, PrimVal::from_i128(result), dest_ty)?;
+            }
+
             "strlen" => {
                 let ptr = self.into_ptr(args[0].value)?.to_ptr()?;
                 let n = self.memory.read_c_str(ptr)?.len();
@@ -377,21 +865,9 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 ];
                 let mut result = None;
                 for &(path, path_value) in paths {
-                    if let Ok(instance) = self.resolve_path(path) {
-                        let cid = GlobalId {
-                            instance,
-                            promoted: None,
-                        };
-                        // compute global if not cached
-                        let val = match self.tcx.interpret_interner.borrow().get_cached(cid) {
-                            Some(ptr) => ptr,
-                            None => eval_body(self.tcx, instance, ty::ParamEnv::empty(traits::Reveal::All)).0?.0,
-                        };
-                        let val = self.value_to_primval(ValTy { value: Value::ByRef(val), ty: args[0].ty })?.to_u64()?;
-                        if val == name {
-                            result = Some(path_value);
-                            break;
-                        }
+                    if self.eval_path_scalar(path, args[0].ty)? == Some(name as u128) {
+                        result = Some(path_value);
+                        break;
                     }
                 }
                 if let Some(result) = result {
@@ -458,6 +934,43 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 self.write_null(dest, dest_ty)?;
             }
 
+            // Windows TLS, used by libstd on Windows
+            "TlsAlloc" => {
+                // Like `pthread_key_create` above, but the key is returned instead of written
+                // through a pointer. The Windows API has no destructor for a key (libstd runs
+                // them itself), so the key is created without one. The check that the key fits
+                // is the same as for `pthread_key_create`.
+
+                // Figure out how large a TLS key actually is. This is c::DWORD.
+                let key_size = self.layout_of(dest_ty)?.size;
+
+                // Create key and return it
+                let key = self.memory.create_tls_key(None) as u128;
+                if key_size.bits() < 128 && key >= (1u128 << key_size.bits() as u128) {
+                    return err!(OutOfTls);
+                }
+                self.write_primval(dest, PrimVal::Bytes(key), dest_ty)?;
+            }
+            "TlsGetValue" => {
+                let key = self.value_to_primval(args[0])?.to_u64()? as TlsKey;
+                let ptr = self.memory.load_tls(key)?;
+                self.write_ptr(dest, ptr, dest_ty)?;
+            }
+            "TlsSetValue" => {
+                let key = self.value_to_primval(args[0])?.to_u64()? as TlsKey;
+                let new_ptr = self.into_ptr(args[1].value)?;
+                self.memory.store_tls(key, new_ptr)?;
+
+                // Return success (1)
+                self.write_primval(dest, PrimVal::Bytes(1), dest_ty)?;
+            }
+
+            // Silently succeeding here would make `thread::spawn` return without ever running the
+            // closure. Running threads needs one stack per thread in the `EvalContext`.
+            "pthread_create" | "CreateThread" => {
+                return err!(Unimplemented("miri does not support threading".to_owned()));
+            }
+
             // Stub out all the other pthread calls to just return 0
             link_name if link_name.starts_with("pthread_") => {
                 info!("ignoring C ABI call: {}", link_name);
@@ -479,6 +992,85 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
         Ok(())
     }
 
+    /// Looks for a function with the given path in the model crate, e.g. for `foo::bar::baz` the
+    /// model crate's `foo::bar::baz`
+    fn resolve_model_fn(&self, path: &str) -> EvalResult<'tcx, Option<ty::Instance<'tcx>>> {
+        let model_crate = match self.machine.config.model_crate {
+            Some(ref model_crate) => model_crate,
+            None => return Ok(None),
+        };
+        // Functions in `extern` blocks have an empty path segment for the block
+        let segments: Vec<&str> = ::std::iter::once(&model_crate[..])
+            .chain(path.split("::").filter(|segment| !segment.is_empty()))
+            .collect();
+        match self.resolve_path(&segments) {
+            Ok(instance) => Ok(Some(instance)),
+            Err(EvalError { kind: EvalErrorKind::PathNotFound(_), .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn current_time(&mut self, realtime: bool) -> (u64, u32) {
+        self.machine.loop_detector.clock_read = true;
+        let duration = if !self.machine.config.host_time {
+            let nanos = self.machine.steps * NANOS_PER_STEP;
+            Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+        } else if realtime {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0))
+        } else {
+            // Monotonic clocks start with the first time they are read
+            self.machine.host_start.get_or_insert_with(Instant::now).elapsed()
+        };
+        (duration.as_secs(), duration.subsec_nanos())
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // splitmix64, small and good enough for hash keys and test data
+        let seed = self.machine.config.seed;
+        let state = self.machine.random_state.get_or_insert(seed);
+        *state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn write_random_bytes(&mut self, ptr: Pointer, len: u64) -> EvalResult<'tcx> {
+        if len == 0 {
+            // The pointer may be dangling, e.g. when probing whether `getrandom` is available
+            return Ok(());
+        }
+        let mut bytes = Vec::with_capacity(len as usize);
+        while (bytes.len() as u64) < len {
+            let random = self.next_random();
+            let remaining = (len - bytes.len() as u64).min(8) as usize;
+            bytes.extend((0..remaining).map(|i| (random >> (i * 8)) as u8));
+        }
+        self.memory.write_bytes(ptr, &bytes)
+    }
+
+    fn track_heap(&mut self, delta: i64) {
+        alloc_stats::heap_changed(&self.memory.data, delta);
+    }
+
+    /// Evaluate the constant at the given path (e.g. `libc::TIOCGWINSZ`), if it exists.
+    fn eval_path_scalar(&mut self, path: &[&str], ty: Ty<'tcx>) -> EvalResult<'tcx, Option<u128>> {
+        if let Ok(instance) = self.resolve_path(path) {
+            let cid = GlobalId {
+                instance,
+                promoted: None,
+            };
+            // compute global if not cached
+            let val = match self.tcx.interpret_interner.borrow().get_cached(cid) {
+                Some(ptr) => ptr,
+                None => eval_body(self.tcx, instance, ty::ParamEnv::empty(traits::Reveal::All)).0?.0,
+            };
+            let val = self.value_to_primval(ValTy { value: Value::ByRef(val), ty })?.to_bytes()?;
+            return Ok(Some(val));
+        }
+        Ok(None)
+    }
+
     /// Get an instance for a path.
     fn resolve_path(&self, path: &[&str]) -> EvalResult<'tcx, ty::Instance<'tcx>> {
         self.tcx
@@ -525,17 +1117,30 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
         match &path[..] {
             "std::panicking::rust_panic_with_hook" |
             "core::panicking::panic_fmt::::panic_impl" |
-            "std::rt::begin_panic_fmt" => return err!(Panic),
+            "std::rt::begin_panic_fmt" => {
+                self.machine.panic = Some(outcome::panic_info(self, &path, args));
+                return err!(Panic);
+            }
+            // The global allocator reported an allocation failure. libstd's allocators print a
+            // message and abort the process in that case.
+            "alloc::heap::::__rust_oom" => {
+                write_to_host(2, b"fatal runtime error: out of memory\n", false);
+                return Err(MiriError::OutOfMemoryAbort.into());
+            }
             _ => {}
         }
 
+        if let Some(ref mut summary) = self.machine.summary {
+            summary.foreign_fn(&path);
+        }
+
         let dest_ty = sig.output();
         let (dest, dest_block) = destination.ok_or_else(
             || EvalErrorKind::NoMirFor(path.clone()),
         )?;
 
-        if sig.abi == Abi::C {
-            // An external C function
+        if sig.abi == Abi::C || sig.abi == Abi::System {
+            // An external C function (or a Windows API function)
             // TODO: That functions actually has a similar preamble to what follows here.  May make sense to
             // unify these two mechanisms for "hooking into missing functions".
             self.call_c_abi(
@@ -559,8 +1164,12 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 if !align.is_power_of_two() {
                     return err!(HeapAllocNonPowerOfTwoAlignment(align));
                 }
-                let ptr = self.memory.allocate(size, align, Some(MemoryKind::Rust.into()))?;
-                self.write_primval(dest, PrimVal::Ptr(ptr), dest_ty)?;
+                let ptr = guest_allocation(self.memory.allocate(size, align, Some(MemoryKind::Rust.into())))?;
+                if let PrimVal::Ptr(ptr) = ptr {
+                    undef::record_allocation(self, ptr, size);
+                    self.track_heap(size as i64);
+                }
+                self.write_primval(dest, ptr, dest_ty)?;
             }
             "alloc::heap::::__rust_alloc_zeroed" => {
                 let size = self.value_to_primval(args[0])?.to_u64()?;
@@ -571,9 +1180,12 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 if !align.is_power_of_two() {
                     return err!(HeapAllocNonPowerOfTwoAlignment(align));
                 }
-                let ptr = self.memory.allocate(size, align, Some(MemoryKind::Rust.into()))?;
-                self.memory.write_repeat(ptr.into(), 0, size)?;
-                self.write_primval(dest, PrimVal::Ptr(ptr), dest_ty)?;
+                let ptr = guest_allocation(self.memory.allocate(size, align, Some(MemoryKind::Rust.into())))?;
+                if let PrimVal::Ptr(ptr) = ptr {
+                    self.memory.write_repeat(ptr.into(), 0, size)?;
+                    self.track_heap(size as i64);
+                }
+                self.write_primval(dest, ptr, dest_ty)?;
             }
             "alloc::heap::::__rust_dealloc" => {
                 let ptr = self.into_ptr(args[0].value)?.to_ptr()?;
@@ -590,6 +1202,7 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     Some((old_size, align)),
                     MemoryKind::Rust.into(),
                 )?;
+                self.track_heap(-(old_size as i64));
             }
             "alloc::heap::::__rust_realloc" => {
                 let ptr = self.into_ptr(args[0].value)?.to_ptr()?;
@@ -606,20 +1219,28 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 if !new_align.is_power_of_two() {
                     return err!(HeapAllocNonPowerOfTwoAlignment(new_align));
                 }
-                let new_ptr = self.memory.reallocate(
+                let new_ptr = guest_allocation(self.memory.reallocate(
                     ptr,
                     old_size,
                     old_align,
                     new_size,
                     new_align,
                     MemoryKind::Rust.into(),
-                )?;
-                self.write_primval(dest, PrimVal::Ptr(new_ptr), dest_ty)?;
+                ))?;
+                if let PrimVal::Ptr(new_ptr) = new_ptr {
+                    if new_size > old_size {
+                        let grown = new_ptr.offset(old_size, &self)?;
+                        undef::record_allocation(self, grown, new_size - old_size);
+                    }
+                    self.track_heap(new_size as i64 - old_size as i64);
+                }
+                self.write_primval(dest, new_ptr, dest_ty)?;
             }
 
             // A Rust function is missing, which means we are running with MIR missing for libstd (or other dependencies).
             // Still, we can make many things mostly work by "emulating" or ignoring some functions.
-            "std::io::_print" => {
+            "std::io::_print" |
+            "std::io::_eprint" => {
                 warn!(
                     "Ignoring output.  To run programs that print, make sure you have a libstd with full MIR."
                 );
@@ -659,3 +1280,40 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
         self.write_primval(dest, PrimVal::Bytes(0), dest_ty)
     }
 }
+
+/// Forwards output of the interpreted program on stdout (`fd == 1`) or stderr (`fd == 2`) to the
+/// host. Returns what `write` would return.
+fn write_to_host(fd: u64, buf: &[u8], short_writes: bool) -> isize {
+    use std::io::{self, Write};
+
+    // Write only the first half, the program is expected to retry with the rest
+    let buf = if short_writes && buf.len() > 1 {
+        &buf[..(buf.len() + 1) / 2]
+    } else {
+        buf
+    };
+    // Flush right away, the program does its own buffering. Otherwise stdout and
+    // stderr output (including miri's own diagnostics) can end up interleaved.
+    let res = if fd == 1 {
+        let mut stdout = io::stdout();
+        stdout.write(buf).and_then(|n| stdout.flush().map(|()| n))
+    } else {
+        io::stderr().write(buf)
+    };
+    match res {
+        Ok(n) => n as isize,
+        Err(_) => -1,
+    }
+}
+
+/// Turns the result of an allocation on behalf of the program's global allocator or of `malloc`,
+/// `calloc` and `realloc` into the allocator's return value. Running out of memory is reported to
+/// the program as a null pointer, just like a native allocator would, and the program decides how
+/// to handle it.
+fn guest_allocation<'tcx>(res: EvalResult<'tcx, MemoryPointer>) -> EvalResult<'tcx, PrimVal> {
+    match res {
+        Ok(ptr) => Ok(PrimVal::Ptr(ptr)),
+        Err(EvalError { kind: EvalErrorKind::OutOfMemory { .. }, .. }) => Ok(PrimVal::Bytes(0)),
+        Err(e) => Err(e),
+    }
+}
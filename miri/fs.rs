@@ -0,0 +1,264 @@
+//! Access to the host's files. Programs are isolated from the host by default, the shims here
+//! only work with `-Zmiri-allow-fs`. Files opened by the program live in a table mapping the file
+//! descriptors the program sees to the host's `File`s.
+
+use std::collections::HashMap;
+use std::fs::{File, Metadata, OpenOptions};
+use std::io::{self, Read, Write};
+
+use rustc::ty::layout::LayoutOf;
+use syntax::ast::Mutability;
+
+use super::*;
+use fn_call::EvalContextExt as MissingFnsEvalContextExt;
+
+/// The most bytes a single `read` hands to the program, it will just ask again for more
+const MAX_READ: u64 = 1 << 20;
+
+pub struct FileHandler {
+    files: HashMap<i32, File>,
+    /// The file descriptor of the next file to be opened, the standard streams take 0 to 2
+    next_fd: i32,
+}
+
+impl Default for FileHandler {
+    fn default() -> Self {
+        FileHandler {
+            files: HashMap::new(),
+            next_fd: 3,
+        }
+    }
+}
+
+impl FileHandler {
+    pub(crate) fn is_open(&self, fd: i32) -> bool {
+        self.files.contains_key(&fd)
+    }
+
+    pub(crate) fn has_open_files(&self) -> bool {
+        !self.files.is_empty()
+    }
+}
+
+pub trait EvalContextExt<'tcx> {
+    /// `open(path, flags, ...)`, returns the new file descriptor or -1
+    fn open(&mut self, path: Pointer, flags: ValTy<'tcx>) -> EvalResult<'tcx, i64>;
+
+    /// `read(fd, buf, count)`, returns the number of bytes read or -1
+    fn read(&mut self, fd: i32, buf: Pointer, count: u64) -> EvalResult<'tcx, i64>;
+
+    /// `write(fd, buf, count)` for a file opened by the program, `None` for other descriptors
+    fn write_file(&mut self, fd: i32, buf: Pointer, count: u64) -> EvalResult<'tcx, Option<i64>>;
+
+    /// `close(fd)`, returns 0 or -1
+    fn close(&mut self, fd: i32) -> EvalResult<'tcx, i64>;
+
+    /// `stat(path, buf)` and `fstat(fd, buf)`: fills in the `libc::<stat_struct>` at `buf` with
+    /// the metadata of `file`, returns 0 or -1
+    fn stat(&mut self, file: Result<Pointer, i32>, buf: Pointer, stat_struct: &str) -> EvalResult<'tcx, i64>;
+
+    /// `unlink(path)`, returns 0 or -1
+    fn unlink(&mut self, path: Pointer) -> EvalResult<'tcx, i64>;
+
+    /// The location of the program's `errno`
+    fn errno_ptr(&mut self) -> EvalResult<'tcx, MemoryPointer>;
+
+    /// Sets `errno` to `code`, e.g. `ENOTTY`, and returns -1 for the shims of other failing calls
+    fn fail_with_errno(&mut self, code: i32) -> EvalResult<'tcx, i64>;
+}
+
+impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, Evaluator<'tcx>> {
+    fn open(&mut self, path: Pointer, flags: ValTy<'tcx>) -> EvalResult<'tcx, i64> {
+        check_fs_allowed(self, "open")?;
+        let path = read_path(self, path)?;
+        let flag_ty = flags.ty;
+        let flags = self.value_to_primval(flags)?.to_bytes()?;
+        let (append, truncate, create, exclusive) = {
+            let mut flag = |name: &str| -> EvalResult<'tcx, bool> {
+                Ok(match self.eval_path_scalar(&["libc", name], flag_ty)? {
+                    Some(value) => flags & value != 0,
+                    None => false,
+                })
+            };
+            (flag("O_APPEND")?, flag("O_TRUNC")?, flag("O_CREAT")?, flag("O_EXCL")?)
+        };
+        let mut options = OpenOptions::new();
+        // The access modes are 0, 1 and 2, they are not flags that can be tested individually
+        match flags & 0b11 {
+            0 => options.read(true),
+            1 => options.write(true),
+            _ => options.read(true).write(true),
+        };
+        options.append(append).truncate(truncate);
+        if create {
+            if exclusive {
+                options.create_new(true);
+            } else {
+                options.create(true);
+            }
+        }
+        let result = options.open(path);
+        let file = match result {
+            Ok(file) => file,
+            Err(e) => return set_last_error(self, e),
+        };
+        let handler = &mut self.machine.file_handler;
+        let fd = handler.next_fd;
+        handler.next_fd += 1;
+        handler.files.insert(fd, file);
+        Ok(fd as i64)
+    }
+
+    fn read(&mut self, fd: i32, buf: Pointer, count: u64) -> EvalResult<'tcx, i64> {
+        check_fs_allowed(self, "read")?;
+        let mut bytes = vec![0; count.min(MAX_READ) as usize];
+        let result = match self.machine.file_handler.files.get_mut(&fd) {
+            Some(file) => file.read(&mut bytes),
+            None => Err(bad_fd()),
+        };
+        match result {
+            Ok(read) => {
+                self.memory.write_bytes(buf, &bytes[..read])?;
+                Ok(read as i64)
+            }
+            Err(e) => set_last_error(self, e),
+        }
+    }
+
+    fn write_file(&mut self, fd: i32, buf: Pointer, count: u64) -> EvalResult<'tcx, Option<i64>> {
+        if !self.machine.file_handler.is_open(fd) {
+            return Ok(None);
+        }
+        let bytes = self.memory.read_bytes(buf, count)?.to_owned();
+        let result = self.machine.file_handler.files.get_mut(&fd).unwrap().write(&bytes);
+        match result {
+            Ok(written) => Ok(Some(written as i64)),
+            Err(e) => set_last_error(self, e).map(Some),
+        }
+    }
+
+    fn close(&mut self, fd: i32) -> EvalResult<'tcx, i64> {
+        check_fs_allowed(self, "close")?;
+        // Dropping the `File` closes it on the host
+        match self.machine.file_handler.files.remove(&fd) {
+            Some(_) => Ok(0),
+            None => set_last_error(self, bad_fd()),
+        }
+    }
+
+    fn stat(&mut self, file: Result<Pointer, i32>, buf: Pointer, stat_struct: &str) -> EvalResult<'tcx, i64> {
+        check_fs_allowed(self, stat_struct)?;
+        let metadata = match file {
+            Ok(path) => {
+                let path = read_path(self, path)?;
+                ::std::fs::metadata(path)
+            }
+            Err(fd) => match self.machine.file_handler.files.get(&fd) {
+                Some(file) => file.metadata(),
+                None => Err(bad_fd()),
+            },
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => return set_last_error(self, e),
+        };
+        // Take the layout of the struct from the libc crate, it differs between platforms
+        let stat_ty = self.tcx.type_of(self.resolve_path(&["libc", stat_struct])?.def_id());
+        let layout = self.layout_of(stat_ty)?;
+        let variant = match stat_ty.sty {
+            ty::TyAdt(adt, _) => &adt.variants[0],
+            _ => bug!("`libc::{}` is not a struct", stat_struct),
+        };
+        self.memory.write_repeat(buf, 0, layout.size.bytes())?;
+        let buf = buf.to_ptr()?;
+        // Everything not filled in here stays zero
+        for &(name, value) in &[("st_mode", mode(&metadata)), ("st_size", metadata.len()), ("st_nlink", 1)] {
+            let index = match variant.fields.iter().position(|field| field.name == name) {
+                Some(index) => index,
+                None => continue,
+            };
+            let offset = layout.fields.offset(index).bytes();
+            let size = layout.field(&*self, index)?.size.bytes();
+            let field = buf.offset(offset, &self)?;
+            self.memory.write_primval(field, PrimVal::Bytes(value as u128), size, false)?;
+        }
+        Ok(0)
+    }
+
+    fn unlink(&mut self, path: Pointer) -> EvalResult<'tcx, i64> {
+        check_fs_allowed(self, "unlink")?;
+        let path = read_path(self, path)?;
+        match ::std::fs::remove_file(path) {
+            Ok(()) => Ok(0),
+            Err(e) => set_last_error(self, e),
+        }
+    }
+
+    fn errno_ptr(&mut self) -> EvalResult<'tcx, MemoryPointer> {
+        if let Some(ptr) = self.machine.errno_ptr {
+            return Ok(ptr);
+        }
+        // `errno` is a C `int`
+        let ptr = self.memory.allocate(4, 4, None)?;
+        self.memory.write_primval(ptr, PrimVal::Bytes(0), 4, true)?;
+        // Like a `static mut`, so it is not reported as leaked
+        self.memory.mark_static_initalized(ptr.alloc_id, Mutability::Mutable)?;
+        self.machine.errno_ptr = Some(ptr);
+        Ok(ptr)
+    }
+
+    fn fail_with_errno(&mut self, code: i32) -> EvalResult<'tcx, i64> {
+        set_last_error(self, io::Error::from_raw_os_error(code))
+    }
+}
+
+fn check_fs_allowed<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, name: &str) -> EvalResult<'tcx> {
+    if ecx.machine.config.allow_fs {
+        Ok(())
+    } else {
+        err!(Unimplemented(format!(
+            "miri does not support `{}` without -Zmiri-allow-fs, which gives the program access \
+             to the host's files",
+            name,
+        )))
+    }
+}
+
+fn read_path<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, path: Pointer) -> EvalResult<'tcx, String> {
+    let path = ecx.memory.read_c_str(path.to_ptr()?)?;
+    match ::std::str::from_utf8(path) {
+        Ok(path) => Ok(path.to_owned()),
+        Err(_) => err!(Unimplemented(format!("miri does not support non-UTF-8 paths: {:?}", path))),
+    }
+}
+
+/// Sets `errno` to the host's error code of `e` and returns -1, the usual result of failed calls
+fn set_last_error<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>, e: io::Error) -> EvalResult<'tcx, i64> {
+    let code = match e.raw_os_error() {
+        Some(code) => code,
+        None => return err!(Unimplemented(format!("file system error without an error code: {}", e))),
+    };
+    let errno = ecx.errno_ptr()?;
+    ecx.memory.write_primval(errno, PrimVal::from_i128(code as i128), 4, true)?;
+    Ok(-1)
+}
+
+// These have the same values on all platforms we run on
+pub(crate) const EBADF: i32 = 9;
+pub(crate) const ENOTTY: i32 = 25;
+
+fn bad_fd() -> io::Error {
+    io::Error::from_raw_os_error(EBADF)
+}
+
+#[cfg(unix)]
+fn mode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.mode() as u64
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &Metadata) -> u64 {
+    // S_IFDIR or S_IFREG, with the usual permissions
+    if metadata.is_dir() { 0o040755 } else { 0o100644 }
+}
@@ -0,0 +1,120 @@
+//! The memory of the program as a Graphviz graph (`-Zmiri-heap-graph=FILE`, or `graph FILE` in the
+//! debugger), which is easier to follow than the bytes of every allocation when debugging linked
+//! data structures. Allocations are nodes with their size and undefined bytes, pointers are edges
+//! labeled with the offset they are stored at. The frames of the stack are nodes too, with an edge
+//! for every local pointing into memory. Render it with `dot -Tsvg FILE > heap.svg`.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use super::*;
+use inspect::EvalContextExt as InspectEvalContextExt;
+use rustc_compat;
+
+/// Writes the allocations reachable from the stack, and all mutable allocations, to `file`
+pub fn write_heap_graph<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, file: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(file)?);
+    writeln!(out, "digraph heap {{")?;
+    writeln!(out, "    node [shape=box, fontname=monospace];")?;
+
+    let mut mutable: Vec<u64> = ecx.memory.data.locks.keys().cloned().collect();
+    mutable.sort();
+    let mut pending: VecDeque<u64> = mutable.into_iter().collect();
+    for (i, frame) in ecx.stack().iter().enumerate() {
+        writeln!(
+            out,
+            "    frame{} [label={}, style=filled, fillcolor=lightgrey];",
+            i,
+            quote(&format!("frame {}: {}", i, rustc_compat::frame_fn_path(ecx.tcx, frame))),
+        )?;
+        for local in ecx.frame_locals(i) {
+            let targets = match local.value {
+                Some(value) => pointers(value),
+                None => continue,
+            };
+            for (alloc_id, offset) in targets {
+                let label = if offset == 0 {
+                    local.display_name()
+                } else {
+                    format!("{} (+{})", local.display_name(), offset)
+                };
+                writeln!(out, "    frame{} -> alloc{} [label={}];", i, alloc_id, quote(&label))?;
+                pending.push_back(alloc_id);
+            }
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    while let Some(alloc_id) = pending.pop_front() {
+        if !seen.insert(alloc_id) {
+            continue;
+        }
+        let alloc = match ecx.memory.get(AllocId(alloc_id)) {
+            Ok(alloc) => alloc,
+            Err(_) => {
+                let ptr = MemoryPointer::new(AllocId(alloc_id), 0);
+                let label = match ecx.memory.get_fn(ptr) {
+                    Ok(instance) => format!("alloc {}\nfn {}", alloc_id, ecx.tcx.item_path_str(instance.def_id())),
+                    Err(_) => format!("alloc {}\n(freed)", alloc_id),
+                };
+                writeln!(out, "    alloc{} [label={}, style=dashed];", alloc_id, quote(&label))?;
+                continue;
+            }
+        };
+        let size = alloc.bytes.len() as u64;
+        let mut label = format!("alloc {}\n{} bytes, aligned to {}", alloc_id, size, alloc.align);
+        let undef = undef_ranges(size, |offset| alloc.undef_mask.get(offset));
+        if !undef.is_empty() {
+            label.push_str("\nundef: ");
+            label.push_str(&undef.join(", "));
+        }
+        writeln!(out, "    alloc{} [label={}];", alloc_id, quote(&label))?;
+        for (offset, target) in &alloc.relocations {
+            writeln!(out, "    alloc{} -> alloc{} [label=\"+{}\"];", alloc_id, target.0, offset)?;
+            pending.push_back(target.0);
+        }
+    }
+
+    writeln!(out, "}}")?;
+    out.flush()
+}
+
+/// The allocations (and offsets into them) `value` points to
+fn pointers(value: Value) -> Vec<(u64, u64)> {
+    let primvals = match value {
+        Value::ByRef(PtrAndAlign { ptr, .. }) => vec![ptr.into_inner_primval()],
+        Value::ByVal(val) => vec![val],
+        Value::ByValPair(a, b) => vec![a, b],
+    };
+    primvals
+        .into_iter()
+        .filter_map(|val| match val {
+            PrimVal::Ptr(ptr) => Some((ptr.alloc_id.0, ptr.offset)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The ranges of undefined bytes, as `start..end`
+fn undef_ranges<F: Fn(u64) -> bool>(size: u64, is_defined: F) -> Vec<String> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for offset in 0..size + 1 {
+        let undef = offset < size && !is_defined(offset);
+        match (start, undef) {
+            (None, true) => start = Some(offset),
+            (Some(first), false) => {
+                ranges.push(format!("{}..{}", first, offset));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// `s` as a quoted Graphviz string, with line breaks
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
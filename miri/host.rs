@@ -0,0 +1,311 @@
+//! Values of the interpreted program as host data, for embedders inspecting the result of an
+//! evaluation without reimplementing the layout of every type, and host data as values of the
+//! program, to pass inputs to `eval_fn` or return them from shims.
+
+use rustc::ty::{self, Ty};
+use rustc::ty::layout::{LayoutOf, TyLayout};
+use syntax::ast::Mutability;
+
+use super::*;
+
+/// How many references are followed, e.g. along a linked list
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Debug)]
+pub enum HostValue {
+    Bool(bool),
+    Char(char),
+    Int(i128),
+    Uint(u128),
+    Float(f64),
+    /// A `str` (behind a reference)
+    Str(String),
+    /// The value behind a reference or `Box`
+    Ref(Box<HostValue>),
+    /// A raw pointer, which is not followed
+    RawPtr(PrimVal),
+    /// The path of the function a function pointer points to
+    FnPtr(String),
+    /// Arrays and slices
+    Array(Vec<HostValue>),
+    /// Tuples, including `()`
+    Tuple(Vec<HostValue>),
+    /// A struct with the values of its fields, in declaration order
+    Struct { name: String, fields: Vec<(String, HostValue)> },
+    /// An enum with the active variant and the values of its fields
+    Enum { name: String, variant: String, fields: Vec<(String, HostValue)> },
+    /// The value is (partially) undefined
+    Undef,
+}
+
+pub trait EvalContextExt<'tcx> {
+    /// Reads the value of type `ty` at `ptr`, following references
+    fn read_host_value(&self, ptr: MemoryPointer, ty: Ty<'tcx>) -> EvalResult<'tcx, HostValue>;
+
+    /// Converts `value` of type `ty`, e.g. the result of `eval_fn`. Values that are not in memory
+    /// are decoded as they are, without allocating, so that inspecting them does not change what
+    /// the program sees. Enums are only read from memory.
+    fn value_to_host(&self, value: Value, ty: Ty<'tcx>) -> EvalResult<'tcx, HostValue>;
+
+    /// Copies `bytes` into a new allocation, returns a `&[u8]` (or `&mut [u8]`) to it
+    fn alloc_bytes(&mut self, bytes: &[u8]) -> EvalResult<'tcx, Value>;
+
+    /// Copies `s` into a new allocation, returns a `&str` to it
+    fn alloc_str(&mut self, s: &str) -> EvalResult<'tcx, Value>;
+
+    /// Writes the scalar `val` of type `ty` into a new allocation, returns a `&T` (or `&mut T`)
+    /// to it
+    fn alloc_scalar(&mut self, val: PrimVal, ty: Ty<'tcx>) -> EvalResult<'tcx, Value>;
+}
+
+impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, Evaluator<'tcx>> {
+    fn read_host_value(&self, ptr: MemoryPointer, ty: Ty<'tcx>) -> EvalResult<'tcx, HostValue> {
+        read(self, ptr, self.layout_of(ty)?, 0)
+    }
+
+    fn value_to_host(&self, value: Value, ty: Ty<'tcx>) -> EvalResult<'tcx, HostValue> {
+        match value {
+            Value::ByRef(PtrAndAlign { ptr, .. }) => self.read_host_value(ptr.to_ptr()?, ty),
+            value => immediate(self, value, self.layout_of(ty)?, 0),
+        }
+    }
+
+    fn alloc_bytes(&mut self, bytes: &[u8]) -> EvalResult<'tcx, Value> {
+        let ptr = self.memory.allocate(bytes.len() as u64, 1, None)?;
+        self.memory.write_bytes(ptr.into(), bytes)?;
+        // Like a `static mut`, it lives as long as the context and is not reported as leaked
+        self.memory.mark_static_initalized(ptr.alloc_id, Mutability::Mutable)?;
+        Ok(Value::ByValPair(PrimVal::Ptr(ptr), PrimVal::Bytes(bytes.len() as u128)))
+    }
+
+    fn alloc_str(&mut self, s: &str) -> EvalResult<'tcx, Value> {
+        self.alloc_bytes(s.as_bytes())
+    }
+
+    fn alloc_scalar(&mut self, val: PrimVal, ty: Ty<'tcx>) -> EvalResult<'tcx, Value> {
+        let layout = self.layout_of(ty)?;
+        let ptr = self.memory.allocate(layout.size.bytes(), layout.align.abi(), None)?;
+        self.write_primval(Place::from_ptr(ptr), val, ty)?;
+        self.memory.mark_static_initalized(ptr.alloc_id, Mutability::Mutable)?;
+        Ok(Value::ByVal(PrimVal::Ptr(ptr)))
+    }
+}
+
+fn read<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    ptr: MemoryPointer,
+    layout: TyLayout<'tcx>,
+    depth: usize,
+) -> EvalResult<'tcx, HostValue> {
+    check_depth(depth)?;
+    Ok(match layout.ty.sty {
+        ty::TyBool | ty::TyChar | ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) | ty::TyRawPtr(_) |
+        ty::TyFnPtr(_) => {
+            let signed = match layout.ty.sty {
+                ty::TyInt(_) => true,
+                _ => false,
+            };
+            primitive(ecx, ecx.memory.read_primval(ptr, layout.size.bytes(), signed)?, layout)?
+        }
+        ty::TyRef(_, ref pointee) => pointee_to_host(ecx, ecx.read_value(ptr.into(), layout.ty)?, layout.ty, pointee.ty, depth)?,
+        ty::TyAdt(adt, _) if adt.is_box() => {
+            pointee_to_host(ecx, ecx.read_value(ptr.into(), layout.ty)?, layout.ty, layout.ty.boxed_ty(), depth)?
+        }
+        ty::TyArray(..) => HostValue::Array(read_fields(ecx, ptr, layout, depth)?),
+        ty::TyTuple(..) => HostValue::Tuple(read_fields(ecx, ptr, layout, depth)?),
+        ty::TyAdt(adt, _) if adt.is_struct() => HostValue::Struct {
+            name: ecx.tcx.item_path_str(adt.did),
+            fields: name_fields(&adt.variants[0], read_fields(ecx, ptr, layout, depth)?),
+        },
+        ty::TyAdt(adt, _) if adt.is_enum() => {
+            let discr = ecx.read_discriminant_value(Place::from_ptr(ptr), layout.ty)?;
+            let variant_index = match adt.discriminants(ecx.tcx).position(|d| d.to_u128_unchecked() == discr) {
+                Some(index) => index,
+                None => return err!(InvalidDiscriminant),
+            };
+            let variant = &adt.variants[variant_index];
+            let variant_layout = layout.for_variant(ecx, variant_index);
+            HostValue::Enum {
+                name: ecx.tcx.item_path_str(adt.did),
+                variant: variant.name.to_string(),
+                fields: name_fields(variant, read_fields(ecx, ptr, variant_layout, depth)?),
+            }
+        }
+        _ => return err!(Unimplemented(format!("values of type `{}` cannot be read", layout.ty))),
+    })
+}
+
+/// Converts a value that is not in memory, a scalar (`ByVal`) or a pair of scalars (`ByValPair`)
+fn immediate<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    value: Value,
+    layout: TyLayout<'tcx>,
+    depth: usize,
+) -> EvalResult<'tcx, HostValue> {
+    check_depth(depth)?;
+    Ok(match (&layout.ty.sty, value) {
+        (&ty::TyRef(_, ref pointee), value) => pointee_to_host(ecx, value, layout.ty, pointee.ty, depth)?,
+        (&ty::TyAdt(adt, _), value) if adt.is_box() => {
+            pointee_to_host(ecx, value, layout.ty, layout.ty.boxed_ty(), depth)?
+        }
+        (&ty::TyTuple(..), value) => HostValue::Tuple(immediate_fields(ecx, value, layout, depth)?),
+        (&ty::TyAdt(adt, _), value) if adt.is_struct() => HostValue::Struct {
+            name: ecx.tcx.item_path_str(adt.did),
+            fields: name_fields(&adt.variants[0], immediate_fields(ecx, value, layout, depth)?),
+        },
+        (&ty::TyAdt(adt, _), _) if adt.is_enum() => {
+            return err!(Unimplemented(format!("enums that are not in memory cannot be read, got a `{}`", layout.ty)));
+        }
+        (_, Value::ByVal(val)) => primitive(ecx, val, layout)?,
+        _ => return err!(Unimplemented(format!("values of type `{}` cannot be read", layout.ty))),
+    })
+}
+
+/// The fields of a struct or tuple that is not in memory. All of its data is in at most two fields
+/// that are not zero-sized, which hold the scalars of the value.
+fn immediate_fields<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    value: Value,
+    layout: TyLayout<'tcx>,
+    depth: usize,
+) -> EvalResult<'tcx, Vec<HostValue>> {
+    let mut field_layouts = Vec::with_capacity(layout.fields.count());
+    for i in 0..layout.fields.count() {
+        field_layouts.push(layout.field(ecx, i)?);
+    }
+    let mut sized: Vec<usize> = (0..field_layouts.len()).filter(|&i| field_layouts[i].size.bytes() != 0).collect();
+    sized.sort_by_key(|&i| layout.fields.offset(i).bytes());
+    // The value of every field with data
+    let values = match (value, &sized[..]) {
+        (value, &[field]) => vec![(field, value)],
+        (Value::ByValPair(a, b), &[first, second]) => vec![(first, Value::ByVal(a)), (second, Value::ByVal(b))],
+        _ => return err!(Unimplemented(format!("values of type `{}` cannot be read", layout.ty))),
+    };
+    field_layouts
+        .into_iter()
+        .enumerate()
+        .map(|(i, field_layout)| match values.iter().find(|&&(field, _)| field == i) {
+            Some(&(_, value)) => immediate(ecx, value, field_layout, depth),
+            None => zero_sized(ecx, field_layout),
+        })
+        .collect()
+}
+
+/// The value of a zero-sized type, which has no data to read
+fn zero_sized<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    layout: TyLayout<'tcx>,
+) -> EvalResult<'tcx, HostValue> {
+    let mut fields = Vec::with_capacity(layout.fields.count());
+    for i in 0..layout.fields.count() {
+        fields.push(zero_sized(ecx, layout.field(ecx, i)?)?);
+    }
+    Ok(match layout.ty.sty {
+        ty::TyArray(..) => HostValue::Array(fields),
+        ty::TyTuple(..) => HostValue::Tuple(fields),
+        ty::TyAdt(adt, _) if adt.is_struct() => HostValue::Struct {
+            name: ecx.tcx.item_path_str(adt.did),
+            fields: name_fields(&adt.variants[0], fields),
+        },
+        _ => return err!(Unimplemented(format!("values of type `{}` cannot be read", layout.ty))),
+    })
+}
+
+/// Converts the scalar `val` of a primitive type
+fn primitive<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    val: PrimVal,
+    layout: TyLayout<'tcx>,
+) -> EvalResult<'tcx, HostValue> {
+    if let ty::TyRawPtr(_) = layout.ty.sty {
+        return Ok(HostValue::RawPtr(val));
+    }
+    if let PrimVal::Undef = val {
+        return Ok(HostValue::Undef);
+    }
+    Ok(match layout.ty.sty {
+        ty::TyBool => HostValue::Bool(val.to_bool()?),
+        ty::TyChar => {
+            let bits = val.to_bytes()?;
+            match ::std::char::from_u32(bits as u32) {
+                Some(c) => HostValue::Char(c),
+                None => return err!(InvalidChar(bits)),
+            }
+        }
+        ty::TyInt(_) => {
+            // Values that are not in memory are not sign extended yet
+            let bits = layout.size.bytes() as u32 * 8;
+            let bytes = val.to_bytes()? as i128;
+            HostValue::Int(bytes << (128 - bits) >> (128 - bits))
+        }
+        ty::TyUint(_) => HostValue::Uint(val.to_bytes()?),
+        ty::TyFloat(_) if layout.size.bytes() == 4 => HostValue::Float(f32::from_bits(val.to_bytes()? as u32) as f64),
+        ty::TyFloat(_) => HostValue::Float(f64::from_bits(val.to_bytes()? as u64)),
+        ty::TyFnPtr(_) => match val {
+            PrimVal::Ptr(fn_ptr) => {
+                let instance = ecx.memory.get_fn(fn_ptr)?;
+                HostValue::FnPtr(ecx.tcx.item_path_str(instance.def_id()))
+            }
+            _ => HostValue::Undef,
+        },
+        _ => return err!(Unimplemented(format!("values of type `{}` cannot be read", layout.ty))),
+    })
+}
+
+fn check_depth<'tcx>(depth: usize) -> EvalResult<'tcx> {
+    if depth > MAX_DEPTH {
+        return err!(Unimplemented(format!(
+            "values nested deeper than {} references cannot be read, is the data cyclic?",
+            MAX_DEPTH,
+        )));
+    }
+    Ok(())
+}
+
+/// Converts the target of the reference or `Box` `value` of type `ty`
+fn pointee_to_host<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    value: Value,
+    ty: Ty<'tcx>,
+    pointee: Ty<'tcx>,
+    depth: usize,
+) -> EvalResult<'tcx, HostValue> {
+    let host = match (&pointee.sty, value) {
+        (&ty::TyStr, Value::ByValPair(data, len)) => {
+            let bytes = ecx.memory.read_bytes(data.into(), len.to_u64()?)?;
+            HostValue::Str(String::from_utf8_lossy(bytes).into_owned())
+        }
+        (&ty::TySlice(elem), Value::ByValPair(data, len)) => {
+            let data = data.to_ptr()?;
+            let elem_layout = ecx.layout_of(elem)?;
+            let mut elems = Vec::new();
+            for i in 0..len.to_u64()? {
+                let elem_ptr = data.offset(i * elem_layout.size.bytes(), ecx)?;
+                elems.push(read(ecx, elem_ptr, elem_layout, depth + 1)?);
+            }
+            HostValue::Array(elems)
+        }
+        (_, Value::ByVal(PrimVal::Undef)) => return Ok(HostValue::Undef),
+        (_, Value::ByVal(target)) => read(ecx, target.to_ptr()?, ecx.layout_of(pointee)?, depth + 1)?,
+        _ => return err!(Unimplemented(format!("values of type `{}` cannot be read", ty))),
+    };
+    Ok(HostValue::Ref(Box::new(host)))
+}
+
+fn read_fields<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    ptr: MemoryPointer,
+    layout: TyLayout<'tcx>,
+    depth: usize,
+) -> EvalResult<'tcx, Vec<HostValue>> {
+    let mut fields = Vec::with_capacity(layout.fields.count());
+    for i in 0..layout.fields.count() {
+        let field_ptr = ptr.offset(layout.fields.offset(i).bytes(), ecx)?;
+        fields.push(read(ecx, field_ptr, layout.field(ecx, i)?, depth)?);
+    }
+    Ok(fields)
+}
+
+fn name_fields(variant: &ty::VariantDef, values: Vec<HostValue>) -> Vec<(String, HostValue)> {
+    variant.fields.iter().map(|field| field.name.to_string()).zip(values).collect()
+}
@@ -0,0 +1,64 @@
+//! The locals of the frames on the stack, with the names and types they have in the source, for
+//! tools and the debugger. MIR only knows locals by index.
+
+use rustc::mir;
+use rustc::ty::Ty;
+
+use super::*;
+use rustc_compat;
+
+#[derive(Clone, Debug)]
+pub struct LocalInfo<'tcx> {
+    pub local: mir::Local,
+    /// The name of the variable or argument in the source, `None` for temporaries
+    pub name: Option<String>,
+    pub kind: mir::LocalKind,
+    /// The type, with the generics of the frame's function filled in
+    pub ty: Ty<'tcx>,
+    /// `None` while the local is dead (before its `StorageLive` and after its `StorageDead`)
+    pub value: Option<Value>,
+}
+
+impl<'tcx> LocalInfo<'tcx> {
+    /// The name in the source, or the MIR name (e.g. `_3`) for temporaries
+    pub fn display_name(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => format!("{:?}", self.local),
+        }
+    }
+}
+
+pub trait EvalContextExt<'tcx> {
+    /// Every local of the frame `frame` (an index into `stack()`), in MIR order: the return
+    /// place, the arguments, the variables and temporaries
+    fn frame_locals(&self, frame: usize) -> Vec<LocalInfo<'tcx>>;
+
+    /// The local of the frame `frame` named `name` in the source, or by its MIR name (e.g. `_3`)
+    fn find_local(&self, frame: usize, name: &str) -> Option<LocalInfo<'tcx>>;
+}
+
+impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, Evaluator<'tcx>> {
+    fn frame_locals(&self, frame: usize) -> Vec<LocalInfo<'tcx>> {
+        let frame = &self.stack()[frame];
+        frame.mir.local_decls
+            .indices()
+            .map(|local| LocalInfo {
+                local,
+                name: rustc_compat::local_name(frame.mir, local).map(|name| name.to_string()),
+                kind: frame.mir.local_kind(local),
+                ty: self.monomorphize(frame.mir.local_decls[local].ty, frame.instance.substs),
+                value: frame.get_local(local).ok(),
+            })
+            .collect()
+    }
+
+    fn find_local(&self, frame: usize, name: &str) -> Option<LocalInfo<'tcx>> {
+        // Shadowed variables have the same name, the last one is the one in scope most of the time
+        self.frame_locals(frame)
+            .into_iter()
+            .rev()
+            .find(|info| info.name.as_ref().map_or(false, |local_name| local_name == name) ||
+                format!("{:?}", info.local) == name)
+    }
+}
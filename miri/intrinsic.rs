@@ -17,6 +17,14 @@ pub trait EvalContextExt<'tcx> {
         dest_layout: TyLayout<'tcx>,
         target: mir::BasicBlock,
     ) -> EvalResult<'tcx>;
+
+    fn call_simd_intrinsic(
+        &mut self,
+        intrinsic_name: &str,
+        args: &[ValTy<'tcx>],
+        dest: Place,
+        dest_layout: TyLayout<'tcx>,
+    ) -> EvalResult<'tcx>;
 }
 
 impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'tcx>> {
@@ -31,6 +39,9 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
         let substs = instance.substs;
 
         let intrinsic_name = &self.tcx.item_name(instance.def_id())[..];
+        if let Some(ref mut summary) = self.machine.summary {
+            summary.intrinsic(intrinsic_name);
+        }
         match intrinsic_name {
             "align_offset" => {
                 // FIXME: return a real value in case the target allocation has an
@@ -82,10 +93,9 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 }
             }
 
-            "atomic_load" |
-            "atomic_load_relaxed" |
-            "atomic_load_acq" |
-            "volatile_load" => {
+            // There is only one thread and no memory-mapped I/O, so atomic and volatile accesses are
+            // plain reads and writes.
+            _ if intrinsic_name.starts_with("atomic_load") || intrinsic_name == "volatile_load" => {
                 let ptr = self.into_ptr(args[0].value)?;
                 let valty = ValTy {
                     value: Value::by_ref(ptr),
@@ -94,16 +104,34 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 self.write_value(valty, dest)?;
             }
 
-            "atomic_store" |
-            "atomic_store_relaxed" |
-            "atomic_store_rel" |
-            "volatile_store" => {
+            _ if intrinsic_name.starts_with("atomic_store") || intrinsic_name == "volatile_store" => {
                 let ty = substs.type_at(0);
                 let dest = self.into_ptr(args[0].value)?;
                 self.write_value_to_ptr(args[1].value, dest, ty)?;
             }
 
-            "atomic_fence_acq" => {
+            "unaligned_volatile_load" => {
+                // Like `volatile_load`, but without requiring the alignment of the type
+                let ptr = self.into_ptr(args[0].value)?;
+                let valty = ValTy {
+                    value: Value::ByRef(PtrAndAlign { ptr, aligned: false }),
+                    ty: substs.type_at(0),
+                };
+                self.write_value(valty, dest)?;
+            }
+
+            "unaligned_volatile_store" => {
+                let ty = substs.type_at(0);
+                let ptr = self.into_ptr(args[0].value)?;
+                self.write_maybe_aligned_mut(
+                    /*aligned*/
+                    false,
+                    |ectx| ectx.write_value_to_ptr(args[1].value, ptr, ty),
+                )?;
+            }
+
+            _ if intrinsic_name.starts_with("atomic_fence") ||
+                 intrinsic_name.starts_with("atomic_singlethreadfence") => {
                 // we are inherently singlethreaded and singlecored, this is a nop
             }
 
@@ -149,31 +177,16 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 )?;
             }
 
-            "atomic_or" |
-            "atomic_or_acq" |
-            "atomic_or_rel" |
-            "atomic_or_acqrel" |
-            "atomic_or_relaxed" |
-            "atomic_xor" |
-            "atomic_xor_acq" |
-            "atomic_xor_rel" |
-            "atomic_xor_acqrel" |
-            "atomic_xor_relaxed" |
-            "atomic_and" |
-            "atomic_and_acq" |
-            "atomic_and_rel" |
-            "atomic_and_acqrel" |
-            "atomic_and_relaxed" |
-            "atomic_xadd" |
-            "atomic_xadd_acq" |
-            "atomic_xadd_rel" |
-            "atomic_xadd_acqrel" |
-            "atomic_xadd_relaxed" |
-            "atomic_xsub" |
-            "atomic_xsub_acq" |
-            "atomic_xsub_rel" |
-            "atomic_xsub_acqrel" |
-            "atomic_xsub_relaxed" => {
+            _ if intrinsic_name.starts_with("atomic_or") ||
+                 intrinsic_name.starts_with("atomic_xor") ||
+                 intrinsic_name.starts_with("atomic_and") ||
+                 intrinsic_name.starts_with("atomic_nand") ||
+                 intrinsic_name.starts_with("atomic_xadd") ||
+                 intrinsic_name.starts_with("atomic_xsub") ||
+                 intrinsic_name.starts_with("atomic_max") ||
+                 intrinsic_name.starts_with("atomic_min") ||
+                 intrinsic_name.starts_with("atomic_umax") ||
+                 intrinsic_name.starts_with("atomic_umin") => {
                 let ty = substs.type_at(0);
                 let ptr = self.into_ptr(args[0].value)?;
                 let change = self.value_to_primval(args[1])?;
@@ -182,20 +195,32 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     Value::ByVal(val) => val,
                     Value::ByRef { .. } => bug!("just read the value, can't be byref"),
                     Value::ByValPair(..) => {
-                        bug!("atomic_xadd_relaxed doesn't work with nonprimitives")
+                        bug!("{} doesn't work with nonprimitives", intrinsic_name)
                     }
                 };
                 self.write_primval(dest, old, ty)?;
                 let op = match intrinsic_name.split('_').nth(1).unwrap() {
                     "or" => mir::BinOp::BitOr,
                     "xor" => mir::BinOp::BitXor,
-                    "and" => mir::BinOp::BitAnd,
+                    "and" | "nand" => mir::BinOp::BitAnd,
                     "xadd" => mir::BinOp::Add,
                     "xsub" => mir::BinOp::Sub,
+                    // The signedness of the comparison comes from `ty`
+                    "max" | "umax" => mir::BinOp::Gt,
+                    "min" | "umin" => mir::BinOp::Lt,
                     _ => bug!(),
                 };
                 // FIXME: what do atomics do on overflow?
                 let (val, _) = self.binary_op(op, old, ty, change, ty)?;
+                let val = match op {
+                    mir::BinOp::Gt | mir::BinOp::Lt => if val.to_bool()? { old } else { change },
+                    _ if intrinsic_name.starts_with("atomic_nand") => {
+                        let bits = self.layout_of(ty)?.size.bits();
+                        let mask = if bits >= 128 { !0 } else { (1u128 << bits) - 1 };
+                        self.binary_op(mir::BinOp::BitXor, val, ty, PrimVal::Bytes(mask), ty)?.0
+                    }
+                    _ => val,
+                };
                 self.write_primval(Place::from_primval_ptr(ptr), val, ty)?;
             }
 
@@ -207,7 +232,11 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 let elem_layout = self.layout_of(elem_ty)?;
                 let elem_size = elem_layout.size.bytes();
                 let count = self.value_to_primval(args[2])?.to_u64()?;
-                if count * elem_size != 0 {
+                let size = match count.checked_mul(elem_size) {
+                    Some(size) => size,
+                    None => return err!(OverflowingMath),
+                };
+                if size != 0 {
                     // TODO: We do not even validate alignment for the 0-bytes case.  libstd relies on this in vec::IntoIter::next.
                     // Also see the write_bytes intrinsic.
                     let elem_align = elem_layout.align.abi();
@@ -216,7 +245,7 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     self.memory.copy(
                         src,
                         dest,
-                        count * elem_size,
+                        size,
                         elem_align,
                         intrinsic_name.ends_with("_nonoverlapping"),
                     )?;
@@ -247,7 +276,8 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
             }
 
             "sinf32" | "fabsf32" | "cosf32" | "sqrtf32" | "expf32" | "exp2f32" | "logf32" |
-            "log10f32" | "log2f32" | "floorf32" | "ceilf32" | "truncf32" => {
+            "log10f32" | "log2f32" | "floorf32" | "ceilf32" | "truncf32" | "roundf32" |
+            "rintf32" | "nearbyintf32" => {
                 let f = self.value_to_primval(args[0])?.to_bytes()?;
                 let f = f32::from_bits(f as u32);
                 let f = match intrinsic_name {
@@ -263,13 +293,21 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     "floorf32" => f.floor(),
                     "ceilf32" => f.ceil(),
                     "truncf32" => f.trunc(),
+                    "roundf32" => f.round(),
+                    // We only support the default rounding mode, round to nearest with ties to even
+                    "rintf32" | "nearbyintf32" => if (f - f.trunc()).abs() == 0.5 {
+                        2.0 * (f / 2.0).round()
+                    } else {
+                        f.round()
+                    },
                     _ => bug!(),
                 };
                 self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_layout.ty)?;
             }
 
             "sinf64" | "fabsf64" | "cosf64" | "sqrtf64" | "expf64" | "exp2f64" | "logf64" |
-            "log10f64" | "log2f64" | "floorf64" | "ceilf64" | "truncf64" => {
+            "log10f64" | "log2f64" | "floorf64" | "ceilf64" | "truncf64" | "roundf64" |
+            "rintf64" | "nearbyintf64" => {
                 let f = self.value_to_primval(args[0])?.to_bytes()?;
                 let f = f64::from_bits(f as u64);
                 let f = match intrinsic_name {
@@ -285,6 +323,37 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     "floorf64" => f.floor(),
                     "ceilf64" => f.ceil(),
                     "truncf64" => f.trunc(),
+                    "roundf64" => f.round(),
+                    // We only support the default rounding mode, round to nearest with ties to even
+                    "rintf64" | "nearbyintf64" => if (f - f.trunc()).abs() == 0.5 {
+                        2.0 * (f / 2.0).round()
+                    } else {
+                        f.round()
+                    },
+                    _ => bug!(),
+                };
+                self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_layout.ty)?;
+            }
+
+            "copysignf32" | "minnumf32" | "maxnumf32" => {
+                let a = f32::from_bits(self.value_to_primval(args[0])?.to_bytes()? as u32);
+                let b = f32::from_bits(self.value_to_primval(args[1])?.to_bytes()? as u32);
+                let f = match intrinsic_name {
+                    "copysignf32" => f32::from_bits((a.to_bits() & !(1 << 31)) | (b.to_bits() & (1 << 31))),
+                    "minnumf32" => a.min(b),
+                    "maxnumf32" => a.max(b),
+                    _ => bug!(),
+                };
+                self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_layout.ty)?;
+            }
+
+            "copysignf64" | "minnumf64" | "maxnumf64" => {
+                let a = f64::from_bits(self.value_to_primval(args[0])?.to_bytes()? as u64);
+                let b = f64::from_bits(self.value_to_primval(args[1])?.to_bytes()? as u64);
+                let f = match intrinsic_name {
+                    "copysignf64" => f64::from_bits((a.to_bits() & !(1 << 63)) | (b.to_bits() & (1 << 63))),
+                    "minnumf64" => a.min(b),
+                    "maxnumf64" => a.max(b),
                     _ => bug!(),
                 };
                 self.write_primval(dest, PrimVal::Bytes(f.to_bits() as u128), dest_layout.ty)?;
@@ -533,6 +602,17 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
 
             "transmute" => {
                 let src_ty = substs.type_at(0);
+                // Type checking rejects this for all but the most exotic generic code
+                let src_size = self.layout_of(src_ty)?.size.bytes();
+                if src_size != dest_layout.size.bytes() {
+                    return err!(Intrinsic(format!(
+                        "transmute from `{}` ({} bytes) to `{}` ({} bytes)",
+                        src_ty,
+                        src_size,
+                        dest_layout.ty,
+                        dest_layout.size.bytes(),
+                    )));
+                }
                 let ptr = self.force_allocation(dest)?.to_ptr()?;
                 self.write_maybe_aligned_mut(
                     /*aligned*/
@@ -637,13 +717,21 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 let ptr = self.into_ptr(args[0].value)?;
                 let count = self.value_to_primval(args[2])?.to_u64()?;
                 if count > 0 {
+                    let size = match count.checked_mul(ty_layout.size.bytes()) {
+                        Some(size) => size,
+                        None => return err!(OverflowingMath),
+                    };
                     // HashMap relies on write_bytes on a NULL ptr with count == 0 to work
                     // TODO: Should we, at least, validate the alignment? (Also see the copy intrinsic)
                     self.memory.check_align(ptr, ty_layout.align.abi(), Some(AccessKind::Write))?;
-                    self.memory.write_repeat(ptr, val_byte, ty_layout.size.bytes() * count)?;
+                    self.memory.write_repeat(ptr, val_byte, size)?;
                 }
             }
 
+            _ if intrinsic_name.starts_with("simd_") => {
+                self.call_simd_intrinsic(intrinsic_name, args, dest, dest_layout)?;
+            }
+
             name => return err!(Unimplemented(format!("unimplemented intrinsic: {}", name))),
         }
 
@@ -654,6 +742,157 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
         // current frame.
         Ok(())
     }
+
+    /// Platform intrinsics on `repr(simd)` vectors, computed element by element
+    fn call_simd_intrinsic(
+        &mut self,
+        intrinsic_name: &str,
+        args: &[ValTy<'tcx>],
+        dest: Place,
+        dest_layout: TyLayout<'tcx>,
+    ) -> EvalResult<'tcx> {
+        let tcx = self.tcx;
+        let u32 = tcx.types.u32;
+        let in_ty = args[0].ty;
+        let in_elem_ty = in_ty.simd_type(tcx);
+        let in_elem_size = self.layout_of(in_elem_ty)?.size.bytes();
+        let in_len = in_ty.simd_size(tcx) as u64;
+        let in_ptr = simd_operand_ptr(args[0].value)?;
+
+        let read_elem = |this: &Self, ptr: Pointer, i: u64, elem_ty: ty::Ty<'tcx>, elem_size: u64| -> EvalResult<'tcx, PrimVal> {
+            let elem_ptr = ptr.offset(i * elem_size, this)?;
+            this.value_to_primval(ValTy { value: Value::by_ref(elem_ptr), ty: elem_ty })
+        };
+
+        let result = match intrinsic_name {
+            "simd_extract" => {
+                let idx = self.value_to_primval(args[1])?.to_u64()?;
+                if idx >= in_len {
+                    return err!(Intrinsic(format!("simd_extract index {} out of bounds for a vector of {} elements", idx, in_len)));
+                }
+                let val = read_elem(self, in_ptr, idx, in_elem_ty, in_elem_size)?;
+                return self.write_primval(dest, val, dest_layout.ty);
+            }
+
+            "simd_insert" => {
+                let idx = self.value_to_primval(args[1])?.to_u64()?;
+                if idx >= in_len {
+                    return err!(Intrinsic(format!("simd_insert index {} out of bounds for a vector of {} elements", idx, in_len)));
+                }
+                let new_val = self.value_to_primval(args[2])?;
+                let mut result = Vec::new();
+                for i in 0..in_len {
+                    result.push(if i == idx {
+                        new_val
+                    } else {
+                        read_elem(self, in_ptr, i, in_elem_ty, in_elem_size)?
+                    });
+                }
+                result
+            }
+
+            _ if intrinsic_name.starts_with("simd_shuffle") => {
+                let other_ptr = simd_operand_ptr(args[1].value)?;
+                let idx_ptr = simd_operand_ptr(args[2].value)?;
+                let out_len = dest_layout.ty.simd_size(tcx) as u64;
+                let mut result = Vec::new();
+                for i in 0..out_len {
+                    let idx = read_elem(self, idx_ptr, i, u32, 4)?.to_u64()?;
+                    result.push(if idx < in_len {
+                        read_elem(self, in_ptr, idx, in_elem_ty, in_elem_size)?
+                    } else if idx < 2 * in_len {
+                        read_elem(self, other_ptr, idx - in_len, in_elem_ty, in_elem_size)?
+                    } else {
+                        return err!(Intrinsic(format!("{} index {} out of bounds for two vectors of {} elements", intrinsic_name, idx, in_len)));
+                    });
+                }
+                result
+            }
+
+            "simd_cast" => {
+                let out_elem_ty = dest_layout.ty.simd_type(tcx);
+                let mut result = Vec::new();
+                for i in 0..in_len {
+                    let val = read_elem(self, in_ptr, i, in_elem_ty, in_elem_size)?;
+                    result.push(self.cast_primval(val, in_elem_ty, out_elem_ty)?);
+                }
+                result
+            }
+
+            "simd_eq" | "simd_ne" | "simd_lt" | "simd_le" | "simd_gt" | "simd_ge" => {
+                let other_ptr = simd_operand_ptr(args[1].value)?;
+                let op = match intrinsic_name {
+                    "simd_eq" => mir::BinOp::Eq,
+                    "simd_ne" => mir::BinOp::Ne,
+                    "simd_lt" => mir::BinOp::Lt,
+                    "simd_le" => mir::BinOp::Le,
+                    "simd_gt" => mir::BinOp::Gt,
+                    "simd_ge" => mir::BinOp::Ge,
+                    _ => bug!(),
+                };
+                // `true` is an element with all bits set
+                let out_elem_ty = dest_layout.ty.simd_type(tcx);
+                let out_bits = self.layout_of(out_elem_ty)?.size.bits();
+                let all_ones = if out_bits >= 128 { !0 } else { (1u128 << out_bits) - 1 };
+                let mut result = Vec::new();
+                for i in 0..in_len {
+                    let left = read_elem(self, in_ptr, i, in_elem_ty, in_elem_size)?;
+                    let right = read_elem(self, other_ptr, i, in_elem_ty, in_elem_size)?;
+                    let (res, _) = self.binary_op(op, left, in_elem_ty, right, in_elem_ty)?;
+                    result.push(PrimVal::Bytes(if res.to_bool()? { all_ones } else { 0 }));
+                }
+                result
+            }
+
+            "simd_add" | "simd_sub" | "simd_mul" | "simd_div" | "simd_rem" | "simd_shl" |
+            "simd_shr" | "simd_and" | "simd_or" | "simd_xor" => {
+                let other_ptr = simd_operand_ptr(args[1].value)?;
+                let op = match intrinsic_name {
+                    "simd_add" => mir::BinOp::Add,
+                    "simd_sub" => mir::BinOp::Sub,
+                    "simd_mul" => mir::BinOp::Mul,
+                    "simd_div" => mir::BinOp::Div,
+                    "simd_rem" => mir::BinOp::Rem,
+                    "simd_shl" => mir::BinOp::Shl,
+                    "simd_shr" => mir::BinOp::Shr,
+                    "simd_and" => mir::BinOp::BitAnd,
+                    "simd_or" => mir::BinOp::BitOr,
+                    "simd_xor" => mir::BinOp::BitXor,
+                    _ => bug!(),
+                };
+                let mut result = Vec::new();
+                for i in 0..in_len {
+                    let left = read_elem(self, in_ptr, i, in_elem_ty, in_elem_size)?;
+                    let right = read_elem(self, other_ptr, i, in_elem_ty, in_elem_size)?;
+                    // SIMD arithmetic wraps around
+                    let (res, _) = self.binary_op(op, left, in_elem_ty, right, in_elem_ty)?;
+                    result.push(res);
+                }
+                result
+            }
+
+            name => return err!(Unimplemented(format!("unimplemented intrinsic: {}", name))),
+        };
+
+        // All inputs have been read, now we can write the resulting vector
+        let out_elem_ty = dest_layout.ty.simd_type(tcx);
+        let out_elem_size = self.layout_of(out_elem_ty)?.size.bytes();
+        let dest_ptr = self.force_allocation(dest)?.to_ptr()?;
+        for (i, val) in result.into_iter().enumerate() {
+            let elem_ptr = dest_ptr.offset(i as u64 * out_elem_size, &self)?;
+            self.write_value_to_ptr(Value::ByVal(val), elem_ptr.into(), out_elem_ty)?;
+        }
+        Ok(())
+    }
+}
+
+/// SIMD vectors (and the shuffle indices) are never primitive values, so they always live in memory
+fn simd_operand_ptr<'tcx>(value: Value) -> EvalResult<'tcx, Pointer> {
+    match value {
+        Value::ByRef(PtrAndAlign { ptr, .. }) => Ok(ptr),
+        Value::ByVal(PrimVal::Undef) => err!(ReadUndefBytes),
+        _ => bug!("SIMD operand is not in memory: {:?}", value),
+    }
 }
 
 fn numeric_intrinsic<'tcx>(
@@ -0,0 +1,128 @@
+//! Machine-readable error reports, for IDEs and other tools that would otherwise have to parse the
+//! rendered diagnostics. With `-Zmiri-error-format=json`, every error is printed to stderr as one
+//! line of JSON:
+//!
+//! ```json
+//! {"kind":"ReadUndefBytes","category":"undefined-behavior","message":"attempted to read undefined bytes",
+//!  "span":{"file":"src/main.rs","line_start":4,"column_start":5,"line_end":4,"column_end":10},
+//!  "backtrace":[{"function":"foo","span":{...}},{"function":"main","span":{...}}]}
+//! ```
+//!
+//! The backtrace starts with the innermost frame, `span` is the span of its statement. When the
+//! error happened outside of any function, `span` is `null` and the backtrace is empty.
+
+use std::fmt::Write;
+
+use super::*;
+use rustc_compat;
+
+/// Prints `e` as JSON and counts it as an error of the session, like a rendered diagnostic
+pub(crate) fn report<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, e: &EvalError<'tcx>) {
+    let mut json = String::new();
+    json.push_str("{\"kind\":");
+    push_str(&mut json, &error_kind(e));
+    json.push_str(",\"category\":");
+    push_str(&mut json, e.category().code());
+    json.push_str(",\"message\":");
+    push_str(&mut json, &e.to_string());
+    json.push_str(",\"span\":");
+    match ecx.stack().last() {
+        Some(frame) => push_span(&mut json, ecx, rustc_compat::frame_span(frame)),
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"backtrace\":[");
+    for (i, frame) in ecx.stack().iter().rev().enumerate() {
+        if i != 0 {
+            json.push(',');
+        }
+        json.push_str("{\"function\":");
+        push_str(&mut json, &rustc_compat::frame_fn_path(ecx.tcx, frame));
+        json.push_str(",\"span\":");
+        push_span(&mut json, ecx, rustc_compat::frame_span(frame));
+        json.push('}');
+    }
+    json.push_str("]}");
+    eprintln!("{}", json);
+    ecx.tcx.sess.diagnostic().bump_err_count();
+}
+
+/// The name of the error's variant, e.g. `ReadUndefBytes`, or of the `MiriError` variant
+pub(crate) fn error_kind(e: &EvalError) -> String {
+    let debug = match MiriError::of(e) {
+        Some(miri_error) => format!("{:?}", miri_error),
+        None => format!("{:?}", e.kind),
+    };
+    let end = debug.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(debug.len());
+    debug[..end].to_owned()
+}
+
+fn push_span<'a, 'tcx>(json: &mut String, ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, span: Span) {
+    let (file, line_start, column_start, line_end, column_end) = rustc_compat::span_lines(ecx.tcx, span);
+    json.push_str("{\"file\":");
+    push_str(json, &file);
+    write!(
+        json,
+        ",\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{}}}",
+        line_start,
+        column_start,
+        line_end,
+        column_end,
+    ).unwrap();
+}
+
+/// Appends `s` as a JSON string literal
+fn push_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_str(s: &str) -> String {
+        let mut json = String::new();
+        push_str(&mut json, s);
+        json
+    }
+
+    #[test]
+    fn plain_strings() {
+        assert_eq!(json_str(""), "\"\"");
+        assert_eq!(json_str("std::ptr::read"), "\"std::ptr::read\"");
+        assert_eq!(json_str("größe"), "\"größe\"");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(json_str("a \"quote\""), "\"a \\\"quote\\\"\"");
+        assert_eq!(json_str("C:\\src"), "\"C:\\\\src\"");
+        assert_eq!(json_str("one\ntwo\r\tthree"), "\"one\\ntwo\\r\\tthree\"");
+        assert_eq!(json_str("\u{0}\u{1f}"), "\"\\u0000\\u001f\"");
+    }
+
+    #[test]
+    fn kind_of_compiler_errors() {
+        assert_eq!(error_kind(&EvalErrorKind::ReadUndefBytes.into()), "ReadUndefBytes");
+        // Only the name of the variant, without its fields
+        assert_eq!(error_kind(&EvalErrorKind::Unimplemented("foo".to_owned()).into()), "Unimplemented");
+    }
+
+    #[test]
+    fn kind_of_miri_errors() {
+        assert_eq!(error_kind(&MiriError::OutOfMemoryAbort.into()), "OutOfMemoryAbort");
+        let e = MiriError::LimitReached(Limit::CallsPerFn, "too many calls".to_owned()).into();
+        assert_eq!(error_kind(&e), "LimitReached");
+    }
+}
@@ -19,14 +19,16 @@ extern crate lazy_static;
 
 use rustc::ty::{self, TyCtxt};
 use rustc::ty::layout::{TyLayout, LayoutOf};
-use rustc::hir::def_id::DefId;
+use rustc::hir;
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
 use rustc::mir;
 use rustc::traits;
 
 use syntax::ast::Mutability;
 use syntax::codemap::Span;
 
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::cell::{Cell, RefCell};
 
 pub use rustc::mir::interpret::*;
 pub use rustc_mir::interpret::*;
@@ -40,6 +42,38 @@ mod tls;
 mod locks;
 mod range_map;
 mod validation;
+mod profile;
+mod breakpoint;
+mod traffic;
+mod describe;
+mod rustc_compat;
+mod overflow;
+mod summary;
+mod constant_time;
+mod misalignment;
+mod fs;
+mod outcome;
+mod shims;
+mod undef;
+mod addresses;
+mod loops;
+mod limits;
+mod json;
+mod category;
+mod call;
+mod host;
+mod observer;
+mod debugger;
+mod inspect;
+mod trace;
+mod coverage;
+mod timings;
+mod heap_graph;
+mod dump;
+mod alloc_stats;
+mod error;
+mod reports;
+mod sysroot;
 
 use fn_call::EvalContextExt as MissingFnsEvalContextExt;
 use operator::EvalContextExt as OperatorEvalContextExt;
@@ -50,13 +84,81 @@ use locks::MemoryExt as LockMemoryExt;
 use validation::EvalContextExt as ValidationEvalContextExt;
 use range_map::RangeMap;
 use validation::{ValidationQuery, AbsPlace};
+pub use describe::EvalContextExt as DescribeEvalContextExt;
+pub use constant_time::check_constant_time;
+pub use outcome::{EvalOutcome, Limit};
+pub use error::MiriError;
+pub use reports::Reports;
+pub use shims::{ForeignShim, ForeignShims};
+pub use limits::{InvalidLimit, ResourceLimitsBuilder, ResourceLimitsExt};
+pub use category::{ErrorCategory, EvalErrorExt};
+pub use call::{eval_fn, new_eval_context};
+pub use host::{HostValue, EvalContextExt as HostEvalContextExt};
+pub use observer::{StepLocation, StepObserver, StepObservers};
+pub use inspect::{LocalInfo, EvalContextExt as InspectEvalContextExt};
+pub use heap_graph::write_heap_graph;
+pub use dump::dump_alloc;
+pub use sysroot::{cache_dir, fullmir_sysroot};
 
 pub fn eval_main<'a, 'tcx: 'a>(
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     main_id: DefId,
     start_wrapper: Option<DefId>,
     limits: ResourceLimits,
-) {
+    config: MiriConfig,
+) -> EvalOutcome<'tcx> {
+    let mut reports = Reports::new(tcx, &config);
+    let outcome = eval_main_with_reports(tcx, main_id, start_wrapper, limits, config, &mut reports);
+    reports.write(tcx);
+    outcome
+}
+
+/// Evaluates the next statement or terminator with everything that watches the evaluation
+/// (observers, the debugger, the profiler, the loop detector, ...) run around it. Returns `false`
+/// once the program's outermost frame returned, like `EvalContext::step`. `eval_main`, `eval_fn`
+/// and the constant time check all step with it, so they see the same hooks.
+pub(crate) fn step<'a, 'tcx: 'a>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) -> EvalResult<'tcx, bool> {
+    let backtrace = breakpoint::backtrace(ecx);
+    ecx.memory.data.current_span.set(ecx.stack().last().map(rustc_compat::frame_span));
+    let span = traffic::next_span(ecx);
+    overflow::check_next_statement(ecx);
+    observer::before_step(ecx);
+    debugger::before_step(ecx)?;
+    trace::before_step(ecx);
+    coverage::before_step(ecx);
+    let depth = ecx.stack().len();
+    let start = timings::start(&ecx.memory.data);
+    let stepped = ecx.step();
+    timings::stop(&ecx.memory.data, timings::Category::Steps, start);
+    if !stepped? {
+        return Ok(false);
+    }
+    ecx.machine.steps += 1;
+    loops::step(ecx)?;
+    let new_depth = ecx.stack().len();
+    if let Some(ref mut summary) = ecx.machine.summary {
+        summary.steps += 1;
+        if new_depth > depth {
+            summary.frames += (new_depth - depth) as u64;
+        }
+    }
+    traffic::step(ecx, span);
+    profile::step(ecx);
+    breakpoint::step(ecx);
+    breakpoint::report_alloc_events(ecx, backtrace);
+    Ok(true)
+}
+
+/// Like `eval_main`, but records the profile, the trace and the coverage in `reports`, which may
+/// cover other evaluations as well, instead of writing them
+pub fn eval_main_with_reports<'a, 'tcx: 'a>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    main_id: DefId,
+    start_wrapper: Option<DefId>,
+    limits: ResourceLimits,
+    config: MiriConfig,
+    reports: &mut Reports<'tcx>,
+) -> EvalOutcome<'tcx> {
     fn run_main<'a, 'tcx: 'a>(
         ecx: &mut rustc_mir::interpret::EvalContext<'a, 'tcx, Evaluator<'tcx>>,
         main_id: DefId,
@@ -114,21 +216,36 @@ pub fn eval_main<'a, 'tcx: 'a>(
                 dest,
             )?;
 
-            // Second argument (argc): 1
+            // The program name (the crate name, for lack of a binary path) followed by the arguments
+            let crate_name = ecx.tcx.crate_name(LOCAL_CRATE).as_str();
+            let program_args = ecx.machine.config.args.clone();
+            let argv_strs: Vec<&str> = ::std::iter::once(&*crate_name)
+                .chain(program_args.iter().map(|arg| &arg[..]))
+                .collect();
+
+            // Second argument (argc): number of entries in argv
             let dest = ecx.eval_place(&mir::Place::Local(args.next().unwrap()))?;
             let ty = ecx.tcx.types.isize;
-            ecx.write_primval(dest, PrimVal::Bytes(1), ty)?;
+            ecx.write_primval(dest, PrimVal::Bytes(argv_strs.len() as u128), ty)?;
 
-            // FIXME: extract main source file path
-            // Third argument (argv): &[b"foo"]
+            // Third argument (argv): NULL-terminated array of pointers to C strings
             let dest = ecx.eval_place(&mir::Place::Local(args.next().unwrap()))?;
             let ty = ecx.tcx.mk_imm_ptr(ecx.tcx.mk_imm_ptr(ecx.tcx.types.u8));
-            let foo = ecx.memory.allocate_cached(b"foo\0");
             let ptr_size = ecx.memory.pointer_size();
-            let foo_ptr = ecx.memory.allocate(ptr_size * 1, ptr_size, None)?;
-            ecx.memory.write_primval(foo_ptr.into(), PrimVal::Ptr(foo.into()), ptr_size, false)?;
-            ecx.memory.mark_static_initalized(foo_ptr.alloc_id, Mutability::Immutable)?;
-            ecx.write_ptr(dest, foo_ptr.into(), ty)?;
+            let argv = ecx.memory.allocate(ptr_size * (argv_strs.len() as u64 + 1), ptr_size, None)?;
+            for (i, arg) in argv_strs.iter().enumerate() {
+                // +1 for the null terminator
+                let arg_ptr = ecx.memory.allocate(arg.len() as u64 + 1, 1, None)?;
+                ecx.memory.write_bytes(arg_ptr.into(), arg.as_bytes())?;
+                let trailing_zero_ptr = arg_ptr.offset(arg.len() as u64, &ecx)?;
+                ecx.memory.write_bytes(trailing_zero_ptr.into(), &[0])?;
+                let elem_ptr = argv.offset(i as u64 * ptr_size, &ecx)?;
+                ecx.memory.write_primval(elem_ptr, PrimVal::Ptr(arg_ptr), ptr_size, false)?;
+            }
+            let end_ptr = argv.offset(argv_strs.len() as u64 * ptr_size, &ecx)?;
+            ecx.memory.write_primval(end_ptr, PrimVal::Bytes(0), ptr_size, false)?;
+            ecx.memory.mark_static_initalized(argv.alloc_id, Mutability::Immutable)?;
+            ecx.write_ptr(dest, argv.into(), ty)?;
 
             assert!(args.next().is_none(), "start lang item has more arguments than expected");
         } else {
@@ -145,8 +262,13 @@ pub fn eval_main<'a, 'tcx: 'a>(
             assert!(args.next().is_none(), "main function must not have arguments");
         }
 
-        while ecx.step()? {}
-        ecx.run_tls_dtors()?;
+        // Report what happened to the tracked allocations while setting up the program
+        breakpoint::report_alloc_events(ecx, None);
+        while step(ecx)? {}
+        if ecx.machine.exit_code.is_none() {
+            // `exit` does not run the thread-local destructors
+            ecx.run_tls_dtors()?;
+        }
         if let Some(cleanup_ptr) = cleanup_ptr {
             ecx.memory_mut().deallocate(
                 cleanup_ptr,
@@ -157,28 +279,315 @@ pub fn eval_main<'a, 'tcx: 'a>(
         Ok(())
     }
 
-    let mut ecx = EvalContext::new(tcx, ty::ParamEnv::empty(traits::Reveal::All), limits, Default::default(), Default::default());
-    match run_main(&mut ecx, main_id, start_wrapper) {
+    let traffic = config.memory_hotspots.map(traffic::Traffic::new);
+    let summary = if config.summary { Some(Default::default()) } else { None };
+    let debugger = if config.debug { Some(Default::default()) } else { None };
+    let memory_data = MemoryData {
+        tracked_allocs: config.track_alloc_ids.iter().cloned().collect(),
+        timings: RefCell::new(timings::Timings::new(config.self_profile)),
+        // The summary reports the allocation counts and the peak heap usage as well
+        alloc_stats: if config.alloc_stats || config.summary { Some(Default::default()) } else { None },
+        ..Default::default()
+    };
+    let machine = Evaluator {
+        config,
+        profiler: reports.profiler.take(),
+        traffic,
+        summary,
+        debugger,
+        tracer: reports.tracer.take(),
+        coverage: reports.coverage.take(),
+        ..Default::default()
+    };
+    let mut ecx = EvalContext::new(tcx, ty::ParamEnv::empty(traits::Reveal::All), limits, machine, memory_data);
+    let res = run_main(&mut ecx, main_id, start_wrapper);
+    // Programs that ended in an error are part of the reports too, the profile may well explain
+    // the error and the coverage shows how far they got
+    reports.profiler = ecx.machine.profiler.take();
+    reports.tracer = ecx.machine.tracer.take();
+    reports.coverage = ecx.machine.coverage.take();
+    ecx.memory.data.timings.borrow().print();
+    // Before the leak check, when the stack of failed runs still points into the heap
+    if let Some(ref file) = ecx.machine.config.heap_graph {
+        if let Err(e) = write_heap_graph(&ecx, file) {
+            tcx.sess.err(&format!("could not write the heap graph: {}", e));
+        }
+    }
+    if let Some(ref traffic) = ecx.machine.traffic {
+        traffic.report(tcx);
+    }
+    if let (true, Some(stats)) = (ecx.machine.config.alloc_stats, ecx.memory.data.alloc_stats.as_ref()) {
+        stats.borrow().report(tcx);
+    }
+    match res {
         Ok(()) => {
+            if let Some(exit_code) = ecx.machine.exit_code {
+                // The program called `exit`, everything still reachable from the heap is
+                // expected to be left over
+                return EvalOutcome::Completed { exit_code };
+            }
             let leaks = ecx.memory().leak_report();
+            if let Some(ref summary) = ecx.machine.summary {
+                let stats = ecx.memory().data.alloc_stats.as_ref().expect("the summary counts allocations");
+                summary.print(&stats.borrow(), leaks);
+            }
             if leaks != 0 {
                 tcx.sess.err("the evaluated program leaked memory");
             }
+            EvalOutcome::Completed { exit_code: 0 }
+        }
+        Err(ref e) if MiriError::of(e).map_or(false, |e| match *e {
+            MiriError::StoppedByDebugger => true,
+            _ => false,
+        }) => {
+            // The user asked for it, this is not an error
+            tcx.sess.note_without_error("the evaluation was stopped by the debugger");
+            EvalOutcome::StoppedByDebugger
         }
         Err(mut e) => {
-            ecx.report(&mut e);
+            // The panic of a `#[should_panic]` test is its success, the driver checks the message
+            let expected_panic = ecx.machine.config.expect_panic && match e.kind {
+                EvalErrorKind::Panic => true,
+                _ => false,
+            };
+            if !expected_panic {
+                breakpoint::on_error(&ecx, &e);
+                report_error(&ecx, &mut e);
+                misalignment::explain(&ecx, &e);
+                undef::explain(&ecx, &e);
+            }
+            let panic = ecx.machine.panic.take();
+            EvalOutcome::from_error(e, panic)
         }
     }
 }
 
+/// Evaluates every constant and static of the local crate, including array lengths and enum
+/// discriminants, without running the program. Errors are reported to the session.
+pub fn eval_consts<'a, 'tcx: 'a>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    limits: ResourceLimits,
+    config: MiriConfig,
+) {
+    fn eval_const<'a, 'tcx: 'a>(
+        ecx: &mut rustc_mir::interpret::EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+        def_id: DefId,
+    ) -> EvalResult<'tcx> {
+        let instance = ty::Instance::mono(ecx.tcx, def_id);
+        let mir = ecx.load_mir(instance.def)?;
+        let layout = ecx.layout_of(mir.return_ty())?;
+        let ptr = ecx.memory_mut().allocate(
+            layout.size.bytes(),
+            layout.align.abi(),
+            Some(MemoryKind::Stack),
+        )?;
+        ecx.push_stack_frame(
+            instance,
+            mir.span,
+            mir,
+            Place::from_ptr(ptr),
+            StackPopCleanup::None,
+        )?;
+        while ecx.step()? {}
+        Ok(())
+    }
+
+    for &body_id in tcx.hir.krate().bodies.keys() {
+        let node_id = tcx.hir.body_owner(body_id);
+        match tcx.hir.body_owner_kind(node_id) {
+            hir::BodyOwnerKind::Const |
+            hir::BodyOwnerKind::Static(_) => {}
+            hir::BodyOwnerKind::Fn => continue,
+        }
+        let def_id = tcx.hir.local_def_id(node_id);
+        // Constants in generic impls and traits only have a value once the generics are known
+        let generics = tcx.generics_of(def_id);
+        if generics.parent_types != 0 || !generics.types.is_empty() {
+            continue;
+        }
+        trace!("Checking constant {:?}", def_id);
+        let machine = Evaluator {
+            config: config.clone(),
+            ..Default::default()
+        };
+        let mut ecx = EvalContext::new(tcx, ty::ParamEnv::empty(traits::Reveal::All), limits, machine, Default::default());
+        if let Err(mut e) = eval_const(&mut ecx, def_id) {
+            report_error(&ecx, &mut e);
+        }
+    }
+}
+
+/// Reports `e` to the user, rendered or as JSON
+fn report_error<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, e: &mut EvalError<'tcx>) {
+    if ecx.machine.config.json_errors {
+        json::report(ecx, e);
+    } else {
+        ecx.report(e);
+    }
+}
+
+/// Configuration of the interpreted program's environment, as set up by the driver
+#[derive(Clone, Debug, Default)]
+pub struct MiriConfig {
+    /// Arguments passed to the interpreted program, without the program name
+    pub args: Vec<String>,
+
+    /// Let writes to stdout and stderr only write part of the buffer, to exercise the
+    /// program's handling of partial writes
+    pub short_writes: bool,
+
+    /// Let the standard streams be a terminal of the given size (columns, rows)
+    pub tty_size: Option<(u16, u16)>,
+
+    /// Sample the program's call stack every given number of steps and write the samples to the
+    /// given file (file, interval)
+    pub profile: Option<(String, u64)>,
+
+    /// Dump the interpreter state if the evaluation fails with the `EvalErrorKind` of this name
+    pub break_on_error: Option<String>,
+
+    /// Dump the interpreter state the first time the given byte has the given value
+    /// (allocation id, offset, value)
+    pub break_on_byte: Option<(u64, u64, u8)>,
+
+    /// Report the given number of statements that read and wrote the most bytes
+    pub memory_hotspots: Option<usize>,
+
+    /// Warn about overflowing arithmetic that behaves differently with and without overflow checks
+    pub warn_overflow_profile: bool,
+
+    /// Stop the program once any function was called more often than this
+    pub max_calls_per_fn: Option<u64>,
+
+    /// Print statistics about the run when the program completes successfully
+    pub summary: bool,
+
+    /// The name of a crate providing replacements for functions without MIR, at the same path
+    /// relative to its root as the original function
+    pub model_crate: Option<String>,
+
+    /// Check that the given function behaves the same for the contents of two files
+    /// (function path, first input file, second input file)
+    pub constant_time: Option<(String, String, String)>,
+
+    /// Seed of the random bytes handed out to the program, e.g. for `HashMap`'s hash keys
+    pub seed: u64,
+
+    /// Let the program read the host's clocks instead of a virtual clock advancing with every step
+    pub host_time: bool,
+
+    /// Let the program open, read and write the host's files
+    pub allow_fs: bool,
+
+    /// Host functions emulating foreign functions, for embedders
+    pub shims: ForeignShims,
+
+    /// Callbacks run before every step, for embedders
+    pub step_observers: StepObservers,
+
+    /// Do not check that `bool`s, `char`s and `str`s are valid when validating values at function
+    /// boundaries
+    pub disable_value_checks: bool,
+
+    /// Give allocations integer addresses, so that pointers can be used as plain numbers
+    pub virtual_addresses: bool,
+
+    /// Report when these allocations are created, read, written and freed
+    pub track_alloc_ids: Vec<u64>,
+
+    /// Print errors as JSON instead of rendering them
+    pub json_errors: bool,
+
+    /// Stop before the first statement and let the user step through the program
+    pub debug: bool,
+
+    /// Write every executed statement and terminator to this file
+    pub trace: Option<String>,
+
+    /// The program is expected to panic (a `#[should_panic]` test), a panic is not reported as an
+    /// error
+    pub expect_panic: bool,
+
+    /// Write a report of the statements of the local crate that were executed to this file
+    pub coverage: Option<String>,
+
+    /// Print how much time miri spent on what when the program ends
+    pub self_profile: bool,
+
+    /// Write the allocations and the pointers between them to this file as a Graphviz graph when
+    /// the program ends
+    pub heap_graph: Option<String>,
+
+    /// Print the peak memory usage, the sizes of the allocations and where they were made when
+    /// the program ends
+    pub alloc_stats: bool,
+
+    /// Stop programs that get back into a state they were in before, they would never terminate
+    pub detect_loops: bool,
+}
+
 #[derive(Default)]
 pub struct Evaluator<'tcx> {
+    /// Settings given to `eval_main`
+    pub(crate) config: MiriConfig,
+
     /// Environment variables set by `setenv`
     /// Miri does not expose env vars from the host to the emulated program
     pub(crate) env_vars: HashMap<Vec<u8>, MemoryPointer>,
 
     /// Places that were suspended by the validation subsystem, and will be recovered later
     pub(crate) suspended: HashMap<DynamicLifetime, Vec<ValidationQuery<'tcx>>>,
+
+    /// The exit code passed to `exit`, if the program terminated that way
+    pub(crate) exit_code: Option<i32>,
+
+    /// Collects stack samples if profiling is enabled
+    pub(crate) profiler: Option<profile::Profiler>,
+
+    /// Whether the `break_on_byte` breakpoint was hit already
+    pub(crate) byte_breakpoint_hit: bool,
+
+    /// Memory traffic per statement, if requested
+    pub(crate) traffic: Option<traffic::Traffic>,
+
+    /// Statements we already warned about because their overflow behavior depends on the profile
+    pub(crate) overflow_sites: HashSet<Span>,
+
+    /// How often every function was called, if the calls are limited
+    pub(crate) call_counts: HashMap<DefId, u64>,
+
+    /// Statistics about the run, if requested
+    pub(crate) summary: Option<summary::Summary>,
+
+    /// State of the generator of random bytes, seeded from the configuration on first use
+    pub(crate) random_state: Option<u64>,
+
+    /// Steps executed so far, the virtual clock is derived from them
+    pub(crate) steps: u64,
+
+    /// When the program first read a monotonic host clock, if `host_time` is enabled
+    pub(crate) host_start: Option<::std::time::Instant>,
+
+    /// The files the program opened
+    pub(crate) file_handler: fs::FileHandler,
+
+    /// The location of `errno`, once the program used it
+    pub(crate) errno_ptr: Option<MemoryPointer>,
+
+    /// Details about the panic the program is in, if any
+    pub(crate) panic: Option<outcome::PanicInfo>,
+
+    /// Snapshots of the program's state, to find out whether it is stuck in a loop
+    pub(crate) loop_detector: loops::LoopDetector<'tcx>,
+
+    /// The interactive debugger, if requested
+    pub(crate) debugger: Option<debugger::Debugger>,
+
+    /// Writes the executed statements to a file, if requested
+    pub(crate) tracer: Option<trace::Tracer<'tcx>>,
+
+    /// The executed statements, if a coverage report was requested
+    pub(crate) coverage: Option<coverage::Coverage>,
 }
 
 pub type TlsKey = usize;
@@ -202,6 +611,36 @@ pub struct MemoryData<'tcx> {
     /// Only mutable (static mut, heap, stack) allocations have an entry in this map.
     /// The entry is created when allocating the memory and deleted after deallocation.
     locks: HashMap<u64, RangeMap<LockInfo<'tcx>>>,
+
+    /// Bytes read since the last step, for the memory traffic report
+    bytes_read: Cell<u64>,
+
+    /// Bytes written since the last step, for the memory traffic report
+    bytes_written: Cell<u64>,
+
+    /// Everything the program does, while checking for constant time behavior
+    constant_time_trace: Option<RefCell<Vec<constant_time::Event>>>,
+
+    /// Where undefined bytes in memory came from, for explaining reads of them
+    undef_origins: RefCell<undef::Origins>,
+
+    /// The statement the program is evaluating
+    current_span: Cell<Option<Span>>,
+
+    /// The addresses given to allocations, to order them and with `virtual_addresses`
+    addresses: RefCell<addresses::Addresses>,
+
+    /// The allocations to report everything about
+    tracked_allocs: HashSet<u64>,
+
+    /// What happened to the tracked allocations during the current step
+    alloc_events: RefCell<Vec<(u64, breakpoint::AllocEvent)>>,
+
+    /// Where miri spends its time, with `self_profile`
+    timings: RefCell<timings::Timings>,
+
+    /// Statistics about the mutable allocations, with `alloc_stats` or `summary`
+    alloc_stats: Option<RefCell<alloc_stats::AllocStats>>,
 }
 
 impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
@@ -217,7 +656,10 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         span: Span,
         sig: ty::FnSig<'tcx>,
     ) -> EvalResult<'tcx, bool> {
-        ecx.eval_fn_call(instance, destination, args, span, sig)
+        let start = timings::start(&ecx.memory.data);
+        let result = ecx.eval_fn_call(instance, destination, args, span, sig);
+        timings::stop(&ecx.memory.data, timings::Category::Calls, start);
+        result
     }
 
     fn call_intrinsic<'a>(
@@ -228,7 +670,10 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         dest_layout: TyLayout<'tcx>,
         target: mir::BasicBlock,
     ) -> EvalResult<'tcx> {
-        ecx.call_intrinsic(instance, args, dest, dest_layout, target)
+        let start = timings::start(&ecx.memory.data);
+        let result = ecx.call_intrinsic(instance, args, dest, dest_layout, target);
+        timings::stop(&ecx.memory.data, timings::Category::Intrinsics, start);
+        result
     }
 
     fn try_ptr_op<'a>(
@@ -239,7 +684,10 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         right: PrimVal,
         right_ty: ty::Ty<'tcx>,
     ) -> EvalResult<'tcx, Option<(PrimVal, bool)>> {
-        ecx.ptr_op(bin_op, left, left_ty, right, right_ty)
+        let start = timings::start(&ecx.memory.data);
+        let result = ecx.ptr_op(bin_op, left, left_ty, right, right_ty);
+        timings::stop(&ecx.memory.data, timings::Category::PointerOps, start);
+        result
     }
 
     fn mark_static_initialized(m: memory::MemoryKind) -> EvalResult<'tcx> {
@@ -335,7 +783,24 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         size: u64,
         access: AccessKind,
     ) -> EvalResult<'tcx> {
-        mem.check_locks(ptr, size, access)
+        // Every access to memory comes through here
+        let start = timings::start(&mem.data);
+        let counter = match access {
+            AccessKind::Read => &mem.data.bytes_read,
+            AccessKind::Write => &mem.data.bytes_written,
+        };
+        counter.set(counter.get() + size);
+        constant_time::record_access(&mem.data, ptr, size, access);
+        if let AccessKind::Write = access {
+            undef::on_write(mem, ptr, size);
+        }
+        breakpoint::record_alloc_event(&mem.data, ptr.alloc_id.0, match access {
+            AccessKind::Read => breakpoint::AllocEvent::Read { offset: ptr.offset, size },
+            AccessKind::Write => breakpoint::AllocEvent::Written { offset: ptr.offset, size },
+        });
+        let result = mem.check_locks(ptr, size, access);
+        timings::stop(&mem.data, timings::Category::MemoryChecks, start);
+        result
     }
 
     fn add_lock<'a>(
@@ -343,6 +808,9 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         id: u64,
     ) {
         mem.data.locks.insert(id, RangeMap::new());
+        let size = mem.get(AllocId(id)).map(|alloc| alloc.bytes.len() as u64).unwrap_or(0);
+        alloc_stats::allocated(&mem.data, size);
+        breakpoint::record_alloc_event(&mem.data, id, breakpoint::AllocEvent::Created);
     }
 
     fn free_lock<'a>(
@@ -350,6 +818,9 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         id: u64,
         len: u64,
     ) -> EvalResult<'tcx> {
+        alloc_stats::freed(&mem.data, len);
+        mem.data.undef_origins.borrow_mut().remove(&id);
+        breakpoint::record_alloc_event(&mem.data, id, breakpoint::AllocEvent::Freed);
         mem.data.locks
             .remove(&id)
             .expect("allocation has no corresponding locks")
@@ -383,6 +854,9 @@ impl<'tcx> Machine<'tcx> for Evaluator<'tcx> {
         op: ::rustc::mir::ValidationOp,
         operand: &::rustc::mir::ValidationOperand<'tcx, ::rustc::mir::Place<'tcx>>,
     ) -> EvalResult<'tcx> {
-        ecx.validation_op(op, operand)
+        let start = timings::start(&ecx.memory.data);
+        let result = ecx.validation_op(op, operand);
+        timings::stop(&ecx.memory.data, timings::Category::Validation, start);
+        result
     }
 }
@@ -0,0 +1,120 @@
+//! Building the `ResourceLimits` an evaluation runs with. The struct belongs to the compiler's
+//! interpreter and its fields change with it, embedders should go through the builder instead:
+//!
+//! ```rust,ignore
+//! use miri::ResourceLimitsExt;
+//! let limits = miri::ResourceLimits::builder().memory(1 << 30).steps(0).build()?;
+//! ```
+
+use std::fmt;
+
+use super::*;
+
+/// Limits that cannot be evaluated with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InvalidLimit {
+    /// A memory limit of 0 bytes
+    NoMemory,
+    /// A stack limit of 0 frames, not even `main` could be called
+    NoStack,
+}
+
+impl fmt::Display for InvalidLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidLimit::NoMemory => write!(f, "the memory limit must be at least 1 byte"),
+            InvalidLimit::NoStack => write!(f, "the stack limit must be at least 1 frame"),
+        }
+    }
+}
+
+/// Starts from the interpreter's default limits, every limit that is not set keeps its default
+#[derive(Copy, Clone)]
+pub struct ResourceLimitsBuilder {
+    limits: ResourceLimits,
+}
+
+impl Default for ResourceLimitsBuilder {
+    fn default() -> Self {
+        ResourceLimitsBuilder {
+            limits: ResourceLimits::default(),
+        }
+    }
+}
+
+impl ResourceLimitsBuilder {
+    /// The most bytes the program may have allocated at once
+    pub fn memory(mut self, bytes: u64) -> Self {
+        self.limits.memory_size = bytes;
+        self
+    }
+
+    /// The most statements and terminators the program may evaluate, 0 means unlimited
+    pub fn steps(mut self, steps: u64) -> Self {
+        // The interpreter counts down the remaining steps, there is no way to turn it off
+        self.limits.step_limit = if steps == 0 { u64::max_value() } else { steps };
+        self
+    }
+
+    /// The most frames the call stack may have
+    pub fn stack(mut self, frames: usize) -> Self {
+        self.limits.stack_limit = frames;
+        self
+    }
+
+    pub fn build(self) -> Result<ResourceLimits, InvalidLimit> {
+        if self.limits.memory_size == 0 {
+            return Err(InvalidLimit::NoMemory);
+        }
+        if self.limits.stack_limit == 0 {
+            return Err(InvalidLimit::NoStack);
+        }
+        Ok(self.limits)
+    }
+}
+
+/// Gives the compiler's `ResourceLimits` a `builder` constructor
+pub trait ResourceLimitsExt {
+    fn builder() -> ResourceLimitsBuilder;
+}
+
+impl ResourceLimitsExt for ResourceLimits {
+    fn builder() -> ResourceLimitsBuilder {
+        ResourceLimitsBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(ResourceLimits::builder().build().is_ok());
+    }
+
+    #[test]
+    fn no_memory() {
+        assert_eq!(ResourceLimits::builder().memory(0).build().err(), Some(InvalidLimit::NoMemory));
+    }
+
+    #[test]
+    fn no_stack() {
+        assert_eq!(ResourceLimits::builder().stack(0).build().err(), Some(InvalidLimit::NoStack));
+    }
+
+    #[test]
+    fn unlimited_steps() {
+        let limits = ResourceLimits::builder().steps(0).build().unwrap();
+        assert_eq!(limits.step_limit, u64::max_value());
+        let limits = ResourceLimits::builder().steps(1000).build().unwrap();
+        assert_eq!(limits.step_limit, 1000);
+    }
+
+    #[test]
+    fn limits_are_kept() {
+        let limits = ResourceLimits::builder().memory(1 << 20).stack(10).build().unwrap();
+        assert_eq!(limits.memory_size, 1 << 20);
+        assert_eq!(limits.stack_limit, 10);
+    }
+}
@@ -0,0 +1,175 @@
+//! Detects programs that never terminate (`-Zmiri-detect-loops`). Evaluation is deterministic, so
+//! a program that gets back into a state it was in before (the same call stack, locals and memory)
+//! repeats what it did in between forever. Instead of running until the step limit, such a program
+//! is stopped with an error. A snapshot copies all mutable memory, so it is only taken every
+//! `SNAPSHOT_INTERVAL` steps, and only the hashes of the snapshots are kept. A snapshot is kept in
+//! full once its hash was seen twice, to tell an actual repetition from a hash collision.
+
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::hash::{Hash, Hasher};
+
+use super::*;
+
+/// How many steps apart the snapshots are taken
+const SNAPSHOT_INTERVAL: u64 = 1000;
+
+#[derive(Default)]
+pub struct LoopDetector<'tcx> {
+    /// The hash of every snapshot taken so far, with the step of the latest snapshot with that
+    /// hash and, if the hash was seen before, the snapshot itself
+    seen: HashMap<u64, (u64, Option<Snapshot<'tcx>>)>,
+
+    /// Set once the program read a clock. Its state does not include the time, so it may be
+    /// waiting for it to pass in a loop.
+    pub(crate) clock_read: bool,
+
+    /// Set once the program called a foreign function registered by the embedder. Their state is
+    /// not part of the snapshot, so the program may be polling them in a loop.
+    pub(crate) foreign_shim_called: bool,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct Snapshot<'tcx> {
+    frames: Vec<FrameSnapshot<'tcx>>,
+    /// The mutable allocations, immutable ones cannot change
+    allocs: Vec<(u64, AllocSnapshot)>,
+    thread_locals: Vec<(TlsKey, Prim, Option<ty::Instance<'tcx>>)>,
+    env_vars: Vec<(Vec<u8>, Prim)>,
+    random_state: Option<u64>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct FrameSnapshot<'tcx> {
+    instance: ty::Instance<'tcx>,
+    block: mir::BasicBlock,
+    stmt: usize,
+    /// `None` for dead locals
+    locals: Vec<Option<LocalSnapshot>>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum LocalSnapshot {
+    ByRef(Prim, bool),
+    ByVal(Prim),
+    ByValPair(Prim, Prim),
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct AllocSnapshot {
+    /// `None` for undefined bytes
+    bytes: Vec<Option<u8>>,
+    relocations: Vec<(u64, u64)>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Prim {
+    Bytes(u128),
+    Ptr(u64, u64),
+    Undef,
+}
+
+impl From<PrimVal> for Prim {
+    fn from(val: PrimVal) -> Self {
+        match val {
+            PrimVal::Bytes(bytes) => Prim::Bytes(bytes),
+            PrimVal::Ptr(ptr) => Prim::Ptr(ptr.alloc_id.0, ptr.offset),
+            PrimVal::Undef => Prim::Undef,
+        }
+    }
+}
+
+impl From<Pointer> for Prim {
+    fn from(ptr: Pointer) -> Self {
+        ptr.into_inner_primval().into()
+    }
+}
+
+/// Called after every step, fails if the program is in a state it was in before
+pub(crate) fn step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) -> EvalResult<'tcx> {
+    let steps = ecx.machine.steps;
+    if !ecx.machine.config.detect_loops || steps % SNAPSHOT_INTERVAL != 0 {
+        return Ok(());
+    }
+    if ecx.machine.loop_detector.clock_read || ecx.machine.loop_detector.foreign_shim_called {
+        return Ok(());
+    }
+    // Files opened by the program can change without the program's state changing
+    if ecx.machine.file_handler.has_open_files() {
+        return Ok(());
+    }
+    let snapshot = snapshot(ecx)?;
+    let mut hasher = DefaultHasher::new();
+    snapshot.hash(&mut hasher);
+    match ecx.machine.loop_detector.seen.entry(hasher.finish()) {
+        Entry::Vacant(entry) => {
+            entry.insert((steps, None));
+        }
+        Entry::Occupied(mut entry) => {
+            let repeated = match *entry.get() {
+                (previous_steps, Some(ref previous)) if *previous == snapshot => Some(previous_steps),
+                _ => None,
+            };
+            if let Some(previous_steps) = repeated {
+                return Err(MiriError::LimitReached(Limit::InfiniteLoop, format!(
+                    "infinite loop detected: the program is in the same state as {} steps ago, \
+                     and will repeat what it did since then forever",
+                    steps - previous_steps,
+                )).into());
+            }
+            entry.insert((steps, Some(snapshot)));
+        }
+    }
+    Ok(())
+}
+
+fn snapshot<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) -> EvalResult<'tcx, Snapshot<'tcx>> {
+    let frames = ecx.stack()
+        .iter()
+        .map(|frame| FrameSnapshot {
+            instance: frame.instance,
+            block: frame.block,
+            stmt: frame.stmt,
+            locals: frame.mir.local_decls
+                .indices()
+                .map(|local| match frame.get_local(local) {
+                    Ok(Value::ByRef(PtrAndAlign { ptr, aligned })) => Some(LocalSnapshot::ByRef(ptr.into(), aligned)),
+                    Ok(Value::ByVal(val)) => Some(LocalSnapshot::ByVal(val.into())),
+                    Ok(Value::ByValPair(a, b)) => Some(LocalSnapshot::ByValPair(a.into(), b.into())),
+                    Err(_) => None,
+                })
+                .collect(),
+        })
+        .collect();
+    let mut alloc_ids: Vec<u64> = ecx.memory.data.locks.keys().cloned().collect();
+    alloc_ids.sort();
+    let mut allocs = Vec::with_capacity(alloc_ids.len());
+    for alloc_id in alloc_ids {
+        let alloc = ecx.memory.get(AllocId(alloc_id))?;
+        let bytes = alloc.bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| if alloc.undef_mask.get(i as u64) { Some(byte) } else { None })
+            .collect();
+        let relocations = alloc.relocations
+            .iter()
+            .map(|(&offset, target)| (offset, target.0))
+            .collect();
+        allocs.push((alloc_id, AllocSnapshot { bytes, relocations }));
+    }
+    let thread_locals = ecx.memory.data.thread_local
+        .iter()
+        .map(|(&key, entry)| (key, entry.data.into(), entry.dtor))
+        .collect();
+    let mut env_vars: Vec<(Vec<u8>, Prim)> = ecx.machine.env_vars
+        .iter()
+        .map(|(name, &ptr)| (name.clone(), PrimVal::Ptr(ptr).into()))
+        .collect();
+    env_vars.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(Snapshot {
+        frames,
+        allocs,
+        thread_locals,
+        env_vars,
+        random_state: ecx.machine.random_state,
+    })
+}
@@ -0,0 +1,125 @@
+//! Explains alignment errors: walks the projections of the place that was accessed to find the
+//! field of a packed struct or the pointer cast that made it less aligned than its type requires.
+
+use rustc::mir;
+use rustc::ty;
+
+use super::*;
+use rustc_compat;
+
+/// Called when the evaluation ends with an error, after it was reported
+pub(crate) fn explain<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, e: &EvalError<'tcx>) {
+    let (required, has) = match e.kind {
+        EvalErrorKind::AlignmentCheckFailed { required, has } => (required, has),
+        _ => return,
+    };
+    let frame = match ecx.stack().last() {
+        Some(frame) => frame,
+        None => return,
+    };
+    let (dest, rvalue) = match frame.mir[frame.block].statements.get(frame.stmt) {
+        Some(&mir::Statement { kind: mir::StatementKind::Assign(ref dest, ref rvalue), .. }) => (dest, rvalue),
+        _ => return,
+    };
+    let tcx = ecx.tcx;
+    let span = rustc_compat::frame_span(frame);
+    let (source, borrowed) = match *rvalue {
+        mir::Rvalue::Ref(_, _, ref place) => (Some(place), true),
+        mir::Rvalue::Use(mir::Operand::Copy(ref place)) |
+        mir::Rvalue::Use(mir::Operand::Move(ref place)) => (Some(place), false),
+        _ => (None, false),
+    };
+    for mut place in ::std::iter::once(dest).chain(source) {
+        while let mir::Place::Projection(ref proj) = *place {
+            match proj.elem {
+                mir::ProjectionElem::Field(..) => {
+                    if let Some(field) = packed_field(ecx, frame.mir, place) {
+                        tcx.sess.span_note_without_error(span, &format!(
+                            "{} is not aligned to the {} bytes its type requires",
+                            field,
+                            required,
+                        ));
+                        if borrowed {
+                            tcx.sess.span_note_without_error(span, SUGGEST_COPY);
+                        }
+                        return;
+                    }
+                }
+                mir::ProjectionElem::Deref => {
+                    if let mir::Place::Local(local) = proj.base {
+                        explain_pointer(ecx, frame.mir, local, has);
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            place = &proj.base;
+        }
+    }
+}
+
+const SUGGEST_COPY: &str = "instead of taking a reference to the field, copy it with \
+                            `ptr::read_unaligned` or assign it to a local";
+
+/// Points at where the misaligned pointer in `local` came from
+fn explain_pointer<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    mir: &mir::Mir<'tcx>,
+    local: mir::Local,
+    has: u64,
+) {
+    // Follow plain copies of the pointer, e.g. into user variables
+    let (_, assignments) = rustc_compat::trace_copies(mir, local);
+    if assignments.len() != 1 {
+        return;
+    }
+    let (rvalue, span) = match assignments[0] {
+        rustc_compat::Assignment::Rvalue(rvalue, span) => (rvalue, span),
+        rustc_compat::Assignment::Call(..) => return,
+    };
+    match *rvalue {
+        mir::Rvalue::Cast(..) => {
+            ecx.tcx.sess.span_note_without_error(span, &format!(
+                "the pointer was created by this cast, but is only aligned to {} bytes; \
+                 access it with `ptr::read_unaligned` and `ptr::write_unaligned`",
+                has,
+            ));
+        }
+        mir::Rvalue::Ref(_, _, ref place) => {
+            if let Some(field) = packed_field(ecx, mir, place) {
+                ecx.tcx.sess.span_note_without_error(span, &format!(
+                    "the reference was created here, to {}, which is only aligned to {} bytes",
+                    field,
+                    has,
+                ));
+                ecx.tcx.sess.span_note_without_error(span, SUGGEST_COPY);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Describes the field `place` projects to, if it is a field of a packed struct
+fn packed_field<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    mir: &mir::Mir<'tcx>,
+    place: &mir::Place<'tcx>,
+) -> Option<String> {
+    let proj = match *place {
+        mir::Place::Projection(ref proj) => proj,
+        _ => return None,
+    };
+    let field = match proj.elem {
+        mir::ProjectionElem::Field(field, _) => field,
+        _ => return None,
+    };
+    let base_ty = proj.base.ty(mir, ecx.tcx).to_ty(ecx.tcx);
+    match base_ty.sty {
+        ty::TyAdt(adt, _) if adt.repr.packed() && !adt.is_enum() => Some(format!(
+            "field `{}` of the packed struct `{}`",
+            adt.variants[0].fields[field.index()].name,
+            ecx.tcx.item_path_str(adt.did),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,68 @@
+//! Callbacks for tools watching the evaluation statement by statement, e.g. tracers and coverage
+//! tools, without driving `step` themselves.
+
+use std::fmt;
+use std::rc::Rc;
+
+use rustc::mir;
+
+use super::*;
+
+/// Where the evaluation is about to continue
+#[derive(Copy, Clone, Debug)]
+pub struct StepLocation {
+    /// The index of the innermost frame in `ecx.stack()`
+    pub frame: usize,
+    pub block: mir::BasicBlock,
+    /// The index of the statement in the block, the block's terminator if it is past the last
+    /// statement
+    pub stmt: usize,
+    pub is_terminator: bool,
+}
+
+/// Called before every statement and terminator, with the evaluation context and the location
+/// of the statement or terminator
+pub type StepObserver = for<'a, 'tcx> Fn(&EvalContext<'a, 'tcx, Evaluator<'tcx>>, StepLocation);
+
+/// The observers registered by the embedder
+#[derive(Clone, Default)]
+pub struct StepObservers {
+    observers: Vec<Rc<StepObserver>>,
+}
+
+impl StepObservers {
+    /// Calls `observer` before every step of the evaluation, after the ones added before
+    pub fn add<F>(&mut self, observer: F)
+    where
+        F: for<'a, 'tcx> Fn(&EvalContext<'a, 'tcx, Evaluator<'tcx>>, StepLocation) + 'static,
+    {
+        self.observers.push(Rc::new(observer));
+    }
+}
+
+impl fmt::Debug for StepObservers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} step observers", self.observers.len())
+    }
+}
+
+/// Called before every step, notifies the observers
+pub(crate) fn before_step<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    let observers = &ecx.machine.config.step_observers.observers;
+    if observers.is_empty() {
+        return;
+    }
+    let frame = match ecx.stack().last() {
+        Some(frame) => frame,
+        None => return,
+    };
+    let location = StepLocation {
+        frame: ecx.stack().len() - 1,
+        block: frame.block,
+        stmt: frame.stmt,
+        is_terminator: frame.stmt == frame.mir[frame.block].statements.len(),
+    };
+    for observer in observers {
+        observer(ecx, location);
+    }
+}
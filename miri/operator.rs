@@ -22,6 +22,17 @@ pub trait EvalContextExt<'tcx> {
         right: i128,
         signed: bool,
     ) -> EvalResult<'tcx, (PrimVal, bool)>;
+
+    /// Evaluates `bin_op` on the addresses of the pointers among its operands, for
+    /// `-Zmiri-virtual-addresses`
+    fn address_op(
+        &self,
+        bin_op: mir::BinOp,
+        left: PrimVal,
+        left_ty: ty::Ty<'tcx>,
+        right: PrimVal,
+        right_ty: ty::Ty<'tcx>,
+    ) -> EvalResult<'tcx, (PrimVal, bool)>;
 }
 
 impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'tcx>> {
@@ -59,6 +70,10 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     (PrimVal::Ptr(left), PrimVal::Ptr(right)) => left == right,
                     (PrimVal::Undef, _) |
                     (_, PrimVal::Undef) => return err!(ReadUndefBytes),
+                    (PrimVal::Ptr(ptr), PrimVal::Bytes(bits)) |
+                    (PrimVal::Bytes(bits), PrimVal::Ptr(ptr)) if self.machine.config.virtual_addresses => {
+                        addresses::address(self, ptr) as u128 == bits
+                    }
                     _ => false,
                 };
                 Ok(Some((PrimVal::from_bool(result), false)))
@@ -69,6 +84,10 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     (PrimVal::Ptr(left), PrimVal::Ptr(right)) => left != right,
                     (PrimVal::Undef, _) |
                     (_, PrimVal::Undef) => return err!(ReadUndefBytes),
+                    (PrimVal::Ptr(ptr), PrimVal::Bytes(bits)) |
+                    (PrimVal::Bytes(bits), PrimVal::Ptr(ptr)) if self.machine.config.virtual_addresses => {
+                        addresses::address(self, ptr) as u128 != bits
+                    }
                     _ => true,
                 };
                 Ok(Some((PrimVal::from_bool(result), false)))
@@ -98,8 +117,12 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                         _ => bug!("We already established it has to be one of these operators."),
                     };
                     Ok(Some((PrimVal::from_bool(res), false)))
+                } else if bin_op != Sub || self.machine.config.virtual_addresses {
+                    // Pointers into different allocations can be compared, they are ordered by
+                    // the addresses of their allocations
+                    self.address_op(bin_op, PrimVal::Ptr(left), left_ty, PrimVal::Ptr(right), right_ty).map(Some)
                 } else {
-                    // Both are pointers, but from different allocations.
+                    // The distance between two allocations is meaningless
                     err!(InvalidPointerMath)
                 }
             }
@@ -126,6 +149,11 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     left_kind == isize,
                 ).map(Some)
             }
+            // Everything else needs the addresses of the pointers
+            _ if self.machine.config.virtual_addresses && (left.is_ptr() || right.is_ptr()) &&
+                (left_kind == usize || left_kind == isize || left_kind == Ptr) => {
+                self.address_op(bin_op, left, left_ty, right, right_ty).map(Some)
+            }
             _ => Ok(None),
         }
     }
@@ -161,6 +189,12 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                 } else if right & base_mask == 0 {
                     // Case 2: The base address bits are all taken away, i.e., right is all-0 there
                     (PrimVal::from_u128((left.offset & right) as u128), false)
+                } else if self.machine.config.virtual_addresses {
+                    let address = addresses::address(self, left) & right;
+                    match addresses::pointer_to(self, left, address) {
+                        Some(ptr) => (PrimVal::Ptr(ptr), false),
+                        None => (PrimVal::from_u128(address as u128), false),
+                    }
                 } else {
                     return err!(ReadPointerAsBytes);
                 }
@@ -172,4 +206,52 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
             }
         })
     }
+
+    fn address_op(
+        &self,
+        bin_op: mir::BinOp,
+        left: PrimVal,
+        left_ty: ty::Ty<'tcx>,
+        right: PrimVal,
+        right_ty: ty::Ty<'tcx>,
+    ) -> EvalResult<'tcx, (PrimVal, bool)> {
+        use rustc::mir::BinOp::*;
+
+        let to_address = |val: PrimVal| match val {
+            PrimVal::Ptr(ptr) => PrimVal::Bytes(addresses::address(self, ptr) as u128),
+            val => val,
+        };
+        // The addresses are plain integers
+        let int_ty = |ty: ty::Ty<'tcx>| match ty.sty {
+            ty::TyRawPtr(_) | ty::TyRef(..) | ty::TyFnPtr(_) => self.tcx.types.usize,
+            _ => ty,
+        };
+        let (result, overflowed) = self.binary_op(
+            bin_op,
+            to_address(left),
+            int_ty(left_ty),
+            to_address(right),
+            int_ty(right_ty),
+        )?;
+        // Arithmetic with an integer that keeps the address in the bounds of the pointer's
+        // allocation, e.g. tagging its low bits, still yields a pointer into that allocation
+        let ptr = match (left, right) {
+            (PrimVal::Ptr(ptr), PrimVal::Bytes(_)) |
+            (PrimVal::Bytes(_), PrimVal::Ptr(ptr)) => ptr,
+            _ => return Ok((result, overflowed)),
+        };
+        match (bin_op, result) {
+            (Add, PrimVal::Bytes(address)) |
+            (Sub, PrimVal::Bytes(address)) |
+            (BitAnd, PrimVal::Bytes(address)) |
+            (BitOr, PrimVal::Bytes(address)) |
+            (BitXor, PrimVal::Bytes(address)) => {
+                if let Some(ptr) = addresses::pointer_to(self, ptr, address as u64) {
+                    return Ok((PrimVal::Ptr(ptr), overflowed));
+                }
+            }
+            _ => {}
+        }
+        Ok((result, overflowed))
+    }
 }
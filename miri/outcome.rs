@@ -0,0 +1,181 @@
+//! How the evaluation of a program ended, for drivers and embedders that want to react to it
+//! without parsing the diagnostics.
+
+use rustc::ty::layout::LayoutOf;
+
+use super::*;
+
+pub enum EvalOutcome<'tcx> {
+    /// The program returned from `main` (with exit code 0) or called `exit`
+    Completed { exit_code: i32 },
+    /// The program panicked. Panics are not unwound, they end the evaluation.
+    Panicked {
+        /// The message, if it is a string literal
+        message: Option<String>,
+        /// File, line and column of the panic
+        location: Option<(String, u32, u32)>,
+    },
+    /// The program caused undefined behavior or did something miri does not support
+    Ub(EvalError<'tcx>),
+    /// The program exceeded one of the limits configured for the evaluation
+    LimitReached(Limit),
+    /// The user stopped the evaluation in the debugger (`--debug`)
+    StoppedByDebugger,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// `#![miri(step_limit)]`
+    Steps,
+    /// `#![miri(stack_limit)]`
+    StackFrames,
+    /// `#![miri(memory_size)]`, reached by an allocation of miri itself, e.g. for a stack frame
+    Memory,
+    /// `#![miri(memory_size)]`, reached by the program's global allocator, which then aborted the
+    /// program like a native one runs out of memory
+    ProgramMemory,
+    /// `-Zmiri-max-calls-per-fn`
+    CallsPerFn,
+    /// `-Zmiri-detect-loops`: the program got back into an earlier state and would never have
+    /// terminated
+    InfiniteLoop,
+}
+
+/// What we know about a panic, recorded when the program starts panicking
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PanicInfo {
+    message: Option<String>,
+    location: Option<(String, u32, u32)>,
+}
+
+impl<'tcx> EvalOutcome<'tcx> {
+    pub(crate) fn from_error(e: EvalError<'tcx>, panic: Option<PanicInfo>) -> Self {
+        let limit = match e.kind {
+            EvalErrorKind::Panic => {
+                let panic = panic.unwrap_or_default();
+                return EvalOutcome::Panicked {
+                    message: panic.message,
+                    location: panic.location,
+                };
+            }
+            EvalErrorKind::ExecutionTimeLimitReached => Some(Limit::Steps),
+            EvalErrorKind::StackFrameLimitReached => Some(Limit::StackFrames),
+            EvalErrorKind::OutOfMemory { .. } => Some(Limit::Memory),
+            _ => match MiriError::of(&e) {
+                Some(&MiriError::LimitReached(limit, _)) => Some(limit),
+                Some(&MiriError::OutOfMemoryAbort) => Some(Limit::ProgramMemory),
+                Some(&MiriError::StoppedByDebugger) => return EvalOutcome::StoppedByDebugger,
+                None => None,
+            },
+        };
+        match limit {
+            Some(limit) => EvalOutcome::LimitReached(limit),
+            None => EvalOutcome::Ub(e),
+        }
+    }
+}
+
+/// Extracts the message and location from the arguments of the panic entry point `path`
+pub(crate) fn panic_info<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    path: &str,
+    args: &[ValTy<'tcx>],
+) -> PanicInfo {
+    let info: EvalResult<'tcx, PanicInfo> = do catch {
+        match path {
+            // fn rust_panic_with_hook(msg: Box<Any + Send>, file_line_col: &(&'static str, u32, u32))
+            "std::panicking::rust_panic_with_hook" => Ok(PanicInfo {
+                message: boxed_str_message(ecx, args[0]).unwrap_or(None),
+                location: Some(read_file_line_col(ecx, args[1])?),
+            }),
+            // fn begin_panic_fmt(msg: &fmt::Arguments, file_line_col: &(&'static str, u32, u32))
+            "std::rt::begin_panic_fmt" => Ok(PanicInfo {
+                message: None,
+                location: Some(read_file_line_col(ecx, args[1])?),
+            }),
+            // fn panic_impl(fmt: fmt::Arguments, file: &'static str, line: u32, col: u32)
+            "core::panicking::panic_fmt::::panic_impl" => {
+                let file = read_str(ecx, args[1].value, args[1].ty)?;
+                let line = ecx.value_to_primval(args[2])?.to_u64()? as u32;
+                let col = ecx.value_to_primval(args[3])?.to_u64()? as u32;
+                Ok(PanicInfo {
+                    message: None,
+                    location: Some((file, line, col)),
+                })
+            }
+            _ => Ok(PanicInfo::default()),
+        }
+    };
+    // The panic is reported either way, the details are just a bonus
+    info.unwrap_or_default()
+}
+
+/// Reads a `&(&'static str, u32, u32)`
+fn read_file_line_col<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    arg: ValTy<'tcx>,
+) -> EvalResult<'tcx, (String, u32, u32)> {
+    let tuple_ty = match arg.ty.sty {
+        ty::TyRef(_, ref pointee) => pointee.ty,
+        _ => return err!(Unimplemented(format!("expected a reference, got `{}`", arg.ty))),
+    };
+    let layout = ecx.layout_of(tuple_ty)?;
+    let ptr = ecx.into_ptr(arg.value)?.to_ptr()?;
+    let mut fields = Vec::new();
+    for i in 0..3 {
+        let field_ptr = ptr.offset(layout.fields.offset(i).bytes(), ecx)?;
+        fields.push((field_ptr, layout.field(ecx, i)?.ty));
+    }
+    let file = read_str(ecx, Value::by_ref(fields[0].0.into()), fields[0].1)?;
+    let line = ecx.memory.read_primval(fields[1].0, 4, false)?.to_u64()? as u32;
+    let col = ecx.memory.read_primval(fields[2].0, 4, false)?.to_u64()? as u32;
+    Ok((file, line, col))
+}
+
+/// The message of a panic payload, if it is a `&'static str` (i.e. `panic!("literal")`)
+fn boxed_str_message<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    payload: ValTy<'tcx>,
+) -> EvalResult<'tcx, Option<String>> {
+    let (data, vtable) = read_pair(ecx, payload.value, payload.ty)?;
+    let (data, vtable) = (Pointer::from(data), vtable.to_ptr()?);
+    // The vtable of `Any` is drop glue, size, align and `get_type_id`, which is generic over the
+    // type behind the trait object
+    let ptr_size = ecx.memory.pointer_size();
+    let get_type_id = vtable.offset(3 * ptr_size, ecx)?;
+    let get_type_id = ecx.memory.read_ptr_sized_unsigned(get_type_id)?.to_ptr()?;
+    let payload_ty = ecx.memory.get_fn(get_type_id)?.substs.type_at(0);
+    match payload_ty.sty {
+        ty::TyRef(_, ref pointee) if pointee.ty.sty == ty::TyStr => {
+            Ok(Some(read_str(ecx, Value::by_ref(data), payload_ty)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Reads the `&str` `value` of type `ty`
+fn read_str<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    value: Value,
+    ty: ty::Ty<'tcx>,
+) -> EvalResult<'tcx, String> {
+    let (ptr, len) = read_pair(ecx, value, ty)?;
+    let bytes = ecx.memory.read_bytes(ptr.into(), len.to_u64()?)?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Reads the two halves of the fat pointer `value` of type `ty`
+fn read_pair<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    value: Value,
+    ty: ty::Ty<'tcx>,
+) -> EvalResult<'tcx, (PrimVal, PrimVal)> {
+    let value = match value {
+        Value::ByRef(PtrAndAlign { ptr, .. }) => ecx.read_value(ptr, ty)?,
+        value => value,
+    };
+    match value {
+        Value::ByValPair(a, b) => Ok((a, b)),
+        _ => err!(Unimplemented(format!("expected a fat pointer, got {:?}", value))),
+    }
+}
@@ -0,0 +1,60 @@
+//! Finds integer arithmetic whose overflow behavior depends on the build profile. With overflow
+//! checks (debug builds), overflowing `+`, `-`, `*`, `<<` and `>>` panic, without them (release
+//! builds) they wrap. Code vetted under miri in one profile can thus behave differently when
+//! shipped in the other one.
+
+use rustc::mir;
+
+use super::*;
+use rustc_compat;
+
+/// Called before every step, checks whether the next statement is arithmetic that overflows
+pub(crate) fn check_next_statement<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    if !ecx.machine.config.warn_overflow_profile {
+        return;
+    }
+    let (op, left, right, checked, span) = {
+        let frame = match ecx.stack().last() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let stmt = match frame.mir[frame.block].statements.get(frame.stmt) {
+            Some(stmt) => stmt,
+            None => return,
+        };
+        match stmt.kind {
+            mir::StatementKind::Assign(_, mir::Rvalue::BinaryOp(op, ref left, ref right)) =>
+                (op, left.clone(), right.clone(), false, rustc_compat::frame_span(frame)),
+            mir::StatementKind::Assign(_, mir::Rvalue::CheckedBinaryOp(op, ref left, ref right)) =>
+                (op, left.clone(), right.clone(), true, rustc_compat::frame_span(frame)),
+            _ => return,
+        }
+    };
+    match op {
+        mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul | mir::BinOp::Shl | mir::BinOp::Shr => {}
+        _ => return,
+    }
+    if ecx.machine.overflow_sites.contains(&span) {
+        return;
+    }
+    // Errors are reported when the statement is actually evaluated
+    let overflowed: EvalResult<'tcx, bool> = do catch {
+        let left = ecx.eval_operand(&left)?;
+        let right = ecx.eval_operand(&right)?;
+        if !left.ty.is_integral() {
+            return;
+        }
+        let left_val = ecx.value_to_primval(left)?;
+        let right_val = ecx.value_to_primval(right)?;
+        Ok(ecx.binary_op(op, left_val, left.ty, right_val, right.ty)?.1)
+    };
+    if let Ok(true) = overflowed {
+        ecx.machine.overflow_sites.insert(span);
+        let msg = if checked {
+            "this arithmetic overflows and panics, but it silently wraps around in builds without overflow checks (e.g. release builds)"
+        } else {
+            "this arithmetic overflows and wraps around, but it panics in builds with overflow checks (e.g. debug builds)"
+        };
+        ecx.tcx.sess.span_warn(span, msg);
+    }
+}
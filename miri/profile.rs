@@ -0,0 +1,88 @@
+//! A sampling profiler for the interpreted program. Every few steps, the current call stack is
+//! recorded. The result is written in the "folded stacks" format (one `outer;inner count` line
+//! per distinct stack) that `flamegraph.pl` and similar tools consume.
+//!
+//! Next to it, `FILE.functions` lists the steps spent in every function: in the function itself
+//! (self) and in it or anything it called (total), most expensive first. A sample stands for the
+//! `interval` steps since the previous one, with an interval of 1 the counts are exact.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Write};
+
+use rustc_mir::interpret::EvalContext;
+
+use super::Evaluator;
+use rustc_compat;
+
+pub struct Profiler {
+    /// Where the folded stacks are written to
+    file: String,
+    /// Take a sample every `interval` steps
+    interval: u64,
+    /// Steps since the last sample
+    steps: u64,
+    /// Number of samples for every distinct stack
+    samples: HashMap<String, u64>,
+    /// Steps spent in every function itself and in total (self, total)
+    functions: HashMap<String, (u64, u64)>,
+}
+
+impl Profiler {
+    pub fn new(file: String, interval: u64) -> Self {
+        Profiler {
+            file,
+            interval: interval.max(1),
+            steps: 0,
+            samples: HashMap::new(),
+            functions: HashMap::new(),
+        }
+    }
+
+    pub fn write(&self) -> io::Result<()> {
+        let mut samples: Vec<_> = self.samples.iter().collect();
+        samples.sort();
+        let mut file = File::create(&self.file)?;
+        for (stack, count) in samples {
+            writeln!(file, "{} {}", stack, count)?;
+        }
+
+        let mut functions: Vec<_> = self.functions.iter().collect();
+        functions.sort_by(|a, b| (b.1).0.cmp(&(a.1).0).then(a.0.cmp(b.0)));
+        let mut file = File::create(format!("{}.functions", self.file))?;
+        writeln!(file, "{:>12} {:>12}  function", "self", "total")?;
+        for (function, &(self_steps, total_steps)) in functions {
+            writeln!(file, "{:>12} {:>12}  {}", self_steps, total_steps, function)?;
+        }
+        Ok(())
+    }
+}
+
+/// Called after every step of the program, samples the stack if it is time to
+pub(crate) fn step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    match ecx.machine.profiler {
+        Some(ref mut profiler) => {
+            profiler.steps += 1;
+            if profiler.steps < profiler.interval {
+                return;
+            }
+            profiler.steps = 0;
+        }
+        None => return,
+    }
+    let paths = ecx.stack()
+        .iter()
+        .map(|frame| rustc_compat::frame_fn_path(ecx.tcx, frame))
+        .collect::<Vec<_>>();
+    let profiler = ecx.machine.profiler.as_mut().unwrap();
+    let interval = profiler.interval;
+    if let Some(innermost) = paths.last() {
+        profiler.functions.entry(innermost.clone()).or_insert((0, 0)).0 += interval;
+    }
+    // Recursive functions are on the stack more than once, but only spend the steps once
+    let distinct: HashSet<&String> = paths.iter().collect();
+    for path in distinct {
+        profiler.functions.entry(path.clone()).or_insert((0, 0)).1 += interval;
+    }
+    *profiler.samples.entry(paths.join(";")).or_insert(0) += 1;
+}
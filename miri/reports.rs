@@ -0,0 +1,55 @@
+//! The reports that can cover several evaluations, e.g. all tests of a crate in `--test` mode:
+//! the profile, the trace and the coverage. `eval_main_with_reports` adds to them, they are
+//! written once all evaluations are done.
+
+use super::*;
+
+pub struct Reports<'tcx> {
+    pub(crate) profiler: Option<profile::Profiler>,
+    pub(crate) tracer: Option<trace::Tracer<'tcx>>,
+    pub(crate) coverage: Option<coverage::Coverage>,
+    /// Where the coverage report goes
+    coverage_file: Option<String>,
+}
+
+impl<'tcx> Reports<'tcx> {
+    /// The reports requested by `config`, with nothing recorded yet. The trace file is created
+    /// right away, errors are reported to the session.
+    pub fn new<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, config: &MiriConfig) -> Self {
+        let tracer = match config.trace {
+            Some(ref file) => match trace::Tracer::create(file) {
+                Ok(tracer) => Some(tracer),
+                Err(e) => {
+                    tcx.sess.err(&format!("could not create the trace file `{}`: {}", file, e));
+                    None
+                }
+            },
+            None => None,
+        };
+        Reports {
+            profiler: config.profile.clone().map(|(file, interval)| profile::Profiler::new(file, interval)),
+            tracer,
+            coverage: config.coverage.as_ref().map(|_| Default::default()),
+            coverage_file: config.coverage.clone(),
+        }
+    }
+
+    /// Writes the reports, errors are reported to the session
+    pub fn write<'a>(&mut self, tcx: TyCtxt<'a, 'tcx, 'tcx>) {
+        if let Some(ref profiler) = self.profiler {
+            if let Err(e) = profiler.write() {
+                tcx.sess.err(&format!("could not write the profile: {}", e));
+            }
+        }
+        if let Some(ref mut tracer) = self.tracer {
+            if let Err(e) = tracer.finish() {
+                tcx.sess.err(&format!("could not write the trace: {}", e));
+            }
+        }
+        if let (Some(coverage), Some(file)) = (self.coverage.as_ref(), self.coverage_file.as_ref()) {
+            if let Err(e) = coverage.write(tcx, file) {
+                tcx.sess.err(&format!("could not write the coverage report: {}", e));
+            }
+        }
+    }
+}
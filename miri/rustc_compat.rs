@@ -0,0 +1,106 @@
+//! Helpers for inspecting the stack and rendering spans for the user: the span a frame is at, the
+//! path of its function, the names of locals and where they are assigned, and the file and lines
+//! of a span. The tools built on
+//! top of the interpreter (debugger, tracer, coverage, profiler, ...) go through these instead of
+//! the compiler's APIs, so a nightly changing how MIR locations, locals or the codemap are reached
+//! only needs a fix here.
+//!
+//! This does not cover the rest of the compiler's internals. Layouts, types, `EvalContext`, the
+//! interpreter's values and MIR statements are used directly throughout the crate and need
+//! adapting wherever they are used when a nightly changes them.
+
+use rustc::mir;
+use rustc::ty::TyCtxt;
+use syntax::ast::Name;
+use syntax::codemap::Span;
+
+use rustc_mir::interpret::Frame;
+
+/// The span of the statement (or terminator) `frame` is going to evaluate next
+pub(crate) fn frame_span(frame: &Frame) -> Span {
+    let location = mir::Location {
+        block: frame.block,
+        statement_index: frame.stmt,
+    };
+    frame.mir.source_info(location).span
+}
+
+/// The path of the function `frame` is executing, e.g. `std::vec::Vec<T>::push`
+pub(crate) fn frame_fn_path<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, frame: &Frame) -> String {
+    tcx.item_path_str(frame.instance.def_id())
+}
+
+/// The name the user gave to `local`, if any
+pub(crate) fn local_name(mir: &mir::Mir, local: mir::Local) -> Option<Name> {
+    mir.local_decls[local].name
+}
+
+/// The file of `span`, and the line and column (both starting at 1) it starts and ends at
+pub(crate) fn span_lines<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, span: Span) -> (String, usize, usize, usize, usize) {
+    let codemap = tcx.sess.codemap();
+    let lo = codemap.lookup_char_pos(span.lo());
+    let hi = codemap.lookup_char_pos(span.hi());
+    (lo.file.name.to_string(), lo.line, lo.col.0 + 1, hi.line, hi.col.0 + 1)
+}
+
+/// Renders `span` as `file:line:col: line:col`
+pub(crate) fn span_to_string<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, span: Span) -> String {
+    tcx.sess.codemap().span_to_string(span)
+}
+
+/// How often `trace_copies` follows a copy from one local to another
+const MAX_COPIES: usize = 8;
+
+/// An assignment to a local in the MIR of a function
+pub(crate) enum Assignment<'m, 'tcx: 'm> {
+    /// A statement `local = rvalue`
+    Rvalue(&'m mir::Rvalue<'tcx>, Span),
+    /// A call `local = func(...)`
+    Call(&'m mir::Operand<'tcx>, Span),
+}
+
+/// Every assignment to the whole of `local`, in the order of the basic blocks. Arguments are
+/// assigned by the caller, in addition to these.
+pub(crate) fn assignments<'m, 'tcx>(mir: &'m mir::Mir<'tcx>, local: mir::Local) -> Vec<Assignment<'m, 'tcx>> {
+    let mut assignments = Vec::new();
+    for block in mir.basic_blocks() {
+        for stmt in &block.statements {
+            if let mir::StatementKind::Assign(mir::Place::Local(dest), ref rvalue) = stmt.kind {
+                if dest == local {
+                    assignments.push(Assignment::Rvalue(rvalue, stmt.source_info.span));
+                }
+            }
+        }
+        if let mir::TerminatorKind::Call { ref func, destination: Some((mir::Place::Local(dest), _)), .. } =
+            block.terminator().kind
+        {
+            if dest == local {
+                assignments.push(Assignment::Call(func, block.terminator().source_info.span));
+            }
+        }
+    }
+    assignments
+}
+
+/// Follows plain copies (`local = other`) back to the local the value of `local` came from, as
+/// long as every local on the way is assigned exactly once. Returns that local and its
+/// assignments.
+pub(crate) fn trace_copies<'m, 'tcx>(
+    mir: &'m mir::Mir<'tcx>,
+    mut local: mir::Local,
+) -> (mir::Local, Vec<Assignment<'m, 'tcx>>) {
+    let mut assignments = self::assignments(mir, local);
+    for _ in 0..MAX_COPIES {
+        if assignments.len() != 1 {
+            break;
+        }
+        let source = match assignments[0] {
+            Assignment::Rvalue(&mir::Rvalue::Use(mir::Operand::Copy(mir::Place::Local(source))), _) |
+            Assignment::Rvalue(&mir::Rvalue::Use(mir::Operand::Move(mir::Place::Local(source))), _) => source,
+            _ => break,
+        };
+        local = source;
+        assignments = self::assignments(mir, local);
+    }
+    (local, assignments)
+}
@@ -0,0 +1,41 @@
+//! Foreign functions emulated by whoever embeds miri. Programs calling into their own native
+//! libraries can be run by registering a host function for every foreign function they call.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::*;
+
+/// Stands in for a foreign function. It gets the arguments of the call and returns the function's
+/// return value, or `None` if the function returns `()`.
+pub type ForeignShim = for<'a, 'tcx> Fn(&mut EvalContext<'a, 'tcx, Evaluator<'tcx>>, &[ValTy<'tcx>])
+    -> EvalResult<'tcx, Option<PrimVal>>;
+
+/// The foreign functions registered by the embedder, by their link name
+#[derive(Clone, Default)]
+pub struct ForeignShims {
+    shims: HashMap<String, Rc<ForeignShim>>,
+}
+
+impl ForeignShims {
+    /// Lets calls of the foreign function `name` (e.g. `"my_lib_init"`) run `shim`. Registered
+    /// functions take precedence over miri's own emulation of the function.
+    pub fn register<F>(&mut self, name: &str, shim: F)
+    where
+        F: for<'a, 'tcx> Fn(&mut EvalContext<'a, 'tcx, Evaluator<'tcx>>, &[ValTy<'tcx>])
+            -> EvalResult<'tcx, Option<PrimVal>> + 'static,
+    {
+        self.shims.insert(name.to_owned(), Rc::new(shim));
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Rc<ForeignShim>> {
+        self.shims.get(name).cloned()
+    }
+}
+
+impl fmt::Debug for ForeignShims {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.shims.keys()).finish()
+    }
+}
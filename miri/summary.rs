@@ -0,0 +1,56 @@
+//! Statistics about a whole run of the program, printed when it completes successfully. They
+//! show how much of the program was actually interpreted and which parts of the platform it
+//! needed.
+
+use std::collections::BTreeMap;
+
+use alloc_stats::AllocStats;
+
+#[derive(Default)]
+pub struct Summary {
+    /// Steps executed
+    pub(crate) steps: u64,
+    /// Stack frames pushed
+    pub(crate) frames: u64,
+    /// How often every intrinsic was called
+    intrinsics: BTreeMap<String, u64>,
+    /// How often every function without MIR was emulated
+    foreign_fns: BTreeMap<String, u64>,
+}
+
+impl Summary {
+    pub(crate) fn intrinsic(&mut self, name: &str) {
+        *self.intrinsics.entry(name.to_owned()).or_insert(0) += 1;
+    }
+
+    pub(crate) fn foreign_fn(&mut self, name: &str) {
+        *self.foreign_fns.entry(name.to_owned()).or_insert(0) += 1;
+    }
+
+    /// The allocations and the peak heap usage are taken from `stats`, `leaks` from the leak check
+    pub fn print(&self, stats: &AllocStats, leaks: usize) {
+        eprintln!(
+            "miri summary: {} steps, {} stack frames, peak heap usage {} bytes, \
+             {} allocations ({} freed, {} leaked)",
+            self.steps,
+            self.frames,
+            stats.peak_heap_bytes,
+            stats.allocations,
+            stats.deallocations,
+            leaks,
+        );
+        eprintln!("intrinsics: {}", format_counts(&self.intrinsics));
+        eprintln!("emulated foreign functions: {}", format_counts(&self.foreign_fns));
+    }
+}
+
+fn format_counts(counts: &BTreeMap<String, u64>) -> String {
+    if counts.is_empty() {
+        return "none".to_owned();
+    }
+    counts
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,31 @@
+//! Where the drivers find a libstd with full MIR. `cargo miri setup` builds one in the cache
+//! directory, the `miri` and `cargo-miri` binaries and the test suite all look for it here.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Where `cargo miri setup` puts the libstd with full MIR: `$MIRI_CACHE_DIR`, by default
+/// `$XDG_CACHE_HOME/miri` or `~/.cache/miri`
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("MIRI_CACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+        return Some(PathBuf::from(dir).join("miri"));
+    }
+    env::home_dir().map(|home| home.join(".cache").join("miri"))
+}
+
+/// The libstd with full MIR to use: `$MIRI_SYSROOT`, or the one built by `cargo miri setup` if
+/// there is one
+pub fn fullmir_sysroot() -> Option<PathBuf> {
+    if let Some(sysroot) = env::var_os("MIRI_SYSROOT") {
+        return Some(PathBuf::from(sysroot));
+    }
+    let sysroot = cache_dir()?.join("HOST");
+    if sysroot.join("lib").exists() {
+        Some(sysroot)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,92 @@
+//! Timings of miri itself (`-Zmiri-self-profile`), to tell where the interpreter spends its time
+//! when a program is slow to run. The time of every hook the interpreter calls into miri with is
+//! measured; layout queries and the evaluation of constants happen inside the compiler's
+//! interpreter and are only part of the time of the steps.
+
+use std::time::{Duration, Instant};
+
+use super::*;
+
+/// What the interpreter is doing. The categories nest: memory checks during a call to a foreign
+/// function are counted as both, and everything is part of some step.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Category {
+    /// Evaluating statements and terminators
+    Steps,
+    /// Setting up calls to functions
+    Calls,
+    /// Loading the MIR of called functions
+    MirLoading,
+    /// Emulating functions without MIR
+    ForeignFns,
+    Intrinsics,
+    /// Binary operations on pointers
+    PointerOps,
+    /// Bookkeeping for every access to memory: locks, undef origins, watchpoints
+    MemoryChecks,
+    /// Validation statements
+    Validation,
+}
+
+const CATEGORIES: [(Category, &str); 8] = [
+    (Category::Steps, "steps"),
+    (Category::Calls, "function calls"),
+    (Category::MirLoading, "MIR loading"),
+    (Category::ForeignFns, "foreign functions"),
+    (Category::Intrinsics, "intrinsics"),
+    (Category::PointerOps, "pointer operations"),
+    (Category::MemoryChecks, "memory access checks"),
+    (Category::Validation, "validation"),
+];
+
+#[derive(Default)]
+pub struct Timings {
+    enabled: bool,
+    /// The total time spent in every category, and how often it was entered
+    totals: [(Duration, u64); 8],
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Self {
+        Timings {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    pub fn print(&self) {
+        if !self.enabled {
+            return;
+        }
+        eprintln!("miri self-profile:");
+        for &(category, name) in &CATEGORIES {
+            let (time, count) = self.totals[category as usize];
+            eprintln!(
+                "    {:<22} {:>6}.{:03}s {:>12} times",
+                name,
+                time.as_secs(),
+                time.subsec_nanos() / 1_000_000,
+                count,
+            );
+        }
+    }
+}
+
+/// Starts measuring, if the self-profile is enabled
+pub(crate) fn start(data: &MemoryData) -> Option<Instant> {
+    if data.timings.borrow().enabled {
+        Some(Instant::now())
+    } else {
+        None
+    }
+}
+
+/// Adds the time since `start` to `category`
+pub(crate) fn stop(data: &MemoryData, category: Category, start: Option<Instant>) {
+    if let Some(start) = start {
+        let elapsed = start.elapsed();
+        let total = &mut data.timings.borrow_mut().totals[category as usize];
+        total.0 += elapsed;
+        total.1 += 1;
+    }
+}
@@ -0,0 +1,70 @@
+//! A trace of every statement and terminator the program executes, one line each, for diffing the
+//! behavior of two runs (e.g. with two compiler versions). A line is the function's path, the
+//! basic block, the index of the statement (`term` for the terminator) and the start of its span:
+//!
+//! ```text
+//! main bb0[1] src/main.rs:3:13
+//! main bb0[term] src/main.rs:4:5
+//! foo::bar bb0[0] src/foo.rs:10:9
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use rustc::ty;
+
+use super::*;
+use rustc_compat;
+
+pub struct Tracer<'tcx> {
+    writer: BufWriter<File>,
+    /// The first error writing the trace, reported at the end
+    error: Option<io::Error>,
+    /// Paths of the functions seen so far, rendering them on every step is slow
+    paths: HashMap<ty::Instance<'tcx>, String>,
+}
+
+impl<'tcx> Tracer<'tcx> {
+    pub fn create(file: &str) -> io::Result<Self> {
+        Ok(Tracer {
+            writer: BufWriter::new(File::create(file)?),
+            error: None,
+            paths: HashMap::new(),
+        })
+    }
+
+    /// Writes out what is still buffered, returns the first error that happened while tracing
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+/// Called before every step, writes the line of the statement or terminator about to be executed
+pub(crate) fn before_step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>) {
+    let mut tracer = match ecx.machine.tracer.take() {
+        Some(tracer) => tracer,
+        None => return,
+    };
+    if let Some(frame) = ecx.stack().last() {
+        let tcx = ecx.tcx;
+        let path = tracer.paths
+            .entry(frame.instance)
+            .or_insert_with(|| rustc_compat::frame_fn_path(tcx, frame));
+        let (file, line, column, ..) = rustc_compat::span_lines(tcx, rustc_compat::frame_span(frame));
+        let result = if frame.stmt == frame.mir[frame.block].statements.len() {
+            writeln!(tracer.writer, "{} {:?}[term] {}:{}:{}", path, frame.block, file, line, column)
+        } else {
+            writeln!(tracer.writer, "{} {:?}[{}] {}:{}:{}", path, frame.block, frame.stmt, file, line, column)
+        };
+        if let Err(e) = result {
+            if tracer.error.is_none() {
+                tracer.error = Some(e);
+            }
+        }
+    }
+    ecx.machine.tracer = Some(tracer);
+}
@@ -0,0 +1,62 @@
+//! Attributes the bytes read from and written to memory to the statement that accessed them. Some
+//! programs are slow under miri because of the amount of memory they move around rather than the
+//! number of steps, this shows where that happens.
+
+use std::collections::HashMap;
+
+use rustc::ty::TyCtxt;
+use syntax::codemap::Span;
+
+use super::*;
+use rustc_compat;
+
+pub struct Traffic {
+    /// Number of most active statements to report
+    top: usize,
+    /// Bytes read and written by every statement
+    per_span: HashMap<Span, (u64, u64)>,
+}
+
+impl Traffic {
+    pub fn new(top: usize) -> Self {
+        Traffic {
+            top,
+            per_span: HashMap::new(),
+        }
+    }
+
+    pub fn report<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>) {
+        let mut spans: Vec<_> = self.per_span.iter().collect();
+        // Most traffic first
+        spans.sort_by_key(|&(_, &(read, written))| ::std::cmp::Reverse(read + written));
+        eprintln!("memory traffic hotspots (bytes read, bytes written):");
+        for (span, &(read, written)) in spans.into_iter().take(self.top) {
+            eprintln!("{:>12} {:>12}  {}", read, written, rustc_compat::span_to_string(tcx, *span));
+        }
+    }
+}
+
+/// The span of the statement (or terminator) that the next step is going to evaluate
+pub(crate) fn next_span<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>) -> Option<Span> {
+    if ecx.machine.traffic.is_none() {
+        return None;
+    }
+    ecx.stack().last().map(rustc_compat::frame_span)
+}
+
+/// Called after every step, attributes the memory accesses of the step to the span it evaluated
+pub(crate) fn step<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>, span: Option<Span>) {
+    let span = match span {
+        Some(span) => span,
+        None => return,
+    };
+    let read = ecx.memory().data.bytes_read.replace(0);
+    let written = ecx.memory().data.bytes_written.replace(0);
+    if read == 0 && written == 0 {
+        return;
+    }
+    let traffic = ecx.machine.traffic.as_mut().unwrap();
+    let entry = traffic.per_span.entry(span).or_insert((0, 0));
+    entry.0 += read;
+    entry.1 += written;
+}
@@ -0,0 +1,307 @@
+//! Explains reads of undefined bytes: follows the value that was read back to the place it came
+//! from and points at why that place is undefined. Memory remembers where some undefined bytes
+//! came from (fresh heap allocations and pointers partially overwritten in them); locals are
+//! traced through the MIR of their function.
+
+use std::collections::HashMap;
+
+use rustc::mir;
+use rustc::ty::layout::LayoutOf;
+use rustc_data_structures::indexed_vec::Idx;
+
+use super::*;
+use rustc_compat::{self, Assignment};
+
+/// How often a value is followed from one place to another before giving up
+const MAX_DEPTH: usize = 8;
+
+/// Why some bytes of an allocation are undefined
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Cause {
+    /// The bytes were allocated on the heap and not initialized
+    Allocation,
+    /// The bytes were part of a pointer, and a write to another part of it made them undefined
+    PartialPointerOverwrite,
+}
+
+/// Where the bytes `start..end` of an allocation became undefined
+#[derive(Clone, Debug)]
+pub(crate) struct Origin {
+    start: u64,
+    end: u64,
+    span: Span,
+    cause: Cause,
+}
+
+/// The origins of undefined bytes, by allocation
+pub(crate) type Origins = HashMap<u64, Vec<Origin>>;
+
+/// Records that `len` bytes at `ptr` were allocated at the statement the program is evaluating
+pub(crate) fn record_allocation<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    ptr: MemoryPointer,
+    len: u64,
+) {
+    // Allocations happen deep inside the standard library, point at the code that asked for one
+    let frame = ecx.stack()
+        .iter()
+        .rev()
+        .find(|frame| frame.instance.def_id().is_local())
+        .or_else(|| ecx.stack().last());
+    if let Some(frame) = frame {
+        let span = rustc_compat::frame_span(frame);
+        record(&ecx.memory.data, ptr.alloc_id.0, ptr.offset, ptr.offset + len, span, Cause::Allocation);
+    }
+}
+
+/// Called before every write, forgets the origins of the overwritten bytes and records the parts
+/// of partially overwritten pointers that become undefined. Only allocations with recorded origins
+/// (heap allocations) are tracked, writes to any other memory return right away.
+pub(crate) fn on_write<'a, 'tcx>(mem: &Memory<'a, 'tcx, Evaluator<'tcx>>, ptr: MemoryPointer, size: u64) {
+    let (start, end) = (ptr.offset, ptr.offset + size);
+    match mem.data.undef_origins.borrow_mut().get_mut(&ptr.alloc_id.0) {
+        Some(origins) => origins.retain(|origin| origin.start < start || origin.end > end),
+        None => return,
+    }
+    let span = match mem.data.current_span.get() {
+        Some(span) => span,
+        None => return,
+    };
+    let alloc = match mem.get(ptr.alloc_id) {
+        Ok(alloc) => alloc,
+        Err(_) => return,
+    };
+    let pointer_size = mem.pointer_size();
+    let first = start.saturating_sub(pointer_size - 1);
+    for (&reloc_start, _) in alloc.relocations.range(first..end) {
+        let reloc_end = reloc_start + pointer_size;
+        if reloc_start < start {
+            record(&mem.data, ptr.alloc_id.0, reloc_start, start, span, Cause::PartialPointerOverwrite);
+        }
+        if reloc_end > end {
+            record(&mem.data, ptr.alloc_id.0, end, reloc_end, span, Cause::PartialPointerOverwrite);
+        }
+    }
+}
+
+fn record(data: &MemoryData, alloc_id: u64, start: u64, end: u64, span: Span, cause: Cause) {
+    let mut origins = data.undef_origins.borrow_mut();
+    let origins = origins.entry(alloc_id).or_insert_with(Vec::new);
+    // Older origins of the same bytes are no longer relevant
+    origins.retain(|origin| origin.start < start || origin.end > end);
+    origins.push(Origin { start, end, span, cause });
+}
+
+/// Called when the evaluation ends with an error, after it was reported
+pub(crate) fn explain<'a, 'tcx>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, e: &EvalError<'tcx>) {
+    match e.kind {
+        EvalErrorKind::ReadUndefBytes => {}
+        _ => return,
+    }
+    let frame = match ecx.stack().len() {
+        0 => return,
+        len => len - 1,
+    };
+    let (mir, block, stmt) = {
+        let frame = &ecx.stack()[frame];
+        (frame.mir, frame.block, frame.stmt)
+    };
+    for place in read_places(mir, block, stmt) {
+        if explain_place(ecx, frame, place, 0) {
+            return;
+        }
+    }
+}
+
+/// The places the statement or terminator at `block` and `stmt` reads from
+fn read_places<'m, 'tcx>(mir: &'m mir::Mir<'tcx>, block: mir::BasicBlock, stmt: usize) -> Vec<&'m mir::Place<'tcx>> {
+    let block = &mir[block];
+    let mut operands = Vec::new();
+    let mut places = Vec::new();
+    match block.statements.get(stmt) {
+        Some(stmt) => if let mir::StatementKind::Assign(_, ref rvalue) = stmt.kind {
+            match *rvalue {
+                mir::Rvalue::Use(ref op) |
+                mir::Rvalue::Repeat(ref op, _) |
+                mir::Rvalue::Cast(_, ref op, _) |
+                mir::Rvalue::UnaryOp(_, ref op) => operands.push(op),
+                mir::Rvalue::BinaryOp(_, ref left, ref right) |
+                mir::Rvalue::CheckedBinaryOp(_, ref left, ref right) => {
+                    operands.push(left);
+                    operands.push(right);
+                }
+                mir::Rvalue::Aggregate(_, ref ops) => operands.extend(ops),
+                mir::Rvalue::Len(ref place) |
+                mir::Rvalue::Discriminant(ref place) => places.push(place),
+                mir::Rvalue::Ref(..) |
+                mir::Rvalue::NullaryOp(..) => {}
+            }
+        },
+        None => match block.terminator().kind {
+            mir::TerminatorKind::SwitchInt { ref discr, .. } => operands.push(discr),
+            mir::TerminatorKind::Assert { ref cond, .. } => operands.push(cond),
+            mir::TerminatorKind::Call { ref func, ref args, .. } => {
+                operands.push(func);
+                operands.extend(args);
+            }
+            mir::TerminatorKind::DropAndReplace { ref value, .. } => operands.push(value),
+            _ => {}
+        },
+    }
+    places.extend(operands.into_iter().filter_map(|op| match *op {
+        mir::Operand::Copy(ref place) | mir::Operand::Move(ref place) => Some(place),
+        mir::Operand::Constant(_) => None,
+    }));
+    places
+}
+
+/// Explains why `place` in the stack frame with the index `frame` holds undefined bytes, if it
+/// does. Returns whether an explanation was given.
+fn explain_place<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    frame: usize,
+    place: &mir::Place<'tcx>,
+    depth: usize,
+) -> bool {
+    let frame_index = frame;
+    let frame = &ecx.stack()[frame_index];
+    match *place {
+        mir::Place::Local(local) => match frame.get_local(local) {
+            Ok(Value::ByRef(PtrAndAlign { ptr, .. })) => {
+                let ty = ecx.monomorphize(frame.mir.local_decls[local].ty, frame.instance.substs);
+                explain_memory(ecx, ptr, ty, depth)
+            }
+            Ok(Value::ByVal(PrimVal::Undef)) |
+            Ok(Value::ByValPair(PrimVal::Undef, _)) |
+            Ok(Value::ByValPair(_, PrimVal::Undef)) => explain_local(ecx, frame_index, local, depth),
+            _ => false,
+        },
+        mir::Place::Projection(ref proj) => {
+            // Either the base itself (e.g. the pointer that is dereferenced) is undefined...
+            if explain_place(ecx, frame_index, &proj.base, depth) {
+                return true;
+            }
+            // ...or the memory it points to
+            let pointer = match (&proj.elem, &proj.base) {
+                (&mir::ProjectionElem::Deref, &mir::Place::Local(local)) => frame.get_local(local),
+                _ => return false,
+            };
+            let ptr = match pointer {
+                Ok(Value::ByVal(ptr)) | Ok(Value::ByValPair(ptr, _)) => Pointer::from(ptr),
+                _ => return false,
+            };
+            let ty = place.ty(frame.mir, ecx.tcx).to_ty(ecx.tcx);
+            explain_memory(ecx, ptr, ecx.monomorphize(ty, frame.instance.substs), depth)
+        }
+        mir::Place::Static(_) => false,
+    }
+}
+
+/// Explains the first undefined byte of the value of type `ty` at `ptr`
+fn explain_memory<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    ptr: Pointer,
+    ty: ty::Ty<'tcx>,
+    depth: usize,
+) -> bool {
+    let ptr = match ptr.to_ptr() {
+        Ok(ptr) => ptr,
+        Err(_) => return false,
+    };
+    let size = match ecx.layout_of(ty) {
+        Ok(layout) if !layout.is_unsized() => layout.size.bytes(),
+        _ => return false,
+    };
+    let alloc = match ecx.memory.get(ptr.alloc_id) {
+        Ok(alloc) => alloc,
+        Err(_) => return false,
+    };
+    let end = (ptr.offset + size).min(alloc.bytes.len() as u64);
+    let undef = match (ptr.offset..end).find(|&i| !alloc.undef_mask.get(i)) {
+        Some(undef) => undef,
+        None => return false,
+    };
+    let origins = ecx.memory.data.undef_origins.borrow();
+    let origin = origins
+        .get(&ptr.alloc_id.0)
+        .and_then(|origins| origins.iter().rev().find(|origin| origin.start <= undef && undef < origin.end));
+    if let Some(origin) = origin {
+        let msg = match origin.cause {
+            Cause::Allocation => "the undefined bytes were allocated here, and never initialized",
+            Cause::PartialPointerOverwrite => {
+                "the undefined bytes were part of a pointer, this write to another part of it made \
+                 the rest of the pointer undefined"
+            }
+        };
+        ecx.tcx.sess.span_note_without_error(origin.span, msg);
+        return true;
+    }
+    // The memory may belong to a local that was never initialized
+    for (index, frame) in ecx.stack().iter().enumerate().rev() {
+        for local in frame.mir.local_decls.indices() {
+            if let Ok(Value::ByRef(PtrAndAlign { ptr: local_ptr, .. })) = frame.get_local(local) {
+                if local_ptr.to_ptr().ok().map(|p| p.alloc_id) == Some(ptr.alloc_id) {
+                    return explain_local(ecx, index, local, depth + 1);
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Explains where the undefined value of `local` in the stack frame with the index `frame` was
+/// assigned
+fn explain_local<'a, 'tcx>(
+    ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    frame: usize,
+    local: mir::Local,
+    depth: usize,
+) -> bool {
+    if depth == MAX_DEPTH {
+        return false;
+    }
+    let frame_index = frame;
+    let frame = &ecx.stack()[frame_index];
+    let (local, assignments) = rustc_compat::trace_copies(frame.mir, local);
+    let assignment = match assignments.len() {
+        // Arguments are assigned by the caller
+        0 if local.index() > frame.mir.arg_count => None,
+        1 => Some(&assignments[0]),
+        _ => return false,
+    };
+    match assignment {
+        Some(&Assignment::Rvalue(&mir::Rvalue::Use(mir::Operand::Copy(ref source)), _)) |
+        Some(&Assignment::Rvalue(&mir::Rvalue::Use(mir::Operand::Move(ref source)), _)) => {
+            explain_place(ecx, frame_index, source, depth + 1)
+        }
+        Some(&Assignment::Call(&mir::Operand::Constant(ref constant), span)) => {
+            let def_id = match constant.ty.sty {
+                ty::TyFnDef(def_id, _) => def_id,
+                _ => return false,
+            };
+            let path = ecx.tcx.item_path_str(def_id);
+            if path.ends_with("mem::uninitialized") || path.ends_with("intrinsics::uninit") {
+                ecx.tcx.sess.span_note_without_error(
+                    span,
+                    "the undefined bytes come from this call to `mem::uninitialized`",
+                );
+                true
+            } else {
+                false
+            }
+        }
+        Some(_) => false,
+        None => {
+            let name = match rustc_compat::local_name(frame.mir, local) {
+                Some(name) => format!("`{}`", name),
+                None => "the value".to_owned(),
+            };
+            ecx.tcx.sess.span_note_without_error(
+                frame.mir.local_decls[local].source_info.span,
+                &format!("{} is declared here, but never initialized", name),
+            );
+            true
+        }
+    }
+}
+
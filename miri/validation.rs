@@ -651,8 +651,17 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
                     if mode.acquiring() {
                         let val = self.read_place(query.place.1)?;
                         let val = self.value_to_primval(ValTy { value: val, ty: query.ty })?;
-                        val.to_bytes()?;
-                        // TODO: Check if these are valid bool/float/codepoint/UTF-8
+                        let bits = val.to_bytes()?;
+                        // Every bit pattern is a valid float
+                        if !self.machine.config.disable_value_checks {
+                            match query.ty.sty {
+                                TyBool if bits > 1 => return err!(InvalidBool),
+                                TyChar if ::std::char::from_u32(bits as u32).is_none() => {
+                                    return err!(InvalidChar(bits))
+                                }
+                                _ => {}
+                            }
+                        }
                     }
                     Ok(())
                 }
@@ -698,7 +707,20 @@ impl<'a, 'tcx> EvalContextExt<'tcx> for EvalContext<'a, 'tcx, super::Evaluator<'
 
                 // Compound types
                 TyStr => {
-                    // TODO: Validate strings
+                    if mode.acquiring() && !self.machine.config.disable_value_checks {
+                        let (ptr, extra) = query.place.1.to_ptr_extra_aligned();
+                        let len = match extra {
+                            PlaceExtra::Length(len) => len,
+                            _ => bug!("TyStr must have a length as extra"),
+                        };
+                        let bytes = self.memory.read_bytes(ptr, len)?;
+                        if let Err(e) = ::std::str::from_utf8(bytes) {
+                            return err!(ValidationFailure(format!(
+                                "a `str` is not valid UTF-8, starting at byte {}",
+                                e.valid_up_to(),
+                            )));
+                        }
+                    }
                     Ok(())
                 }
                 TySlice(elem_ty) => {
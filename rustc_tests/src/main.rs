@@ -10,6 +10,7 @@ use std::path::{PathBuf, Path};
 use std::io::Write;
 use std::sync::{Mutex, Arc};
 use std::io;
+use std::cell::RefCell;
 
 
 use rustc::session::Session;
@@ -20,6 +21,7 @@ use rustc::session::config::{self, Input, ErrorOutputType};
 use rustc::hir::{self, itemlikevisit};
 use rustc::ty::TyCtxt;
 use syntax::ast;
+use miri::{ErrorCategory, EvalErrorKind, EvalOutcome, Limit, ResourceLimitsExt};
 
 struct MiriCompilerCalls {
     default: RustcDefaultCalls,
@@ -81,7 +83,7 @@ fn after_analysis<'a, 'tcx>(state: &mut CompileState<'a, 'tcx>) {
     state.session.abort_if_errors();
 
     let tcx = state.tcx.unwrap();
-    let limits = Default::default();
+    let limits = miri::ResourceLimits::builder().build().expect("the default limits are valid");
 
     if std::env::args().any(|arg| arg == "--test") {
         struct Visitor<'a, 'tcx: 'a>(miri::ResourceLimits, TyCtxt<'a, 'tcx, 'tcx>, &'a CompileState<'a, 'tcx>);
@@ -91,7 +93,7 @@ fn after_analysis<'a, 'tcx>(state: &mut CompileState<'a, 'tcx>) {
                     if i.attrs.iter().any(|attr| attr.name().map_or(false, |n| n == "test")) {
                         let did = self.1.hir.body_owner_def_id(body_id);
                         println!("running test: {}", self.1.def_path_debug_str(did));
-                        miri::eval_main(self.1, did, None, self.0);
+                        record_outcome(&miri::eval_main(self.1, did, None, self.0, Default::default()));
                         self.2.session.abort_if_errors();
                     }
                 }
@@ -104,7 +106,7 @@ fn after_analysis<'a, 'tcx>(state: &mut CompileState<'a, 'tcx>) {
         let entry_def_id = tcx.hir.local_def_id(entry_node_id);
         let start_wrapper = tcx.lang_items().start_fn().and_then(|start_fn|
                                 if tcx.is_mir_available(start_fn) { Some(start_fn) } else { None });
-        miri::eval_main(tcx, entry_def_id, start_wrapper, limits);
+        record_outcome(&miri::eval_main(tcx, entry_def_id, start_wrapper, limits, Default::default()));
 
         state.session.abort_if_errors();
     } else {
@@ -112,6 +114,40 @@ fn after_analysis<'a, 'tcx>(state: &mut CompileState<'a, 'tcx>) {
     }
 }
 
+/// Why an evaluation failed
+enum Failure {
+    /// A function without MIR was called
+    NoMir(String),
+    /// One of the limits of the evaluation was reached
+    Limit(Limit),
+    /// Any other error, with its message
+    Error(ErrorCategory, String),
+}
+
+thread_local! {
+    /// The failed evaluations of the current `run_miri`, recorded by `after_analysis`
+    static FAILURES: RefCell<Vec<Failure>> = RefCell::new(Vec::new());
+}
+
+fn record_outcome(outcome: &EvalOutcome) {
+    let category = match outcome.error_category() {
+        Some(category) => category,
+        None => return,
+    };
+    let failure = match *outcome {
+        EvalOutcome::LimitReached(limit) => Failure::Limit(limit),
+        EvalOutcome::Ub(ref e) => match e.kind {
+            EvalErrorKind::NoMirFor(ref path) => Failure::NoMir(path.clone()),
+            _ => Failure::Error(category, e.to_string()),
+        },
+        EvalOutcome::Panicked { ref message, .. } => {
+            Failure::Error(category, message.clone().unwrap_or_else(|| "the program panicked".to_owned()))
+        }
+        EvalOutcome::Completed { .. } | EvalOutcome::StoppedByDebugger => return,
+    };
+    FAILURES.with(|failures| failures.borrow_mut().push(failure));
+}
+
 fn main() {
     let path = option_env!("MIRI_RUSTC_TEST")
         .map(String::from)
@@ -124,11 +160,31 @@ fn main() {
     let mut crate_not_found = Vec::new();
     let mut success = 0;
     let mut failed = Vec::new();
-    let mut c_abi_fns = Vec::new();
-    let mut abi = Vec::new();
+    let mut undefined_behavior = Vec::new();
     let mut unsupported = Vec::new();
-    let mut unimplemented_intrinsic = Vec::new();
     let mut limits = Vec::new();
+    let mut memory_limit = Vec::new();
+    let mut guest_oom = Vec::new();
+    let mut divergences = Vec::new();
+    let mut host_target = false;
+    let mut bitwidths = Vec::new();
+    let mut base_args = Vec::new();
+    let mut env_args = std::env::args();
+    while let Some(arg) = env_args.next() {
+        if arg == "--miri_host_target" {
+            host_target = true; // remove the flag, rustc doesn't know it
+        } else if arg == "--bitwidths" {
+            // run every test once per pointer width, e.g. `--bitwidths 32,64`
+            let list = env_args.next().expect("--bitwidths needs a comma separated list of widths");
+            // checked here, before any test ran, instead of in the middle of the sweep
+            bitwidths = list.split(',').map(|bits| match bits.trim().parse::<u64>() {
+                Ok(bits) if bits == 32 || bits == 64 => bits,
+                _ => panic!("invalid bitwidth `{}` in --bitwidths, only 32 and 64 are supported", bits),
+            }).collect();
+        } else {
+            base_args.push(arg);
+        }
+    }
     let mut files: Vec<_> = std::fs::read_dir(path).unwrap().collect();
     while let Some(file) = files.pop() {
         let file = file.unwrap();
@@ -144,114 +200,125 @@ fn main() {
             continue;
         }
         let stderr = std::io::stderr();
-        write!(stderr.lock(), "test [miri-pass] {} ... ", path.display()).unwrap();
-        let mut host_target = false;
-        let mut args: Vec<String> = std::env::args().filter(|arg| {
-            if arg == "--miri_host_target" {
-                host_target = true;
-                false // remove the flag, rustc doesn't know it
-            } else {
-                true
+        // one run per bitwidth, or a single run for the target given on the command line
+        let runs: Vec<Option<u64>> = if bitwidths.is_empty() {
+            vec![None]
+        } else {
+            bitwidths.iter().cloned().map(Some).collect()
+        };
+        let mut passed_on = Vec::new();
+        let mut failed_on = Vec::new();
+        for bits in runs {
+            match bits {
+                Some(bits) => write!(stderr.lock(), "test [miri-pass] {} ({}-bit) ... ", path.display(), bits).unwrap(),
+                None => write!(stderr.lock(), "test [miri-pass] {} ... ", path.display()).unwrap(),
             }
-        }).collect();
-        // file to process
-        args.push(path.display().to_string());
-
-        let sysroot_flag = String::from("--sysroot");
-        if !args.contains(&sysroot_flag) {
-            args.push(sysroot_flag);
-            args.push(Path::new(&std::env::var("HOME").unwrap()).join(".xargo").join("HOST").display().to_string());
-        }
-
-        args.push("-Zmir-opt-level=3".to_owned());
-        // for auxilary builds in unit tests
-        args.push("-Zalways-encode-mir".to_owned());
-
-        // A threadsafe buffer for writing.
-        #[derive(Default, Clone)]
-        struct BufWriter(Arc<Mutex<Vec<u8>>>);
+            let mut args = base_args.clone();
+            // file to process
+            args.push(path.display().to_string());
 
-        impl Write for BufWriter {
-            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-                self.0.lock().unwrap().write(buf)
-            }
-            fn flush(&mut self) -> io::Result<()> {
-                self.0.lock().unwrap().flush()
+            let xargo_dir = Path::new(&std::env::var("HOME").unwrap()).join(".xargo");
+            let sysroot_flag = String::from("--sysroot");
+            if let Some(bits) = bits {
+                args.push("--target".to_owned());
+                args.push(bitwidth_target(bits).to_owned());
+                if !args.contains(&sysroot_flag) {
+                    // xargo keeps the sysroots of all non-host targets together
+                    args.push(sysroot_flag);
+                    args.push(xargo_dir.display().to_string());
+                }
+            } else if !args.contains(&sysroot_flag) {
+                args.push(sysroot_flag);
+                args.push(xargo_dir.join("HOST").display().to_string());
             }
-        }
-        let buf = BufWriter::default();
-        let output = buf.clone();
-        let result = std::panic::catch_unwind(|| {
-            rustc_driver::run_compiler(&args, &mut MiriCompilerCalls {
-                default: RustcDefaultCalls,
-                host_target,
-            }, None, Some(Box::new(buf)));
-        });
 
-        match result {
-            Ok(()) => {
-                success += 1;
-                writeln!(stderr.lock(), "ok").unwrap()
-            },
-            Err(_) => {
-                let output = output.0.lock().unwrap();
-                let output_err = std::str::from_utf8(&output).unwrap();
-                if let Some(text) = output_err.splitn(2, "no mir for `").nth(1) {
-                    let end = text.find('`').unwrap();
-                    mir_not_found.push(text[..end].to_string());
-                    writeln!(stderr.lock(), "NO MIR FOR `{}`", &text[..end]).unwrap();
-                } else if let Some(text) = output_err.splitn(2, "can't find crate for `").nth(1) {
-                    let end = text.find('`').unwrap();
-                    crate_not_found.push(text[..end].to_string());
-                    writeln!(stderr.lock(), "CAN'T FIND CRATE FOR `{}`", &text[..end]).unwrap();
-                } else {
-                    for text in output_err.split("error: ").skip(1) {
-                        let end = text.find('\n').unwrap_or(text.len());
-                        let c_abi = "can't call C ABI function: ";
-                        let unimplemented_intrinsic_s = "unimplemented intrinsic: ";
-                        let unsupported_s = "miri does not support ";
-                        let abi_s = "can't handle function with ";
-                        let limit_s = "reached the configured maximum ";
-                        if text.starts_with(c_abi) {
-                            c_abi_fns.push(text[c_abi.len()..end].to_string());
-                        } else if text.starts_with(unimplemented_intrinsic_s) {
-                            unimplemented_intrinsic.push(text[unimplemented_intrinsic_s.len()..end].to_string());
-                        } else if text.starts_with(unsupported_s) {
-                            unsupported.push(text[unsupported_s.len()..end].to_string());
-                        } else if text.starts_with(abi_s) {
-                            abi.push(text[abi_s.len()..end].to_string());
-                        } else if text.starts_with(limit_s) {
-                            limits.push(text[limit_s.len()..end].to_string());
-                        } else if text.find("aborting").is_none() {
-                            failed.push(text[..end].to_string());
+            args.push("-Zmir-opt-level=3".to_owned());
+            // for auxilary builds in unit tests
+            args.push("-Zalways-encode-mir".to_owned());
+
+            match run_miri(&args, host_target) {
+                Ok(()) => {
+                    success += 1;
+                    passed_on.extend(bits);
+                    writeln!(stderr.lock(), "ok").unwrap()
+                },
+                Err(output_err) => {
+                    failed_on.extend(bits);
+                    let failures = FAILURES.with(|failures| std::mem::replace(&mut *failures.borrow_mut(), Vec::new()));
+                    if failures.is_empty() {
+                        // The test did not compile
+                        if let Some(text) = output_err.splitn(2, "can't find crate for `").nth(1) {
+                            let end = text.find('`').unwrap();
+                            crate_not_found.push(text[..end].to_string());
+                            writeln!(stderr.lock(), "CAN'T FIND CRATE FOR `{}`", &text[..end]).unwrap();
+                            continue;
+                        }
+                        for text in output_err.split("error: ").skip(1) {
+                            let end = text.find('\n').unwrap_or(text.len());
+                            if text.find("aborting").is_none() {
+                                failed.push(text[..end].to_string());
+                            }
+                        }
+                    }
+                    for failure in failures {
+                        match failure {
+                            Failure::NoMir(fn_path) => {
+                                writeln!(stderr.lock(), "NO MIR FOR `{}`", fn_path).unwrap();
+                                mir_not_found.push(fn_path);
+                            }
+                            // miri itself ran out of its configured memory
+                            Failure::Limit(Limit::Memory) => memory_limit.push(path.display().to_string()),
+                            // the program's allocator failed and the program gave up
+                            Failure::Limit(Limit::ProgramMemory) => guest_oom.push(path.display().to_string()),
+                            Failure::Limit(limit) => limits.push(format!("{:?}", limit)),
+                            Failure::Error(ErrorCategory::UndefinedBehavior, msg) => undefined_behavior.push(msg),
+                            Failure::Error(ErrorCategory::Unsupported, msg) => unsupported.push(msg),
+                            Failure::Error(category, msg) => failed.push(format!("{}: {}", category, msg)),
                         }
                     }
                     writeln!(stderr.lock(), "stderr: \n {}", output_err).unwrap();
                 }
             }
         }
+        if !passed_on.is_empty() && !failed_on.is_empty() {
+            // the test makes assumptions about the pointer width
+            divergences.push(format!(
+                "{}: passes on {}, fails on {}",
+                path.display(),
+                format_bitwidths(&passed_on),
+                format_bitwidths(&failed_on),
+            ));
+        }
     }
     let stderr = std::io::stderr();
     let mut stderr = stderr.lock();
     writeln!(stderr, "{} success, {} no mir, {} crate not found, {} failed, \
-                        {} C fn, {} ABI, {} unsupported, {} intrinsic",
+                        {} undefined behavior, {} unsupported, {} limits, \
+                        {} miri memory limit, {} guest out of memory, {} bitwidth divergences",
                         success, mir_not_found.len(), crate_not_found.len(), failed.len(),
-                        c_abi_fns.len(), abi.len(), unsupported.len(), unimplemented_intrinsic.len()).unwrap();
+                        undefined_behavior.len(), unsupported.len(),
+                        limits.len(), memory_limit.len(), guest_oom.len(), divergences.len()).unwrap();
     writeln!(stderr, "# The \"other reasons\" errors").unwrap();
     writeln!(stderr, "(sorted, deduplicated)").unwrap();
     print_vec(&mut stderr, failed);
 
-    writeln!(stderr, "# can't call C ABI function").unwrap();
-    print_vec(&mut stderr, c_abi_fns);
-
-    writeln!(stderr, "# unsupported ABI").unwrap();
-    print_vec(&mut stderr, abi);
+    writeln!(stderr, "# undefined behavior").unwrap();
+    print_vec(&mut stderr, undefined_behavior);
 
     writeln!(stderr, "# unsupported").unwrap();
     print_vec(&mut stderr, unsupported);
 
-    writeln!(stderr, "# unimplemented intrinsics").unwrap();
-    print_vec(&mut stderr, unimplemented_intrinsic);
+    writeln!(stderr, "# reached a configured limit").unwrap();
+    print_vec(&mut stderr, limits);
+
+    writeln!(stderr, "# miri memory limit reached (raise `memory_size`)").unwrap();
+    print_vec(&mut stderr, memory_limit);
+
+    writeln!(stderr, "# guest allocation failed").unwrap();
+    print_vec(&mut stderr, guest_oom);
+
+    writeln!(stderr, "# different results for different bitwidths").unwrap();
+    print_vec(&mut stderr, divergences);
 
     writeln!(stderr, "# mir not found").unwrap();
     print_vec(&mut stderr, mir_not_found);
@@ -260,6 +327,47 @@ fn main() {
     print_vec(&mut stderr, crate_not_found);
 }
 
+/// Runs miri with the given rustc arguments, returning its output if the compiler panicked
+fn run_miri(args: &[String], host_target: bool) -> Result<(), String> {
+    // A threadsafe buffer for writing.
+    #[derive(Default, Clone)]
+    struct BufWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for BufWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.lock().unwrap().flush()
+        }
+    }
+    let buf = BufWriter::default();
+    let output = buf.clone();
+    let result = std::panic::catch_unwind(|| {
+        rustc_driver::run_compiler(args, &mut MiriCompilerCalls {
+            default: RustcDefaultCalls,
+            host_target,
+        }, None, Some(Box::new(buf)));
+    });
+    result.map_err(|_| {
+        let output = output.0.lock().unwrap();
+        std::str::from_utf8(&output).unwrap().to_owned()
+    })
+}
+
+/// The target used for running tests with the given pointer width
+fn bitwidth_target(bits: u64) -> &'static str {
+    match bits {
+        32 => "i686-unknown-linux-gnu",
+        64 => "x86_64-unknown-linux-gnu",
+        _ => unreachable!("--bitwidths only accepts 32 and 64"),
+    }
+}
+
+fn format_bitwidths(bitwidths: &[u64]) -> String {
+    bitwidths.iter().map(|bits| format!("{}-bit", bits)).collect::<Vec<_>>().join(", ")
+}
+
 fn print_vec<W: std::io::Write>(stderr: &mut W, v: Vec<String>) {
     writeln!(stderr, "```").unwrap();
     for (n, s) in vec_to_hist(v).into_iter().rev() {
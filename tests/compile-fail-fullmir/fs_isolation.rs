@@ -0,0 +1,7 @@
+// error-pattern: miri does not support `open` without -Zmiri-allow-fs
+
+use std::fs::File;
+
+fn main() {
+    let _file = File::open("Cargo.toml");
+}
@@ -0,0 +1,8 @@
+fn max() -> u8 {
+    255
+}
+
+fn main() {
+    let m = max();
+    let _x = m + 1; //~ ERROR attempt to add with overflow
+}
@@ -0,0 +1,8 @@
+fn divisor() -> i32 {
+    0
+}
+
+fn main() {
+    let d = divisor();
+    let _x = 42 / d; //~ ERROR attempt to divide by zero
+}
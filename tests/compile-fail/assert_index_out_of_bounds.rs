@@ -0,0 +1,9 @@
+fn index(len: usize) -> usize {
+    len + 2
+}
+
+fn main() {
+    let v = [1u8, 2, 3, 4];
+    let i = index(v.len());
+    let _x = v[i]; //~ ERROR index out of bounds: the len is 4 but the index is 6
+}
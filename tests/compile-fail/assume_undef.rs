@@ -0,0 +1,10 @@
+// compile-flags: -Zmir-emit-validate=0
+#![feature(core_intrinsics)]
+
+fn main() {
+    let b: bool = unsafe { std::mem::uninitialized() };
+    unsafe {
+        // An undefined assumption is as bad as a false one
+        std::intrinsics::assume(b); //~ ERROR: attempted to read undefined bytes
+    }
+}
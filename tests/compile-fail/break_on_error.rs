@@ -0,0 +1,8 @@
+// The breakpoint only prints the interpreter state, the error is reported as usual
+// compile-flags: -Zmir-emit-validate=0 -Zmiri-break-on-error=ReadUndefBytes
+
+fn main() {
+    let x: u32 = unsafe { std::mem::uninitialized() };
+    let y = x + 1; //~ ERROR: attempted to read undefined bytes
+    panic!("this should never print: {}", y);
+}
@@ -0,0 +1,16 @@
+// Breakpoints also work for the errors of miri itself, not only those of the interpreter
+// compile-flags: -Zmiri-max-calls-per-fn=10 -Zmiri-break-on-error=LimitReached
+
+//error-pattern: reached the configured maximum number of calls (10)
+
+fn step(n: u64) -> u64 {
+    n + 1
+}
+
+fn main() {
+    let f: fn(u64) -> u64 = step;
+    let mut n = 0;
+    while n < 100 {
+        n = f(n);
+    }
+}
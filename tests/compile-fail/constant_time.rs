@@ -0,0 +1,17 @@
+// compile-flags: -Zmiri-constant-time=compare,tests/compile-fail/auxiliary/constant_time_secret,tests/compile-fail/auxiliary/constant_time_guess
+
+// Returning at the first mismatch reveals how much of the secret was guessed correctly
+fn compare(input: &[u8]) {
+    let secret = [1u8, 2, 3, 4];
+    let mut i = 0;
+    while i < secret.len() {
+        if input[i] != secret[i] { //~ ERROR depends on its input
+            return;
+        }
+        i += 1;
+    }
+}
+
+fn main() {
+    compare(&[1, 2, 3, 4]);
+}
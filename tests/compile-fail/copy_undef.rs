@@ -0,0 +1,13 @@
+// This should fail even without validation
+// compile-flags: -Zmir-emit-validate=0
+
+use std::ptr;
+
+fn main() {
+    let src: [u8; 4] = unsafe { std::mem::uninitialized() };
+    let mut dest = [0u8; 4];
+    // Copying undefined bytes is fine, they stay undefined in the destination
+    unsafe { ptr::copy_nonoverlapping(src.as_ptr(), dest.as_mut_ptr(), 4) };
+    let x = dest[2] + 1; //~ ERROR: attempted to read undefined bytes
+    panic!("this should never print: {}", x);
+}
@@ -0,0 +1,11 @@
+#![feature(libc)]
+extern crate libc;
+
+// error-pattern: tried to deallocate Machine(Rust) memory but gave Machine(C) as the kind
+
+fn main() {
+    let b = Box::new(42);
+    unsafe {
+        libc::free(Box::into_raw(b) as *mut libc::c_void);
+    }
+}
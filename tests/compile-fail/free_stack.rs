@@ -0,0 +1,11 @@
+#![feature(libc)]
+extern crate libc;
+
+// error-pattern: tried to deallocate Stack memory but gave Machine(C) as the kind
+
+fn main() {
+    let mut x = 42;
+    unsafe {
+        libc::free(&mut x as *mut i32 as *mut libc::c_void);
+    }
+}
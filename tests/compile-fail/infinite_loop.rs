@@ -0,0 +1,10 @@
+// compile-flags: -Zmiri-detect-loops
+
+// error-pattern: infinite loop detected
+
+fn main() {
+    let mut x = 0u8;
+    loop {
+        x = x.wrapping_add(1);
+    }
+}
@@ -0,0 +1,16 @@
+// compile-flags: -Zmiri-max-calls-per-fn=100
+
+//error-pattern: reached the configured maximum number of calls (100)
+
+fn step(n: u64) -> u64 {
+    n + 1
+}
+
+// Every call returns before the next one, the stack never gets deep
+fn main() {
+    let f: fn(u64) -> u64 = step;
+    let mut n = 0;
+    while n < 1000 {
+        n = f(n);
+    }
+}
@@ -0,0 +1,8 @@
+// Validation forces more allocation; disable it.
+// compile-flags: -Zmir-emit-validate=0 -Zmiri-memory-limit=1K
+
+// The flag takes precedence over the default memory limit
+fn main() {
+    let _x = [42u8; 2048];
+    //~^ERROR tried to allocate 2048 more bytes, but only
+}
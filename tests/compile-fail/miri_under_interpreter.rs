@@ -0,0 +1,15 @@
+#![feature(linkage)]
+
+// The definition is only used in native builds, miri overrides it
+#[no_mangle]
+#[linkage = "weak"]
+pub extern "C" fn miri_under_interpreter() -> bool {
+    false
+}
+
+fn main() {
+    if miri_under_interpreter() {
+        let x: *const i32 = std::ptr::null();
+        let _y = unsafe { *x }; //~ ERROR: invalid use of NULL pointer
+    }
+}
@@ -0,0 +1,7 @@
+#![feature(core_intrinsics)]
+
+fn main() {
+    unsafe {
+        std::intrinsics::move_val_init(std::ptr::null_mut::<u32>(), 42); //~ ERROR: invalid use of NULL pointer
+    }
+}
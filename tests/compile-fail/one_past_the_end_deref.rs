@@ -0,0 +1,12 @@
+// error-pattern: outside bounds of allocation
+
+fn main() {
+    let v = [1u32, 2, 3];
+    let start = v.as_ptr();
+    // Creating and comparing the pointer is fine
+    let end = unsafe { start.offset(3) };
+    assert!(end > start);
+    // Reading from it is not
+    let x = unsafe { *end };
+    panic!("this should never print: {}", x);
+}
@@ -0,0 +1,9 @@
+#![feature(custom_attribute, attr_literals)]
+#![miri(memory_size=100000)]
+
+// error-pattern: the evaluated program aborted: out of memory
+
+fn main() {
+    let v: Vec<u8> = Vec::with_capacity(200000);
+    assert_eq!(v.capacity(), 200000);
+}
@@ -0,0 +1,8 @@
+// error-pattern: outside bounds of allocation
+fn main() {
+    let b = Box::new([0u32; 2]);
+    let x = &*b as *const [u32; 2] as *const u32;
+    // One past the end is fine, but two past the end is not
+    let x = unsafe { x.offset(2).offset(1) };
+    panic!("this should never print: {:?}", x);
+}
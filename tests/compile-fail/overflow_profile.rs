@@ -0,0 +1,15 @@
+// compile-flags: -Zmiri-warn-overflow-profile -Coverflow-checks=off
+
+fn add(x: u8, y: u8) -> u8 {
+    x + y //~ WARN wraps around, but it panics in builds with overflow checks
+}
+
+fn main() {
+    assert_eq!(add(1, 2), 3);
+    let wrapped = add(200, 100);
+    // Only warned about once
+    assert_eq!(add(250, 10), 4);
+    let v: Vec<u8> = vec![1, 2];
+    let x = unsafe { *v.as_ptr().wrapping_offset(wrapped as isize) }; //~ ERROR outside bounds of allocation
+    panic!("this should never print: {}", x);
+}
@@ -2,7 +2,8 @@ fn main() {
     let mut p = &42;
     unsafe {
         let ptr: *mut _ = &mut p;
-        *(ptr as *mut u8) = 123; // if we ever support 8 bit pointers, this is gonna cause
+        *(ptr as *mut u8) = 123; //~ NOTE this write to another part of it made the rest of the pointer undefined
+        // if we ever support 8 bit pointers, this is gonna cause
         // "attempted to interpret some raw bytes as a pointer address" instead of
         // "attempted to read undefined bytes"
     }
@@ -0,0 +1,19 @@
+// compile-flags: -Zmir-emit-validate=0
+
+#![allow(dead_code, unused_variables)]
+
+#[repr(packed)]
+struct Header {
+    tag: u8,
+    len: u32,
+}
+
+fn main() {
+    let header = Header {
+        tag: 1,
+        len: 99,
+    };
+    let len = unsafe { &header.len }; //~ NOTE the reference was created here, to field `len` of the packed struct `Header`
+    //~^ NOTE instead of taking a reference to the field
+    let _x = *len; //~ ERROR tried to access memory with alignment 1, but alignment 4 is required
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let x = &1u8 as *const u8 as usize;
+    let y = &2u8 as *const u8 as usize;
+    let _distance = x - y; //~ ERROR: attempted to do invalid arithmetic on pointers
+}
@@ -0,0 +1,8 @@
+// compile-flags: --test
+// error-pattern: the test did not panic, but it is marked `#[should_panic]`
+
+#[test]
+#[should_panic]
+fn no_panic() {
+    assert_eq!(1 + 1, 2);
+}
@@ -0,0 +1,8 @@
+// compile-flags: --test
+// error-pattern: the test panicked with `index out of range`, which does not contain the expected `overflow`
+
+#[test]
+#[should_panic(expected = "overflow")]
+fn wrong_message() {
+    panic!("index out of range");
+}
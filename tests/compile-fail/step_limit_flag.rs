@@ -0,0 +1,12 @@
+// compile-flags: -Zmiri-step-limit=1000
+
+//error-pattern: reached the configured maximum execution time
+#![feature(custom_attribute, attr_literals)]
+#![miri(step_limit=1000000)]
+
+// The flag takes precedence over the attribute
+fn main() {
+    for i in 0..1000000 {
+        assert!(i < 1000);
+    }
+}
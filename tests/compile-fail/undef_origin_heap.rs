@@ -0,0 +1,8 @@
+// compile-flags: -Zmir-emit-validate=0
+
+fn main() {
+    let mut v: Vec<u8> = Vec::with_capacity(4); //~ NOTE the undefined bytes were allocated here, and never initialized
+    unsafe { v.set_len(4) };
+    let x = v[2] + 1; //~ ERROR attempted to read undefined bytes
+    panic!("this should never print: {}", x);
+}
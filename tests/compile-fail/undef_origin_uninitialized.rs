@@ -0,0 +1,7 @@
+// compile-flags: -Zmir-emit-validate=0
+
+fn main() {
+    let v: [u32; 2] = unsafe { std::mem::uninitialized() }; //~ NOTE the undefined bytes come from this call to `mem::uninitialized`
+    let x = v[1] + 1; //~ ERROR attempted to read undefined bytes
+    panic!("this should never print: {}", x);
+}
@@ -0,0 +1,14 @@
+#![feature(core_intrinsics)]
+
+fn pick(x: u8) -> u8 {
+    match x {
+        0 => 1,
+        1 => 2,
+        _ => unsafe { std::intrinsics::unreachable() }, //~ ERROR entered unreachable code
+    }
+}
+
+fn main() {
+    assert_eq!(pick(0) + pick(1), 3);
+    pick(5);
+}
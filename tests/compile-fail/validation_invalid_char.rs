@@ -0,0 +1,14 @@
+// error-pattern: tried to interpret an invalid 32-bit value as a char: 55296
+#![allow(unused_variables)]
+
+mod safe {
+    pub(crate) fn is_digit(c: char) -> bool {
+        c.is_digit(10)
+    }
+}
+
+fn main() {
+    // A surrogate, not a Unicode scalar value
+    let c = unsafe { std::mem::transmute::<u32, char>(0xd800) };
+    safe::is_digit(c);
+}
@@ -0,0 +1,14 @@
+// error-pattern: a `str` is not valid UTF-8, starting at byte 1
+#![allow(unused_variables)]
+
+mod safe {
+    pub(crate) fn len(s: &str) -> usize {
+        s.len()
+    }
+}
+
+fn main() {
+    let bytes = [b'a', 0xff, b'b'];
+    let s = unsafe { std::mem::transmute::<&[u8], &str>(&bytes) };
+    safe::len(s);
+}
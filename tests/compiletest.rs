@@ -1,6 +1,7 @@
 #![feature(slice_concat_ext)]
 
 extern crate compiletest_rs as compiletest;
+extern crate miri;
 
 use std::slice::SliceConcatExt;
 use std::path::{PathBuf, Path};
@@ -50,16 +51,17 @@ fn compile_fail(sysroot: &Path, path: &str, target: &str, host: &str, fullmir: b
             // skip fullmir on nonhost
             return;
         }
-        let sysroot = std::env::home_dir().unwrap()
-            .join(".xargo")
-            .join("HOST");
-        config.target_rustcflags = Some(format!("--sysroot {}", sysroot.to_str().unwrap()));
-        config.src_base = PathBuf::from(path.to_string());
+        let sysroot = fullmir_sysroot();
+        flags.push(format!("--sysroot {}", sysroot.to_str().unwrap()));
     } else {
-        config.target_rustcflags = Some(format!("--sysroot {}", sysroot.to_str().unwrap()));
-        config.src_base = PathBuf::from(path.to_string());
+        flags.push(format!("--sysroot {}", sysroot.to_str().unwrap()));
     }
+    config.src_base = PathBuf::from(path.to_string());
     flags.push("-Zmir-emit-validate=1".to_owned());
+    // compiletest checks the JSON diagnostics against the `//~ ERROR` (`//~^` for the line above)
+    // annotations of a test: every annotated error has to be reported at its line, and no other
+    // error may be. Errors that miri reports outside of the test file (e.g. in liballoc) are
+    // matched with `// error-pattern:` instead.
     config.target_rustcflags = Some(flags.join(" "));
     config.target = target.to_owned();
     compiletest::run_tests(&config);
@@ -110,10 +112,7 @@ fn miri_pass(path: &str, target: &str, host: &str, fullmir: bool, opt: bool) {
             // skip fullmir on nonhost
             return;
         }
-        let sysroot = std::env::home_dir().unwrap()
-            .join(".xargo")
-            .join("HOST");
-
+        let sysroot = fullmir_sysroot();
         flags.push(format!("--sysroot {}", sysroot.to_str().unwrap()));
     }
     if opt {
@@ -158,6 +157,12 @@ fn get_sysroot() -> PathBuf {
     PathBuf::from(sysroot.trim())
 }
 
+/// The libstd with full MIR: `MIRI_SYSROOT`, the one built by `cargo miri setup`, or the one
+/// built with `xargo/build.sh`
+fn fullmir_sysroot() -> PathBuf {
+    miri::fullmir_sysroot().unwrap_or_else(|| std::env::home_dir().unwrap().join(".xargo").join("HOST"))
+}
+
 fn get_host() -> String {
     let rustc = rustc_test_suite().unwrap_or(PathBuf::from("rustc"));
     println!("using rustc at {}", rustc.display());
@@ -182,6 +187,8 @@ fn run_pass_miri(opt: bool) {
         miri_pass("tests/run-pass", &target, &host, false, opt);
     });
     miri_pass("tests/run-pass-fullmir", &host, &host, true, opt);
+    // These use miri's own flags, so they cannot be run against rustc
+    miri_pass("tests/run-pass-miri-only", &host, &host, true, opt);
 }
 
 #[test]
@@ -0,0 +1,247 @@
+//! Tests of the library API for tools embedding the interpreter in their own rustc driver. Each
+//! test compiles `tests/embedding/functions.rs` and evaluates some of its functions with the
+//! type context, like such a tool would.
+
+#![feature(rustc_private, i128_type)]
+
+extern crate getopts;
+extern crate miri;
+extern crate rustc;
+extern crate rustc_driver;
+extern crate rustc_errors;
+extern crate syntax;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::middle::cstore::CrateStore;
+use rustc::session::Session;
+use rustc::session::config::{self, ErrorOutputType, Input};
+use rustc::ty::TyCtxt;
+use rustc_driver::{Compilation, CompilerCalls, RustcDefaultCalls};
+use rustc_driver::driver::{CompileController, CompileState};
+use syntax::ast;
+
+use miri::{EvalContext, Evaluator, HostEvalContextExt, PrimVal, ResourceLimitsExt, Value};
+
+/// What a test does with the compiled crate
+type Check = for<'a, 'tcx> fn(TyCtxt<'a, 'tcx, 'tcx>);
+
+struct EmbeddingCompilerCalls {
+    default: RustcDefaultCalls,
+    check: Check,
+}
+
+impl<'a> CompilerCalls<'a> for EmbeddingCompilerCalls {
+    fn early_callback(
+        &mut self,
+        matches: &getopts::Matches,
+        sopts: &config::Options,
+        cfg: &ast::CrateConfig,
+        descriptions: &rustc_errors::registry::Registry,
+        output: ErrorOutputType,
+    ) -> Compilation {
+        self.default.early_callback(matches, sopts, cfg, descriptions, output)
+    }
+    fn no_input(
+        &mut self,
+        matches: &getopts::Matches,
+        sopts: &config::Options,
+        cfg: &ast::CrateConfig,
+        odir: &Option<PathBuf>,
+        ofile: &Option<PathBuf>,
+        descriptions: &rustc_errors::registry::Registry,
+    ) -> Option<(Input, Option<PathBuf>)> {
+        self.default.no_input(matches, sopts, cfg, odir, ofile, descriptions)
+    }
+    fn late_callback(
+        &mut self,
+        matches: &getopts::Matches,
+        sess: &Session,
+        cstore: &CrateStore,
+        input: &Input,
+        odir: &Option<PathBuf>,
+        ofile: &Option<PathBuf>,
+    ) -> Compilation {
+        self.default.late_callback(matches, sess, cstore, input, odir, ofile)
+    }
+    fn build_controller(&mut self, sess: &Session, matches: &getopts::Matches) -> CompileController<'a> {
+        let mut control = self.default.build_controller(sess, matches);
+        let check = self.check;
+        control.after_analysis.callback = Box::new(move |state: &mut CompileState| {
+            state.session.abort_if_errors();
+            check(state.tcx.unwrap());
+        });
+        // The crate is only evaluated, not translated
+        control.after_analysis.stop = Compilation::Stop;
+        control
+    }
+}
+
+fn sysroot() -> String {
+    std::env::var("MIRI_SYSROOT").unwrap_or_else(|_| {
+        let sysroot = std::process::Command::new("rustc")
+            .arg("--print")
+            .arg("sysroot")
+            .output()
+            .expect("rustc not found")
+            .stdout;
+        String::from_utf8(sysroot).expect("sysroot is not utf8").trim().to_owned()
+    })
+}
+
+/// Compiles the test crate and runs `check` with its type context. The compiler needs a bigger
+/// stack than the test threads have, so it runs on its own thread.
+fn with_tcx(check: Check) {
+    let args = vec![
+        "embedding".to_owned(),
+        "tests/embedding/functions.rs".to_owned(),
+        "--crate-type".to_owned(),
+        "lib".to_owned(),
+        "--sysroot".to_owned(),
+        sysroot(),
+        "-Zalways-encode-mir".to_owned(),
+    ];
+    let compiler = std::thread::Builder::new()
+        .stack_size(16 << 20)
+        .spawn(move || {
+            // Errors in the test crate abort the compilation with a panic, stopping after the
+            // analysis is not an error
+            rustc_driver::run_compiler(&args, &mut EmbeddingCompilerCalls {
+                default: RustcDefaultCalls,
+                check,
+            }, None, None);
+        })
+        .unwrap();
+    if compiler.join().is_err() {
+        panic!("the check failed, see the output above");
+    }
+}
+
+/// The function of the test crate with the given name
+fn find_fn<'a, 'tcx: 'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, name: &str) -> DefId {
+    tcx.hir.krate().bodies.keys()
+        .map(|&body_id| tcx.hir.body_owner(body_id))
+        .filter(|&node_id| match tcx.hir.body_owner_kind(node_id) {
+            hir::BodyOwnerKind::Fn => true,
+            hir::BodyOwnerKind::Const | hir::BodyOwnerKind::Static(_) => false,
+        })
+        .map(|node_id| tcx.hir.local_def_id(node_id))
+        .find(|&def_id| tcx.item_path_str(def_id) == name)
+        .unwrap_or_else(|| panic!("no function `{}` in the test crate", name))
+}
+
+fn eval_context<'a, 'tcx: 'a>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    config: miri::MiriConfig,
+) -> EvalContext<'a, 'tcx, Evaluator<'tcx>> {
+    let limits = miri::ResourceLimits::builder().build().expect("the default limits are valid");
+    miri::new_eval_context(tcx, limits, config)
+}
+
+/// Calls the function `name` with `args`, panics with the diagnostic if the evaluation fails
+fn call<'a, 'tcx: 'a>(
+    ecx: &mut EvalContext<'a, 'tcx, Evaluator<'tcx>>,
+    name: &str,
+    args: &[Value],
+) -> Value {
+    let def_id = find_fn(ecx.tcx, name);
+    match miri::eval_fn(ecx, def_id, args) {
+        Ok(value) => value,
+        Err(mut e) => {
+            ecx.report(&mut e);
+            panic!("evaluating `{}` failed", name);
+        }
+    }
+}
+
+fn u32_result<'a, 'tcx: 'a>(ecx: &EvalContext<'a, 'tcx, Evaluator<'tcx>>, value: Value) -> u64 {
+    let ty = ecx.tcx.types.u32;
+    ecx.value_to_primval(miri::ValTy { value, ty }).and_then(|val| val.to_u64()).unwrap()
+}
+
+#[test]
+fn eval_fn_returns_the_result() {
+    with_tcx(|tcx| {
+        let mut ecx = eval_context(tcx, Default::default());
+        let sum = call(&mut ecx, "add", &[
+            Value::ByVal(PrimVal::Bytes(2)),
+            Value::ByVal(PrimVal::Bytes(3)),
+        ]);
+        assert_eq!(u32_result(&ecx, sum), 5);
+    });
+}
+
+#[test]
+fn eval_fn_runs_the_step_observers() {
+    with_tcx(|tcx| {
+        let steps = Rc::new(RefCell::new(0));
+        let mut config = miri::MiriConfig::default();
+        let counter = steps.clone();
+        config.step_observers.add(move |_, _| *counter.borrow_mut() += 1);
+        let mut ecx = eval_context(tcx, config);
+        let sum = call(&mut ecx, "sum_to", &[Value::ByVal(PrimVal::Bytes(10))]);
+        assert_eq!(u32_result(&ecx, sum), 55);
+        // Every iteration of the loop is several steps
+        assert!(*steps.borrow() > 10, "the observer only saw {} steps", steps.borrow());
+    });
+}
+
+#[test]
+fn alloc_str_passes_a_str() {
+    with_tcx(|tcx| {
+        let mut ecx = eval_context(tcx, Default::default());
+        let input = ecx.alloc_str("embedded interpreter").unwrap();
+        let count = call(&mut ecx, "count_vowels", &[input]);
+        assert_eq!(u32_result(&ecx, count), 7);
+    });
+}
+
+#[test]
+fn alloc_bytes_passes_a_slice() {
+    with_tcx(|tcx| {
+        let mut ecx = eval_context(tcx, Default::default());
+        let input = ecx.alloc_bytes(&[1, 2, 3, 250]).unwrap();
+        let sum = call(&mut ecx, "sum_bytes", &[input]);
+        assert_eq!(u32_result(&ecx, sum), 256);
+        // An empty slice is fine too
+        let empty = ecx.alloc_bytes(&[]).unwrap();
+        let sum = call(&mut ecx, "sum_bytes", &[empty]);
+        assert_eq!(u32_result(&ecx, sum), 0);
+    });
+}
+
+#[test]
+fn alloc_scalar_passes_a_reference() {
+    with_tcx(|tcx| {
+        let mut ecx = eval_context(tcx, Default::default());
+        let input = ecx.alloc_scalar(PrimVal::Bytes(21), tcx.types.u32).unwrap();
+        let doubled = call(&mut ecx, "double", &[input]);
+        assert_eq!(u32_result(&ecx, doubled), 42);
+    });
+}
+
+#[test]
+fn registered_shims_are_called() {
+    with_tcx(|tcx| {
+        let recorded = Rc::new(RefCell::new(Vec::new()));
+        let mut config = miri::MiriConfig::default();
+        config.shims.register("host_answer", |ecx, args| {
+            let question = ecx.value_to_primval(args[0])?.to_u64()?;
+            Ok(Some(PrimVal::Bytes((question * 2) as u128)))
+        });
+        let record = recorded.clone();
+        config.shims.register("host_record", move |ecx, args| {
+            record.borrow_mut().push(ecx.value_to_primval(args[0])?.to_u64()?);
+            // The function returns `()`
+            Ok(None)
+        });
+        let mut ecx = eval_context(tcx, config);
+        let answer = call(&mut ecx, "ask", &[Value::ByVal(PrimVal::Bytes(20))]);
+        assert_eq!(u32_result(&ecx, answer), 41);
+        assert_eq!(*recorded.borrow(), vec![20]);
+    });
+}
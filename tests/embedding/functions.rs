@@ -0,0 +1,55 @@
+// The functions `tests/embedding.rs` calls with `eval_fn`
+
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+pub fn sum_to(n: u32) -> u32 {
+    let mut sum = 0;
+    let mut i = 1;
+    while i <= n {
+        sum += i;
+        i += 1;
+    }
+    sum
+}
+
+pub fn count_vowels(s: &str) -> u32 {
+    let bytes = s.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'a' | b'e' | b'i' | b'o' | b'u' => count += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    count
+}
+
+pub fn sum_bytes(bytes: &[u8]) -> u32 {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        sum += bytes[i] as u32;
+        i += 1;
+    }
+    sum
+}
+
+pub fn double(x: &u32) -> u32 {
+    *x * 2
+}
+
+extern "C" {
+    fn host_answer(question: u32) -> u32;
+    fn host_record(value: u32);
+}
+
+pub fn ask(question: u32) -> u32 {
+    unsafe {
+        host_record(question);
+        host_answer(question) + 1
+    }
+}
@@ -0,0 +1,58 @@
+//ignore-msvc
+use std::any::{Any, TypeId};
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn describe(value: &Any) -> String {
+    if let Some(i) = value.downcast_ref::<i32>() {
+        format!("i32 {}", i)
+    } else if let Some(s) = value.downcast_ref::<&'static str>() {
+        format!("str {}", s)
+    } else if let Some(p) = value.downcast_ref::<Point>() {
+        format!("point {}/{}", p.x, p.y)
+    } else {
+        "unknown".to_string()
+    }
+}
+
+fn main() {
+    let values: Vec<Box<Any>> = vec![
+        Box::new(42i32),
+        Box::new("hello"),
+        Box::new(Point { x: 1, y: 2 }),
+        Box::new(42u32),
+    ];
+    let descriptions: Vec<String> = values.iter().map(|v| describe(&**v)).collect();
+    assert_eq!(descriptions, ["i32 42", "str hello", "point 1/2", "unknown"]);
+
+    assert!(values[0].is::<i32>());
+    assert!(!values[0].is::<u32>());
+    assert!(values[3].is::<u32>());
+
+    let mut values = values;
+    if let Some(p) = values[2].downcast_mut::<Point>() {
+        p.x = 10;
+    }
+    let point = values.remove(2).downcast::<Point>().unwrap();
+    assert_eq!(*point, Point { x: 10, y: 2 });
+    let not_a_point = values.remove(0).downcast::<Point>();
+    assert!(not_a_point.is_err());
+    assert!(not_a_point.unwrap_err().is::<i32>());
+
+    // `TypeId`s compare and hash by type
+    let ids: HashSet<TypeId> = [
+        TypeId::of::<i32>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<i32>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<Point>(),
+    ].iter().cloned().collect();
+    assert_eq!(ids.len(), 3);
+    assert!(ids.contains(&TypeId::of::<Point>()));
+    assert!(!ids.contains(&TypeId::of::<&'static str>()));
+}
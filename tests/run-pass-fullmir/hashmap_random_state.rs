@@ -0,0 +1,17 @@
+//ignore-msvc
+// `HashMap::new` seeds its hasher with random keys from the operating system
+use std::collections::{HashMap, HashSet};
+
+fn main() {
+    let mut map = HashMap::new();
+    for i in 0..20 {
+        map.insert(i, i * 2);
+    }
+    assert_eq!(map.len(), 20);
+    assert_eq!(map[&7], 14);
+    assert_eq!(map.remove(&7), Some(14));
+    assert!(!map.contains_key(&7));
+
+    let set: HashSet<_> = map.values().cloned().collect();
+    assert_eq!(set.len(), 19);
+}
@@ -0,0 +1,16 @@
+//ignore-msvc
+
+struct PrintOnDrop(&'static str);
+
+impl Drop for PrintOnDrop {
+    fn drop(&mut self) {
+        println!("{}", self.0);
+    }
+}
+
+fn main() {
+    let _guard = PrintOnDrop("destructors must not run on exit");
+    let _leaked = Box::new(42);
+    println!("before exit");
+    std::process::exit(0);
+}
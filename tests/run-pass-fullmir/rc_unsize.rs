@@ -0,0 +1,34 @@
+// Unsizing smart pointers coerces the pointer nested inside their fields
+
+use std::rc::Rc;
+use std::sync::Arc;
+use std::fmt::Debug;
+
+trait Shape {
+    fn area(&self) -> u32;
+}
+
+struct Square(u32);
+
+impl Shape for Square {
+    fn area(&self) -> u32 { self.0 * self.0 }
+}
+
+fn main() {
+    let slice: Rc<[u8]> = Rc::new([1, 2, 3]);
+    assert_eq!(slice.len(), 3);
+    assert_eq!(slice[2], 3);
+    let other = slice.clone();
+    assert_eq!(Rc::strong_count(&other), 2);
+    drop(slice);
+    assert_eq!(&other[..], &[1, 2, 3]);
+
+    let shape: Rc<Shape> = Rc::new(Square(4));
+    assert_eq!(shape.area(), 16);
+
+    let shared: Arc<Debug> = Arc::new(42u8);
+    assert_eq!(format!("{:?}", shared), "42");
+
+    let boxes: [Box<Shape>; 2] = [Box::new(Square(2)), Box::new(Square(3))];
+    assert_eq!(boxes.iter().map(|s| s.area()).sum::<u32>(), 13);
+}
@@ -0,0 +1,12 @@
+//ignore-msvc
+use std::io::Write;
+
+fn main() {
+    println!("to stdout");
+    eprintln!("to stderr");
+    print!("no newline, ");
+    std::io::stdout().flush().unwrap();
+    println!("{}", 42);
+    let stderr = std::io::stderr();
+    writeln!(stderr.lock(), "locked stderr").unwrap();
+}
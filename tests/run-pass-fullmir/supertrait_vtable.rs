@@ -0,0 +1,76 @@
+//ignore-msvc
+trait Named {
+    fn name(&self) -> String;
+    fn id(&self) -> u32 { 0 }
+}
+
+trait Counter {
+    fn count(&self) -> u32;
+}
+
+// Two supertraits, so the second one's methods do not start right after the header
+trait Animal: Named + Counter {
+    fn legs(&self) -> u32;
+    fn as_named(&self) -> &Named;
+    fn describe(&self) -> String {
+        format!("{} ({}, {} legs, count {})", self.name(), self.id(), self.legs(), self.count())
+    }
+}
+
+trait Pet: Animal {
+    fn owner(&self) -> &'static str;
+}
+
+struct Dog;
+struct Spider(u32);
+
+impl Named for Dog {
+    fn name(&self) -> String { "dog".to_string() }
+    fn id(&self) -> u32 { 7 }
+}
+impl Counter for Dog {
+    fn count(&self) -> u32 { 1 }
+}
+impl Animal for Dog {
+    fn legs(&self) -> u32 { 4 }
+    fn as_named(&self) -> &Named { self }
+}
+impl Pet for Dog {
+    fn owner(&self) -> &'static str { "alice" }
+}
+
+impl Named for Spider {
+    fn name(&self) -> String { "spider".to_string() }
+}
+impl Counter for Spider {
+    fn count(&self) -> u32 { self.0 }
+}
+impl Animal for Spider {
+    fn legs(&self) -> u32 { 8 }
+    fn as_named(&self) -> &Named { self }
+    fn describe(&self) -> String { format!("{} spiders", self.count()) }
+}
+
+fn main() {
+    let animals: Vec<Box<Animal>> = vec![Box::new(Dog), Box::new(Spider(3))];
+    assert_eq!(animals[0].name(), "dog");
+    assert_eq!(animals[0].id(), 7);
+    assert_eq!(animals[0].count(), 1);
+    assert_eq!(animals[0].describe(), "dog (7, 4 legs, count 1)");
+    assert_eq!(animals[1].id(), 0);
+    assert_eq!(animals[1].legs(), 8);
+    assert_eq!(animals[1].describe(), "3 spiders");
+
+    // "Upcast" through a method, then call through the supertrait's own vtable
+    let named: Vec<&Named> = animals.iter().map(|a| a.as_named()).collect();
+    assert_eq!(named[0].name(), "dog");
+    assert_eq!(named[1].name(), "spider");
+
+    // Methods of the super-supertraits through a subtrait object
+    let pet: &Pet = &Dog;
+    assert_eq!(pet.owner(), "alice");
+    assert_eq!(pet.legs(), 4);
+    assert_eq!(pet.count(), 1);
+    assert_eq!(pet.name(), "dog");
+    assert_eq!(pet.as_named().id(), 7);
+}
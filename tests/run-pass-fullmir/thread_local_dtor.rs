@@ -0,0 +1,23 @@
+//ignore-msvc
+use std::cell::RefCell;
+
+struct PrintOnDrop(&'static str);
+
+impl Drop for PrintOnDrop {
+    fn drop(&mut self) {
+        println!("dropping {}", self.0);
+    }
+}
+
+thread_local! {
+    static COUNTER: RefCell<u32> = RefCell::new(0);
+    static GUARD: PrintOnDrop = PrintOnDrop("guard");
+}
+
+fn main() {
+    for _ in 0..3 {
+        COUNTER.with(|c| *c.borrow_mut() += 1);
+    }
+    COUNTER.with(|c| assert_eq!(*c.borrow(), 3));
+    GUARD.with(|g| println!("using {}", g.0));
+}
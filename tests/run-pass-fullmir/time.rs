@@ -0,0 +1,22 @@
+//ignore-msvc
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+fn busy_work() -> u64 {
+    let mut sum = 0;
+    for i in 0..100 {
+        sum += i;
+    }
+    sum
+}
+
+fn main() {
+    let start = Instant::now();
+    assert_eq!(busy_work(), 4950);
+    let elapsed = start.elapsed();
+    // The clock advances while the program runs
+    assert!(Instant::now() > start);
+    assert!(start.elapsed() >= elapsed);
+
+    let now = SystemTime::now();
+    assert!(now.duration_since(UNIX_EPOCH).is_ok());
+}
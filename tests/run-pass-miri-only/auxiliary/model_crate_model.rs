@@ -0,0 +1,5 @@
+// The model of the foreign functions of `model_crate.rs`
+
+pub extern "C" fn answer(question: u32) -> u32 {
+    question * 2
+}
@@ -0,0 +1,21 @@
+//ignore-windows
+#![feature(libc, custom_attribute, attr_literals)]
+#![miri(memory_size=100000)]
+
+extern crate libc;
+
+// Like the global allocator, the C allocator returns null when it runs out of memory
+fn main() {
+    unsafe {
+        assert!(libc::malloc(200000).is_null());
+        assert!(libc::calloc(200000, 1).is_null());
+
+        let ptr = libc::malloc(16) as *mut u8;
+        assert!(!ptr.is_null());
+        *ptr = 42;
+        assert!(libc::realloc(ptr as *mut libc::c_void, 200000).is_null());
+        // A failed `realloc` leaves the original allocation alone
+        assert_eq!(*ptr, 42);
+        libc::free(ptr as *mut libc::c_void);
+    }
+}
@@ -0,0 +1,24 @@
+//ignore-msvc
+// compile-flags: --check-consts
+
+#![allow(dead_code)]
+
+const SIZE: usize = 3 * 4;
+static TABLE: [u8; SIZE] = [1; SIZE];
+static FIRST: &u8 = &TABLE[0];
+
+enum Flags {
+    A = 1 << 3,
+    B = (SIZE as isize) - 1,
+}
+
+struct Wrapper<T>(T);
+
+impl<T> Wrapper<T> {
+    // Not checked, this needs a concrete `T`
+    const EMPTY: Option<T> = None;
+}
+
+fn main() {
+    panic!("`--check-consts` does not run the program");
+}
@@ -0,0 +1,13 @@
+// compile-flags: -Zmiri-disable-value-checks
+
+mod safe {
+    pub(crate) fn len(s: &str) -> usize {
+        s.len()
+    }
+}
+
+fn main() {
+    let bytes = [b'a', 0xff, b'b'];
+    let s = unsafe { std::mem::transmute::<&[u8], &str>(&bytes) };
+    assert_eq!(safe::len(s), 3);
+}
@@ -0,0 +1,10 @@
+//ignore-msvc
+// compile-flags: -- hello world
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    assert_eq!(args, ["hello", "world"]);
+    for arg in std::env::args().skip(1) {
+        println!("{}", arg);
+    }
+}
@@ -0,0 +1,26 @@
+//ignore-windows
+// compile-flags: -Zmiri-allow-fs
+
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+
+fn main() {
+    let path = "target/miri_fs_test.txt";
+    let contents = b"Hello, files!\n";
+    {
+        let mut file = File::create(path).unwrap();
+        file.write_all(contents).unwrap();
+    }
+    assert_eq!(fs::metadata(path).unwrap().len(), contents.len() as u64);
+
+    let mut file = File::open(path).unwrap();
+    assert!(file.metadata().unwrap().is_file());
+    let mut read = Vec::new();
+    file.read_to_end(&mut read).unwrap();
+    assert_eq!(&read[..], &contents[..]);
+    drop(file);
+
+    fs::remove_file(path).unwrap();
+    // Errors are reported through `errno`
+    assert_eq!(File::open(path).unwrap_err().kind(), ErrorKind::NotFound);
+}
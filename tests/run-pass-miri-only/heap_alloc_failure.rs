@@ -0,0 +1,22 @@
+//ignore-msvc
+#![feature(allocator_api, custom_attribute, attr_literals)]
+#![miri(memory_size=100000)]
+
+use std::heap::{Alloc, Heap, Layout};
+
+// Running out of memory is reported to the program's allocator calls, which can recover from it
+fn main() {
+    let layout = Layout::from_size_align(200000, 8).unwrap();
+    unsafe {
+        assert!(Heap.alloc(layout.clone()).is_err());
+        assert!(Heap.alloc_zeroed(layout.clone()).is_err());
+
+        let small = Layout::from_size_align(16, 8).unwrap();
+        let ptr = Heap.alloc(small.clone()).unwrap();
+        *ptr = 42;
+        assert!(Heap.realloc(ptr, small.clone(), layout).is_err());
+        // A failed `realloc` leaves the original allocation alone
+        assert_eq!(*ptr, 42);
+        Heap.dealloc(ptr, small);
+    }
+}
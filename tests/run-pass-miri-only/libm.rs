@@ -0,0 +1,22 @@
+// The libm functions libstd's float methods call
+
+use std::{f32, f64};
+
+extern "C" {
+    fn fdimf(a: f32, b: f32) -> f32;
+    fn fdim(a: f64, b: f64) -> f64;
+    fn hypot(a: f64, b: f64) -> f64;
+    fn cbrtf(a: f32) -> f32;
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(fdimf(5.0, 3.0), 2.0);
+        assert_eq!(fdimf(3.0, 5.0), 0.0);
+        assert!(fdimf(f32::NAN, 1.0).is_nan());
+        assert_eq!(fdim(-1.0, -4.0), 3.0);
+        assert!(fdim(1.0, f64::NAN).is_nan());
+        assert_eq!(hypot(3.0, 4.0), 5.0);
+        assert_eq!(cbrtf(27.0), 3.0);
+    }
+}
@@ -0,0 +1,15 @@
+// aux-build:model_crate_model.rs
+// compile-flags: -Zmiri-model-crate=model_crate_model
+
+// ignore-cross-compile
+
+extern crate model_crate_model;
+
+// There is no library with this function, its model runs instead
+extern "C" {
+    fn answer(question: u32) -> u32;
+}
+
+fn main() {
+    assert_eq!(unsafe { answer(21) }, 42);
+}
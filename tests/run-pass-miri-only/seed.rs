@@ -0,0 +1,35 @@
+//ignore-windows
+//ignore-macos
+// compile-flags: -Zmiri-seed=42
+
+#![feature(libc)]
+extern crate libc;
+
+// The generator miri uses for random bytes
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn getrandom(buf: &mut [u8]) {
+    let ret = unsafe { libc::syscall(libc::SYS_getrandom, buf.as_mut_ptr(), buf.len(), 0) };
+    assert_eq!(ret, buf.len() as libc::c_long);
+}
+
+fn main() {
+    let mut state = 42;
+    let first = splitmix64(&mut state);
+    let second = splitmix64(&mut state);
+
+    let mut buf = [0u8; 8];
+    getrandom(&mut buf);
+    assert_eq!(buf, unsafe { std::mem::transmute::<u64, [u8; 8]>(first.to_le()) });
+
+    // Partial outputs are truncated
+    let mut buf = [0u8; 3];
+    getrandom(&mut buf);
+    assert_eq!(buf, [second as u8, (second >> 8) as u8, (second >> 16) as u8]);
+}
@@ -0,0 +1,8 @@
+//ignore-msvc
+// compile-flags: -Zmiri-short-writes
+
+// Every write to stdout only writes half of the buffer, `println!` has to retry
+fn main() {
+    println!("this line needs several calls to `write`");
+    println!("{}", "0123456789".repeat(10));
+}
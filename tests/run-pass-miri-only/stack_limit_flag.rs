@@ -0,0 +1,10 @@
+// compile-flags: -Zmiri-stack-limit=1000
+
+// Deeper than the default stack limit allows
+fn depth(n: u64) -> u64 {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+
+fn main() {
+    assert_eq!(depth(500), 500);
+}
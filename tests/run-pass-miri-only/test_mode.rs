@@ -0,0 +1,18 @@
+// compile-flags: --test
+
+#[test]
+fn adds() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[test]
+#[ignore]
+fn ignored() {
+    panic!("ignored tests do not run");
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn panics() {
+    panic!("index out of range");
+}
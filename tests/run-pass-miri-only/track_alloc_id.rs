@@ -0,0 +1,9 @@
+// compile-flags: -Zmiri-track-alloc-id=1,2,3
+
+// Tracking allocations only reports on them, it does not change what the program does
+fn main() {
+    let b = Box::new(42u32);
+    let mut v = vec![1u8, 2, 3];
+    v.push(*b as u8);
+    assert_eq!(v, [1, 2, 3, 42]);
+}
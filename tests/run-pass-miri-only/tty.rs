@@ -0,0 +1,29 @@
+//ignore-windows
+// compile-flags: -Zmiri-tty=100x40
+
+#![feature(libc)]
+extern crate libc;
+
+use std::io;
+
+fn last_error() -> Option<i32> {
+    io::Error::last_os_error().raw_os_error()
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(libc::isatty(1), 1);
+        assert_eq!(libc::isatty(3), 0);
+        assert_eq!(last_error(), Some(libc::ENOTTY));
+
+        let mut size: libc::winsize = std::mem::zeroed();
+        assert_eq!(libc::ioctl(1, libc::TIOCGWINSZ, &mut size), 0);
+        assert_eq!(size.ws_col, 100);
+        assert_eq!(size.ws_row, 40);
+        assert_eq!(libc::ioctl(3, libc::TIOCGWINSZ, &mut size), -1);
+        assert_eq!(last_error(), Some(libc::ENOTTY));
+
+        assert_eq!(libc::fcntl(42, libc::F_GETFD), -1);
+        assert_eq!(last_error(), Some(libc::EBADF));
+    }
+}
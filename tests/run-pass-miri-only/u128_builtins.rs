@@ -0,0 +1,30 @@
+//ignore-msvc
+#![feature(i128_type)]
+#![allow(improper_ctypes)]
+
+// The functions targets without 128 bit arithmetic instructions call for it
+extern "C" {
+    fn __udivti3(a: u128, b: u128) -> u128;
+    fn __umodti3(a: u128, b: u128) -> u128;
+    fn __divti3(a: i128, b: i128) -> i128;
+    fn __modti3(a: i128, b: i128) -> i128;
+    fn __multi3(a: i128, b: i128) -> i128;
+    fn __muloti4(a: i128, b: i128, overflow: &mut i32) -> i128;
+}
+
+fn main() {
+    let big = 1u128 << 100;
+    unsafe {
+        assert_eq!(__udivti3(big + 7, 1 << 64), 1 << 36);
+        assert_eq!(__umodti3(big + 7, 1 << 64), 7);
+        assert_eq!(__divti3(-(big as i128) - 7, 1 << 64), -(1 << 36));
+        assert_eq!(__modti3(-(big as i128) - 7, 1 << 64), -7);
+        assert_eq!(__multi3(-3, 1 << 100), -3 << 100);
+
+        let mut overflow = 0;
+        assert_eq!(__muloti4(1 << 62, 1 << 62, &mut overflow), 1 << 124);
+        assert_eq!(overflow, 0);
+        __muloti4(i128::max_value(), 2, &mut overflow);
+        assert_eq!(overflow, 1);
+    }
+}
@@ -0,0 +1,34 @@
+// compile-flags: -Zmiri-virtual-addresses
+
+fn main() {
+    let x = 42u32;
+    let y = 7u32;
+    let p = &x as *const u32;
+    let q = &y as *const u32;
+
+    // Alignment checks and hashing work on the address
+    let addr = p as usize;
+    assert_eq!(addr % 4, 0);
+    assert_ne!(addr.wrapping_mul(31), 0);
+
+    // Tagging the low bits keeps the pointer usable
+    let tagged = addr | 1;
+    assert_eq!(tagged & 1, 1);
+    let untagged = (tagged & !1) as *const u32;
+    assert_eq!(unsafe { *untagged }, 42);
+
+    // Pointers into different allocations have a consistent order
+    assert!(p != q);
+    assert!((p < q) != (q < p));
+    assert_eq!(p < q, (p as usize) < (q as usize));
+    assert!(p as usize != 0);
+
+    // A dangling pointer keeps the address it had before its allocation was freed
+    let (dangling, dangling_addr) = {
+        let z = 5u8;
+        let r = &z as *const u8;
+        (r, r as usize)
+    };
+    assert_eq!(dangling as usize, dangling_addr);
+    assert!(dangling_addr != addr);
+}
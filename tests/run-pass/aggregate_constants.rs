@@ -0,0 +1,37 @@
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Circle(Point, u32),
+    Empty,
+}
+
+const ORIGIN: Point = Point { x: 0, y: 0 };
+const PAIR: (u8, u64, bool) = (1, 2, true);
+const ARRAY: [Point; 2] = [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+const REPEAT: [u16; 5] = [7; 5];
+const SHAPES: [Shape; 2] = [Shape::Circle(Point { x: 1, y: 1 }, 5), Shape::Empty];
+const NESTED: ((u8, [u32; 2]), Option<&'static str>) = ((1, [2, 3]), Some("four"));
+static STATIC_ARRAY: [(char, i8); 2] = [('a', -1), ('b', -2)];
+
+fn double(x: u32) -> u32 { x * 2 }
+const FUNCTION: fn(u32) -> u32 = double;
+const FUNCTIONS: [fn(u32) -> u32; 2] = [double, double];
+
+fn main() {
+    assert_eq!(ORIGIN, Point { x: 0, y: 0 });
+    assert_eq!(PAIR, (1, 2, true));
+    assert_eq!(ARRAY[1], Point { x: 3, y: 4 });
+    assert_eq!(REPEAT, [7, 7, 7, 7, 7]);
+    assert_eq!(SHAPES[0], Shape::Circle(Point { x: 1, y: 1 }, 5));
+    assert_eq!(SHAPES[1], Shape::Empty);
+    assert_eq!((NESTED.0).1[1], 3);
+    assert_eq!(NESTED.1, Some("four"));
+    assert_eq!(STATIC_ARRAY[1], ('b', -2));
+    assert_eq!(FUNCTION(21), 42);
+    assert_eq!(FUNCTIONS[1](4), 8);
+}
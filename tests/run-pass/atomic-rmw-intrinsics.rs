@@ -0,0 +1,45 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics;
+use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
+
+fn main() {
+    unsafe {
+        let mut x: u32 = 0b1100;
+        assert_eq!(intrinsics::atomic_nand(&mut x, 0b1010), 0b1100);
+        assert_eq!(x, !0b1000);
+
+        let mut x: i32 = -5;
+        assert_eq!(intrinsics::atomic_max_acq(&mut x, -7), -5);
+        assert_eq!(x, -5);
+        assert_eq!(intrinsics::atomic_max_rel(&mut x, 3), -5);
+        assert_eq!(x, 3);
+        assert_eq!(intrinsics::atomic_min_relaxed(&mut x, -9), 3);
+        assert_eq!(x, -9);
+
+        let mut x: u8 = 200;
+        assert_eq!(intrinsics::atomic_umax(&mut x, 100), 200);
+        assert_eq!(x, 200);
+        assert_eq!(intrinsics::atomic_umin_acqrel(&mut x, 100), 200);
+        assert_eq!(x, 100);
+
+        let mut x: u64 = 1;
+        intrinsics::atomic_store_unordered(&mut x, 2);
+        assert_eq!(intrinsics::atomic_load_unordered(&x), 2);
+
+        intrinsics::atomic_fence();
+        intrinsics::atomic_fence_rel();
+        intrinsics::atomic_fence_acqrel();
+        intrinsics::atomic_singlethreadfence();
+    }
+
+    let counter = AtomicUsize::new(5);
+    assert_eq!(counter.fetch_add(3, Ordering::SeqCst), 5);
+    assert_eq!(counter.fetch_sub(1, Ordering::Release), 8);
+    assert_eq!(counter.swap(42, Ordering::AcqRel), 7);
+    assert_eq!(counter.load(Ordering::Acquire), 42);
+
+    let flag = AtomicBool::new(true);
+    assert_eq!(flag.fetch_nand(true, Ordering::SeqCst), true);
+    assert_eq!(flag.load(Ordering::SeqCst), false);
+}
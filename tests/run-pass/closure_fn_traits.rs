@@ -0,0 +1,48 @@
+// Calls through the `Fn*` traits go through shims that untuple the arguments
+
+fn call_once<F: FnOnce(Vec<u8>, u8) -> usize>(f: F) -> usize {
+    f(vec![1, 2, 3], 2)
+}
+
+fn call_mut<F: FnMut(u32)>(mut f: F) {
+    f(1);
+    f(2);
+}
+
+fn call_fn<F: Fn() -> i32>(f: &F) -> i32 {
+    f() + f()
+}
+
+// A `Fn` closure used where only `FnOnce` is needed
+fn fn_as_once<F: FnOnce() -> i32>(f: F) -> i32 {
+    f()
+}
+
+fn main() {
+    let suffix = vec![4u8, 5, 6];
+    assert_eq!(call_once(move |s, n| s.len() + suffix.len() + n as usize), 8);
+
+    let mut sum = 0;
+    call_mut(|x| sum += x);
+    assert_eq!(sum, 3);
+
+    let base = 20;
+    let f = || base + 1;
+    assert_eq!(call_fn(&f), 42);
+    assert_eq!(fn_as_once(f), 21);
+
+    // Trait objects
+    let mut count = 0;
+    {
+        let mut inc: Box<FnMut()> = Box::new(|| count += 1);
+        inc();
+        inc();
+    }
+    assert_eq!(count, 2);
+    let boxed: Box<Fn(i32, i32) -> i32> = Box::new(|a: i32, b: i32| a * b);
+    assert_eq!(boxed(6, 7), 42);
+
+    // Iterator adapters pass closures around a lot
+    let v: Vec<u32> = (1..6).map(|x| x * x).filter(|x| x % 2 == 1).collect();
+    assert_eq!(v, [1, 9, 25]);
+}
@@ -0,0 +1,46 @@
+// Calls through `Fn` trait objects dispatch through the vtable and pass the data pointer
+// as the receiver
+
+struct Callbacks<'a> {
+    on_value: Vec<Box<FnMut(u32) + 'a>>,
+}
+
+impl<'a> Callbacks<'a> {
+    fn fire(&mut self, v: u32) {
+        for callback in self.on_value.iter_mut() {
+            callback(v);
+        }
+    }
+}
+
+fn apply(f: &Fn(u64, u64) -> u64, a: u64, b: u64) -> u64 {
+    f(a, b)
+}
+
+fn apply_mut(f: &mut FnMut() -> u8) -> u8 {
+    f() + f()
+}
+
+fn main() {
+    let mut total = 0;
+    let mut count = 0;
+    {
+        let mut callbacks = Callbacks { on_value: Vec::new() };
+        callbacks.on_value.push(Box::new(|v: u32| total += v));
+        callbacks.on_value.push(Box::new(|_: u32| count += 1));
+        callbacks.fire(3);
+        callbacks.fire(4);
+    }
+    assert_eq!((total, count), (7, 2));
+
+    let offset = 100;
+    assert_eq!(apply(&|a: u64, b: u64| a * b + offset, 6, 7), 142);
+
+    let mut state = 1u8;
+    assert_eq!(apply_mut(&mut || { state *= 2; state }), 6);
+
+    // Large captures are behind the data pointer, not in the fat pointer
+    let big = [1u64, 2, 3, 4, 5, 6, 7, 8];
+    let sum: Box<Fn() -> u64> = Box::new(move || big.iter().sum());
+    assert_eq!(sum(), 36);
+}
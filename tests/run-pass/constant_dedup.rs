@@ -0,0 +1,20 @@
+// Constants are evaluated once, every use of them points to the same allocation
+
+fn greeting() -> &'static str {
+    "hello"
+}
+
+fn primes() -> &'static [u32; 4] {
+    &[2, 3, 5, 7]
+}
+
+fn main() {
+    let s = greeting();
+    let p = primes();
+    for _ in 0..10 {
+        assert_eq!(greeting().as_ptr(), s.as_ptr());
+        assert_eq!(primes() as *const _, p as *const _);
+    }
+    assert_eq!(s, "hello");
+    assert_eq!(p[3], 7);
+}
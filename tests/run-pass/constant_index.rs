@@ -0,0 +1,34 @@
+#![feature(advanced_slice_patterns)]
+#![feature(slice_patterns)]
+
+fn ends(s: &[u16]) -> Option<(u16, u16)> {
+    match *s {
+        // On slices, `b` is found by counting from the end
+        [a, .., b] => Some((a, b)),
+        [a] => Some((a, a)),
+        [] => None,
+    }
+}
+
+fn second_to_last(s: &[u8]) -> Option<u8> {
+    match *s {
+        [.., x, _] => Some(x),
+        _ => None,
+    }
+}
+
+fn main() {
+    let arr = [1u32, 2, 3, 4, 5];
+    let [a, .., e] = arr;
+    assert_eq!((a, e), (1, 5));
+    let [_, b, _, d, _] = arr;
+    assert_eq!((b, d), (2, 4));
+
+    assert_eq!(ends(&[7, 8, 9]), Some((7, 9)));
+    assert_eq!(ends(&[7, 8]), Some((7, 8)));
+    assert_eq!(ends(&[7]), Some((7, 7)));
+    assert_eq!(ends(&[]), None);
+
+    assert_eq!(second_to_last(&[1, 2, 3, 4]), Some(3));
+    assert_eq!(second_to_last(&[1]), None);
+}
@@ -0,0 +1,52 @@
+#![feature(i128_type, core_intrinsics)]
+
+use std::intrinsics;
+
+#[derive(Clone, Copy)]
+struct Pair {
+    small: u8,
+    big: i128,
+}
+
+fn id<T>(x: T) -> T { x }
+
+fn main() {
+    // Values going through memory keep all 128 bits
+    let arr = [u128::max_value(), 1 << 100, 0];
+    let r = &arr[1];
+    assert_eq!(*r >> 100, 1);
+    assert_eq!(id(arr)[0], !0);
+
+    let mut pair = Pair { small: 1, big: -(1 << 90) };
+    let p = &mut pair.big as *mut i128;
+    unsafe { *p -= 1; }
+    assert_eq!(id(pair).big, -(1 << 90) - 1);
+    assert_eq!(pair.small, 1);
+
+    // Byte representation
+    let bytes: [u8; 16] = unsafe { std::mem::transmute(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10u128.to_le()) };
+    assert_eq!(bytes[0], 0x10);
+    assert_eq!(bytes[15], 0x01);
+    let back: u128 = unsafe { std::mem::transmute(bytes) };
+    assert_eq!(u128::from_le(back), 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10);
+
+    // Casts to and from the smaller types
+    assert_eq!(-1i8 as i128, -1);
+    assert_eq!(-1i64 as u128, u128::max_value());
+    assert_eq!((1u128 << 64) as u64, 0);
+    assert_eq!(i128::min_value() as i64, 0);
+
+    unsafe {
+        assert_eq!(intrinsics::ctpop(u128::max_value()), 128);
+        assert_eq!(intrinsics::ctlz(1u128), 127);
+        assert_eq!(intrinsics::cttz(1i128 << 127), 127);
+        assert_eq!(intrinsics::bswap(1u128), 1 << 120);
+    }
+
+    assert_eq!(i128::max_value().checked_add(1), None);
+    assert_eq!(u128::max_value().wrapping_mul(2), u128::max_value() - 1);
+    assert_eq!((-7i128).overflowing_sub(i128::max_value()), (i128::max_value() - 5, true));
+    assert_eq!((-7i128) / 2, -3);
+    assert_eq!((-7i128) % 2, -1);
+    assert_eq!(u128::max_value() / 3, 0x5555_5555_5555_5555_5555_5555_5555_5555);
+}
@@ -0,0 +1,27 @@
+// The public integer methods are thin wrappers around the bit-manipulation
+// intrinsics, make sure they work for every width and signedness.
+
+fn main() {
+    assert_eq!(1u8.leading_zeros(), 7);
+    assert_eq!((-1i16).leading_zeros(), 0);
+    assert_eq!(0u32.leading_zeros(), 32);
+    assert_eq!(0x100u64.leading_zeros(), 55);
+    assert_eq!(1usize.leading_zeros() as usize, std::mem::size_of::<usize>() * 8 - 1);
+
+    assert_eq!(0x80u8.trailing_zeros(), 7);
+    assert_eq!(0i32.trailing_zeros(), 32);
+    assert_eq!(i64::min_value().trailing_zeros(), 63);
+
+    assert_eq!(0xF0F0u16.count_ones(), 8);
+    assert_eq!((-1i64).count_ones(), 64);
+    assert_eq!(0u8.count_zeros(), 8);
+
+    assert_eq!(0x1234u16.swap_bytes(), 0x3412);
+    assert_eq!(0x12345678i32.swap_bytes(), 0x78563412);
+    assert_eq!(0x80i16.swap_bytes(), i16::min_value());
+    assert_eq!(0x0102030405060708u64.to_be().to_be(), 0x0102030405060708);
+
+    assert_eq!(5u32.next_power_of_two(), 8);
+    assert!(64u64.is_power_of_two());
+    assert!(!65u64.is_power_of_two());
+}
@@ -8,6 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#![feature(core_intrinsics)]
+
 macro_rules! assert_approx_eq {
     ($a:expr, $b:expr) => ({
         let (a, b) = (&$a, &$b);
@@ -64,4 +66,19 @@ pub fn main() {
 
     assert_approx_eq!(0.1f32.trunc(), 0.0f32);
     assert_approx_eq!((-0.1f64).trunc(), 0.0f64);
+
+    assert_approx_eq!(2.5f32.round(), 3.0f32);
+    assert_approx_eq!((-2.5f64).round(), -3.0f64);
+
+    unsafe {
+        use std::intrinsics;
+        assert_approx_eq!(intrinsics::rintf32(2.5), 2.0f32);
+        assert_approx_eq!(intrinsics::rintf64(3.5), 4.0f64);
+        assert_approx_eq!(intrinsics::nearbyintf64(-2.5), -2.0f64);
+        assert_approx_eq!(intrinsics::nearbyintf32(2.4), 2.0f32);
+        assert_approx_eq!(intrinsics::copysignf32(3.0, -0.0), -3.0f32);
+        assert_approx_eq!(intrinsics::copysignf64(-3.0, 1.0), 3.0f64);
+        assert_approx_eq!(intrinsics::minnumf32(1.0, f32::NAN), 1.0f32);
+        assert_approx_eq!(intrinsics::maxnumf64(f64::NAN, -1.0), -1.0f64);
+    }
 }
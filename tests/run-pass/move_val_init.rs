@@ -0,0 +1,35 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics;
+
+struct Pair {
+    a: u8,
+    b: Box<u64>,
+}
+
+fn main() {
+    unsafe {
+        let mut x: (u16, u32) = intrinsics::init();
+        assert_eq!(x, (0, 0));
+        intrinsics::move_val_init(&mut x, (1, 2));
+        assert_eq!(x, (1, 2));
+
+        let mut arr: [u8; 5] = intrinsics::init();
+        assert_eq!(arr, [0; 5]);
+        intrinsics::move_val_init(&mut arr[2], 7);
+        assert_eq!(arr, [0, 0, 7, 0, 0]);
+
+        let mut p: Pair = intrinsics::uninit();
+        intrinsics::move_val_init(&mut p, Pair { a: 3, b: Box::new(42) });
+        assert_eq!(p.a, 3);
+        assert_eq!(*p.b, 42);
+
+        let raw = Box::into_raw(Box::new(Pair { a: 0, b: Box::new(0) }));
+        // Overwriting with `move_val_init` must not drop the old value
+        let old = std::ptr::read(raw);
+        intrinsics::move_val_init(raw, Pair { a: 5, b: Box::new(6) });
+        let new = Box::from_raw(raw);
+        assert_eq!((old.a, *old.b), (0, 0));
+        assert_eq!((new.a, *new.b), (5, 6));
+    }
+}
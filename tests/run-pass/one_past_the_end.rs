@@ -0,0 +1,24 @@
+// Pointers one past the end of an allocation can be created and compared, just not dereferenced
+
+fn main() {
+    let v = [1u32, 2, 3];
+    let start = v.as_ptr();
+    let end = unsafe { start.offset(v.len() as isize) };
+    assert!(end > start);
+    assert!(end != start);
+
+    // Walk the array like a C loop would
+    let mut p = start;
+    let mut sum = 0;
+    while p != end {
+        sum += unsafe { *p };
+        p = unsafe { p.offset(1) };
+    }
+    assert_eq!(sum, 6);
+    assert!(p == end);
+
+    // Empty subslices at the end point one past the end
+    let tail = &v[3..];
+    assert_eq!(tail.as_ptr(), end);
+    assert!(tail.is_empty());
+}
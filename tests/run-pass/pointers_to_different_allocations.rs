@@ -0,0 +1,14 @@
+// Pointers into different allocations are never equal, and have a consistent order
+
+fn main() {
+    let x: *const u8 = &1;
+    let y: *const u8 = &2;
+    assert!(x != y);
+    assert!(!(x == y));
+    assert!((x < y) != (y < x));
+    assert_eq!(x < y, y > x);
+    assert_eq!(x <= y, x < y);
+    let mut ptrs = [y, x];
+    ptrs.sort();
+    assert!(ptrs[0] < ptrs[1]);
+}
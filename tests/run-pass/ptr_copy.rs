@@ -0,0 +1,41 @@
+use std::ptr;
+
+fn main() {
+    // `copy` has `memmove` semantics, overlapping ranges are fine in both directions
+    let mut data = [1u16, 2, 3, 4, 5, 6];
+    unsafe {
+        let p = data.as_mut_ptr();
+        ptr::copy(p, p.offset(2), 3);
+    }
+    assert_eq!(data, [1, 2, 1, 2, 3, 6]);
+    unsafe {
+        let p = data.as_mut_ptr();
+        ptr::copy(p.offset(3), p.offset(1), 3);
+    }
+    assert_eq!(data, [1, 2, 3, 6, 3, 6]);
+
+    // Copying pointers keeps them usable
+    let (a, b) = (13u32, 37u32);
+    let refs = [&a, &b];
+    let mut copies: [&u32; 2] = [&0, &0];
+    unsafe {
+        ptr::copy_nonoverlapping(refs.as_ptr(), copies.as_mut_ptr(), 2);
+    }
+    assert_eq!(*copies[0] + *copies[1], 50);
+
+    // ... even when copied as raw bytes
+    let mut copy: &u32 = &0;
+    unsafe {
+        ptr::copy_nonoverlapping(
+            &refs[1] as *const &u32 as *const u8,
+            &mut copy as *mut &u32 as *mut u8,
+            std::mem::size_of::<&u32>(),
+        );
+    }
+    assert_eq!(*copy, 37);
+
+    // Zero-sized copies do not need valid pointers
+    unsafe {
+        ptr::copy_nonoverlapping(8 as *const u64, 16 as *mut u64, 0);
+    }
+}
@@ -3,4 +3,17 @@ fn main() {
     let x = &v as *const i16;
     let x = unsafe { x.offset(1) };
     assert_eq!(unsafe { *x }, 2);
+
+    // One past the end is still in bounds, and we can go back from there
+    let end = unsafe { x.offset(1) };
+    assert_eq!(unsafe { *end.offset(-2) }, 1);
+
+    // Zero-sized types never leave their allocation
+    let units = [(); 4];
+    let unit = &units as *const ();
+    let _ = unsafe { unit.offset(1000) };
+
+    // Iterating a slice offsets all the way to the end
+    let sum: i16 = v.iter().sum();
+    assert_eq!(sum, 3);
 }
@@ -0,0 +1,36 @@
+use std::ptr;
+
+#[repr(packed)]
+struct Packed {
+    tag: u8,
+    value: u32,
+}
+
+fn parse_u32s(bytes: &[u8]) -> [u32; 2] {
+    // Starting at offset 1, the values are not aligned
+    let ptr = bytes[1..].as_ptr() as *const u32;
+    unsafe { [ptr::read_unaligned(ptr), ptr::read_unaligned(ptr.offset(1))] }
+}
+
+fn main() {
+    let bytes = [0u8, 1, 0, 0, 0, 2, 0, 0, 0];
+    let values = parse_u32s(&bytes);
+    assert_eq!(u32::from_le(values[0]), 1);
+    assert_eq!(u32::from_le(values[1]), 2);
+
+    let mut packed = Packed { tag: 7, value: 0 };
+    unsafe {
+        // Avoid creating a reference to the misaligned field
+        let value_ptr = (&mut packed as *mut Packed as *mut u8).offset(1) as *mut u32;
+        ptr::write_unaligned(value_ptr, 0xdead_beef);
+        assert_eq!(ptr::read_unaligned(value_ptr), 0xdead_beef);
+    }
+    let tag = packed.tag;
+    let value = packed.value;
+    assert_eq!(tag, 7);
+    assert_eq!(value, 0xdead_beef);
+
+    let mut buf = [0u8; 9];
+    unsafe { ptr::write_unaligned(buf[1..].as_mut_ptr() as *mut u64, 0x0102_0304_0506_0708u64.to_le()) };
+    assert_eq!(buf, [0, 8, 7, 6, 5, 4, 3, 2, 1]);
+}
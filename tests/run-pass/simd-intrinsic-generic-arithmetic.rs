@@ -0,0 +1,67 @@
+#![feature(repr_simd, platform_intrinsics)]
+#![allow(non_camel_case_types)]
+
+#[repr(simd)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct i32x4(i32, i32, i32, i32);
+#[repr(simd)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct u8x4(u8, u8, u8, u8);
+#[repr(simd)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct f32x4(f32, f32, f32, f32);
+#[repr(simd)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct i32x2(i32, i32);
+
+extern "platform-intrinsic" {
+    fn simd_add<T>(x: T, y: T) -> T;
+    fn simd_sub<T>(x: T, y: T) -> T;
+    fn simd_mul<T>(x: T, y: T) -> T;
+    fn simd_div<T>(x: T, y: T) -> T;
+    fn simd_shl<T>(x: T, y: T) -> T;
+    fn simd_and<T>(x: T, y: T) -> T;
+    fn simd_xor<T>(x: T, y: T) -> T;
+    fn simd_eq<T, U>(x: T, y: T) -> U;
+    fn simd_lt<T, U>(x: T, y: T) -> U;
+    fn simd_cast<T, U>(x: T) -> U;
+    fn simd_extract<T, E>(x: T, idx: u32) -> E;
+    fn simd_insert<T, E>(x: T, idx: u32, val: E) -> T;
+    fn simd_shuffle2<T, U>(x: T, y: T, idx: [u32; 2]) -> U;
+    fn simd_shuffle4<T, U>(x: T, y: T, idx: [u32; 4]) -> U;
+}
+
+fn main() {
+    let x = i32x4(1, 2, 3, 4);
+    let y = i32x4(10, -20, 30, i32::max_value());
+    unsafe {
+        assert_eq!(simd_add(x, y), i32x4(11, -18, 33, i32::min_value() + 3));
+        assert_eq!(simd_sub(y, x), i32x4(9, -22, 27, i32::max_value() - 4));
+        assert_eq!(simd_mul(x, x), i32x4(1, 4, 9, 16));
+        assert_eq!(simd_div(y, x), i32x4(10, -10, 10, i32::max_value() / 4));
+        assert_eq!(simd_shl(x, i32x4(1, 2, 3, 4)), i32x4(2, 8, 24, 64));
+        assert_eq!(simd_and(x, i32x4(1, 1, 1, 1)), i32x4(1, 0, 1, 0));
+        assert_eq!(simd_xor(x, x), i32x4(0, 0, 0, 0));
+
+        let eq: i32x4 = simd_eq(x, i32x4(1, 0, 3, 0));
+        assert_eq!(eq, i32x4(-1, 0, -1, 0));
+        let lt: i32x4 = simd_lt(y, x);
+        assert_eq!(lt, i32x4(0, -1, 0, 0));
+
+        let bytes = u8x4(250, 1, 2, 3);
+        assert_eq!(simd_add(bytes, u8x4(10, 1, 1, 1)), u8x4(4, 2, 3, 4));
+
+        let floats: f32x4 = simd_cast(x);
+        assert_eq!(floats, f32x4(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(simd_mul(floats, f32x4(0.5, 0.5, 0.5, 0.5)), f32x4(0.5, 1.0, 1.5, 2.0));
+
+        let third: i32 = simd_extract(x, 2);
+        assert_eq!(third, 3);
+        assert_eq!(simd_insert(x, 0, 42), i32x4(42, 2, 3, 4));
+
+        let low: i32x2 = simd_shuffle2(x, y, [0, 4]);
+        assert_eq!(low, i32x2(1, 10));
+        let mixed: i32x4 = simd_shuffle4(x, y, [7, 6, 1, 0]);
+        assert_eq!(mixed, i32x4(i32::max_value(), 30, 2, 1));
+    }
+}
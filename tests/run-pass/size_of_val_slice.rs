@@ -0,0 +1,34 @@
+#![feature(core_intrinsics)]
+
+use std::mem::{size_of, align_of, size_of_val, align_of_val};
+
+struct Header<T: ?Sized> {
+    len: u8,
+    data: T,
+}
+
+fn main() {
+    let arr = [1u32, 2, 3, 4, 5];
+    let slice: &[u32] = &arr[1..4];
+    assert_eq!(size_of_val(slice), 12);
+    assert_eq!(align_of_val(slice), align_of::<u32>());
+    assert_eq!(size_of_val(&slice[..0]), 0);
+
+    let s: &str = "hello";
+    assert_eq!(size_of_val(s), 5);
+    assert_eq!(align_of_val(s), 1);
+
+    // The slice length is taken from the fat pointer, the header adds its padded size
+    let header: &Header<[u16]> = &Header { len: 3, data: [1u16, 2, 3] };
+    assert_eq!(header.len, 3);
+    assert_eq!(size_of_val(header), size_of::<Header<[u16; 3]>>());
+    assert_eq!(align_of_val(header), align_of::<u16>());
+
+    unsafe {
+        assert_eq!(std::intrinsics::min_align_of_val(slice), align_of::<u32>());
+        assert_eq!(std::intrinsics::min_align_of_val(header), align_of::<u16>());
+    }
+
+    let boxed: Box<[u64]> = vec![1, 2, 3].into_boxed_slice();
+    assert_eq!(size_of_val(&*boxed), 3 * size_of::<u64>());
+}
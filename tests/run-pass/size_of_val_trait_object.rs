@@ -0,0 +1,44 @@
+use std::mem::{size_of, align_of, size_of_val, align_of_val};
+
+trait Trait {
+    fn get(&self) -> u64;
+}
+
+struct Small(u8);
+struct Aligned(u64, u64, u64);
+
+impl Trait for Small {
+    fn get(&self) -> u64 { self.0 as u64 }
+}
+
+impl Trait for Aligned {
+    fn get(&self) -> u64 { self.0 + self.1 + self.2 }
+}
+
+struct Wrapper<T: ?Sized> {
+    tag: u8,
+    inner: T,
+}
+
+fn main() {
+    let small: &Trait = &Small(1);
+    assert_eq!(size_of_val(small), 1);
+    assert_eq!(align_of_val(small), 1);
+
+    let aligned: &Trait = &Aligned(1, 2, 3);
+    assert_eq!(size_of_val(aligned), 24);
+    assert_eq!(align_of_val(aligned), align_of::<u64>());
+    assert_eq!(aligned.get(), 6);
+
+    // The unsized tail's alignment determines the offset of `inner`
+    let wrapped: &Wrapper<Trait> = &Wrapper { tag: 0, inner: Aligned(4, 5, 6) };
+    assert_eq!(wrapped.tag, 0);
+    assert_eq!(size_of_val(wrapped), size_of::<Wrapper<Aligned>>());
+    assert_eq!(align_of_val(wrapped), align_of::<u64>());
+    assert_eq!(wrapped.inner.get(), 15);
+
+    // Deallocation of the box uses the size and alignment stored in the vtable
+    let boxed: Box<Trait> = Box::new(Aligned(7, 8, 9));
+    assert_eq!(size_of_val(&*boxed), 24);
+    assert_eq!(boxed.get(), 24);
+}
@@ -0,0 +1,40 @@
+// Statics with interior mutability stay writable, and all functions see the same instance
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+struct Slot(UnsafeCell<i32>);
+
+unsafe impl Sync for Slot {}
+
+static SLOT: Slot = Slot(UnsafeCell::new(1));
+
+static mut LOG: [u8; 4] = [0; 4];
+
+fn bump() -> usize {
+    COUNTER.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+fn set_slot(value: i32) {
+    unsafe { *SLOT.0.get() = value };
+}
+
+fn log(index: usize, value: u8) {
+    unsafe { LOG[index] = value };
+}
+
+fn main() {
+    assert_eq!(bump(), 1);
+    assert_eq!(bump(), 2);
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
+
+    set_slot(42);
+    assert_eq!(unsafe { *SLOT.0.get() }, 42);
+
+    for i in 0..4 {
+        log(i, i as u8 * 2);
+    }
+    assert_eq!(unsafe { LOG }, [0, 2, 4, 6]);
+}
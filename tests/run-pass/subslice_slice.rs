@@ -0,0 +1,34 @@
+#![feature(advanced_slice_patterns)]
+#![feature(slice_patterns)]
+
+fn sum(s: &[u32]) -> u32 {
+    match *s {
+        [] => 0,
+        // `rest` is a subslice with an adjusted length
+        [x, ref rest..] => x + sum(rest),
+    }
+}
+
+fn middle(s: &[u8]) -> &[u8] {
+    match *s {
+        [_, ref mid.., _] => mid,
+        _ => &[],
+    }
+}
+
+fn main() {
+    assert_eq!(sum(&[1, 2, 3, 4]), 10);
+    assert_eq!(sum(&[]), 0);
+
+    assert_eq!(middle(&[1, 2, 3, 4]), [2, 3]);
+    assert_eq!(middle(&[1, 2]).len(), 0);
+    assert_eq!(middle(&[1]).len(), 0);
+
+    let mut arr = [1i16, 2, 3, 4, 5];
+    {
+        let [_, ref mut inner.., _] = arr;
+        inner[0] = 20;
+        inner[2] = 40;
+    }
+    assert_eq!(arr, [1, 20, 3, 40, 5]);
+}
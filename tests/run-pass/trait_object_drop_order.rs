@@ -0,0 +1,46 @@
+// Destructors behind trait objects must run, in the right order, including the drop glue of
+// the fields of the unsized value.
+
+trait Foo {
+    fn id(&self) -> usize;
+}
+
+static mut ORDER: [usize; 4] = [0; 4];
+static mut DROPS: usize = 0;
+
+struct Noisy(usize);
+
+impl Drop for Noisy {
+    fn drop(&mut self) {
+        unsafe {
+            ORDER[DROPS] = self.0;
+            DROPS += 1;
+        }
+    }
+}
+
+impl Foo for Noisy {
+    fn id(&self) -> usize { self.0 }
+}
+
+// No `Drop` impl of its own, only drop glue for its fields
+struct Pair(Noisy, Noisy);
+
+impl Foo for Pair {
+    fn id(&self) -> usize { self.0.id() + self.1.id() }
+}
+
+fn main() {
+    {
+        let a: Box<Foo> = Box::new(Noisy(1));
+        let b: Box<Foo> = Box::new(Pair(Noisy(2), Noisy(3)));
+        assert_eq!(a.id() + b.id(), 6);
+        // `b` is dropped before `a`, and its fields in declaration order
+    }
+    let c: Box<Foo> = Box::new(Noisy(4));
+    drop(c);
+    unsafe {
+        assert_eq!(DROPS, 4);
+        assert_eq!(ORDER, [2, 3, 1, 4]);
+    }
+}
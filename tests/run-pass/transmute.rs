@@ -0,0 +1,25 @@
+use std::mem::transmute;
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Pair {
+    a: u16,
+    b: u16,
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(transmute::<f32, u32>(1.0), 0x3f800000);
+        assert_eq!(transmute::<[u8; 4], u32>([1, 1, 1, 1]), 0x01010101);
+        assert_eq!(transmute::<u32, Pair>(0x12341234), Pair { a: 0x1234, b: 0x1234 });
+        assert_eq!(transmute::<Pair, [u16; 2]>(Pair { a: 1, b: 2 }), [1, 2]);
+
+        // Pointers keep pointing to their allocation
+        let x = 42u64;
+        let r: &u64 = transmute(&x as *const u64);
+        assert_eq!(*r, 42);
+
+        let c: char = transmute(0x41u32);
+        assert_eq!(c, 'A');
+    }
+}
@@ -0,0 +1,30 @@
+use std::ptr::{read_volatile, write_volatile};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Register {
+    status: u8,
+    data: u32,
+}
+
+fn main() {
+    let mut x = 0u64;
+    unsafe {
+        write_volatile(&mut x, 0xdead_beef);
+        assert_eq!(read_volatile(&x), 0xdead_beef);
+    }
+
+    let mut reg = Register { status: 0, data: 0 };
+    unsafe {
+        write_volatile(&mut reg, Register { status: 1, data: 42 });
+        write_volatile(&mut reg.status, 2);
+        assert_eq!(read_volatile(&reg), Register { status: 2, data: 42 });
+    }
+
+    // Pointers survive a volatile round trip
+    let target = 7i32;
+    let mut slot: &i32 = &0;
+    unsafe {
+        write_volatile(&mut slot, &target);
+        assert_eq!(*read_volatile(&slot), 7);
+    }
+}